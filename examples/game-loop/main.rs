@@ -21,6 +21,11 @@ sm! {
         // that starting point, based on the below defined valid transitions.
         InitialStates { Idle }
 
+        // `FinalStates` defines the states in which the machine is done, and
+        // no further transitions are expected. The macro warns about any other
+        // state without outgoing transitions, as it is most likely a mistake.
+        FinalStates { Finished }
+
         // The first defined event is called `DrainAccumulatedTime`. When this
         // event is triggered, the game loop is instructed to update the game
         // state for as many times as the "accumulated time" allows for. See the
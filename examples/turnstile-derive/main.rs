@@ -0,0 +1,136 @@
+// to run this example:
+//
+//     $ cargo run --example turnstile-derive
+//
+// see: https://en.wikipedia.org/wiki/Finite-state_machine#Example:_coin-operated_turnstile
+
+extern crate sm;
+use sm::{AsEnum, Event, InitialState, Initializer, Machine, NoneEvent, State, Transition};
+
+// Create a new `TurnStile` tuple-struct, which will be our state machine
+// object, similar to the `turnstile-manual` example. Deriving `Machine`
+// implements both the `Machine` and `Initializer` traits.
+#[derive(Debug, Eq, PartialEq, Machine)]
+pub struct TurnStile<S: State, E: Event>(S, Option<E>);
+
+// Define the `Locked` and `Unlocked` states, deriving the `State` marker
+// trait, and the `InitialState` marker trait for the `Locked` state.
+#[derive(Clone, Debug, Eq, PartialEq, State, InitialState)]
+pub struct Locked;
+
+#[derive(Clone, Debug, Eq, PartialEq, State)]
+pub struct Unlocked;
+
+// Define the `Coin` and `Push` events, deriving the `Event` marker trait.
+#[derive(Clone, Debug, Eq, PartialEq, Event)]
+pub struct Coin;
+
+#[derive(Clone, Debug, Eq, PartialEq, Event)]
+pub struct Push;
+
+// The transitions are still implemented by hand, since they describe the
+// behaviour of the machine.
+
+// `Locked -> Push -> Locked`
+impl<E: Event> Transition<Push> for TurnStile<Locked, E> {
+    type Machine = TurnStile<Locked, Push>;
+
+    fn transition(self, event: Push) -> Self::Machine {
+        TurnStile(Locked, Some(event))
+    }
+}
+
+// `Unlocked -> Push -> Locked`
+impl<E: Event> Transition<Push> for TurnStile<Unlocked, E> {
+    type Machine = TurnStile<Locked, Push>;
+
+    fn transition(self, event: Push) -> Self::Machine {
+        TurnStile(Locked, Some(event))
+    }
+}
+
+// `Locked -> Coin -> Unlocked`
+impl<E: Event> Transition<Coin> for TurnStile<Locked, E> {
+    type Machine = TurnStile<Unlocked, Coin>;
+
+    fn transition(self, event: Coin) -> Self::Machine {
+        TurnStile(Unlocked, Some(event))
+    }
+}
+
+// `Unlocked -> Coin -> Unlocked`
+impl<E: Event> Transition<Coin> for TurnStile<Unlocked, E> {
+    type Machine = TurnStile<Unlocked, Coin>;
+
+    fn transition(self, event: Coin) -> Self::Machine {
+        TurnStile(Unlocked, Some(event))
+    }
+}
+
+// Finally, we add an enum implementation to allow pattern matching against
+// possible machine states and events.
+
+pub enum Variant {
+    InitialLocked(TurnStile<Locked, NoneEvent>),
+    UnlockedByCoin(TurnStile<Unlocked, Coin>),
+    LockedByPush(TurnStile<Locked, Push>),
+}
+
+impl AsEnum for TurnStile<Locked, NoneEvent> {
+    type Enum = Variant;
+
+    fn as_enum(self) -> Self::Enum {
+        Variant::InitialLocked(self)
+    }
+}
+
+impl AsEnum for TurnStile<Unlocked, Coin> {
+    type Enum = Variant;
+
+    fn as_enum(self) -> Self::Enum {
+        Variant::UnlockedByCoin(self)
+    }
+}
+
+impl AsEnum for TurnStile<Locked, Push> {
+    type Enum = Variant;
+
+    fn as_enum(self) -> Self::Enum {
+        Variant::LockedByPush(self)
+    }
+}
+
+fn main() {
+    // non-enum usage
+    let sm = TurnStile::new(Locked);
+    assert_eq!(sm.state(), Locked);
+    assert!(sm.trigger().is_none());
+
+    let sm = sm.transition(Coin);
+    assert_eq!(sm.state(), Unlocked);
+    assert_eq!(sm.trigger().unwrap(), Coin);
+
+    // enum usage
+    let mut sm = TurnStile::new(Locked).as_enum();
+    let mut coins = 0;
+
+    loop {
+        sm = match sm {
+            Variant::InitialLocked(m) => m.transition(Push).as_enum(),
+            Variant::UnlockedByCoin(m) => {
+                coins += 1;
+
+                m.transition(Push).as_enum()
+            }
+            Variant::LockedByPush(m) => {
+                if coins == 100 {
+                    break;
+                }
+
+                m.transition(Coin).as_enum()
+            }
+        }
+    }
+
+    println!("coins: {}", coins);
+}
@@ -0,0 +1,90 @@
+//! The driver module contains `drive`, which transitions a state machine
+//! using a `Stream` of events, and awaits the asynchronous side effects of
+//! every transition before taking the next event.
+//!
+//! The driver does not depend on any particular async runtime. It is available
+//! when the `async` feature is enabled.
+//!
+//! ```rust
+//! # extern crate sm;
+//! # extern crate futures_executor;
+//! # extern crate futures_util;
+//! # use sm::sm;
+//! use futures_executor::block_on;
+//! use futures_util::{stream, StreamExt};
+//! use sm::driver::drive;
+//!
+//! sm! {
+//!     Lock {
+//!         InitialStates { Locked }
+//!
+//!         TurnKey {
+//!             Locked => Unlocked
+//!             Unlocked => Locked
+//!         }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     use Lock::*;
+//!
+//!     let events = stream::iter(vec![TurnKey.into(), TurnKey.into()]);
+//!     let machine = Machine::new(Locked).as_enum();
+//!
+//!     let variants: Vec<_> = block_on(
+//!         drive(machine, events, |variant| {
+//!             let unlocked = matches!(variant, Variant::UnlockedByTurnKey(_));
+//!
+//!             async move {
+//!                 // Perform any asynchronous side effects here.
+//!                 let _ = unlocked;
+//!             }
+//!         })
+//!         .collect(),
+//!     );
+//!
+//!     assert_eq!(variants.len(), 2);
+//! }
+//! ```
+
+use crate::Dispatch;
+use core::future::Future;
+use futures_core::Stream;
+use futures_util::{stream, StreamExt};
+
+/// drive returns a stream that takes events from the passed in stream one at
+/// a time, transitions the machine variant, and yields the new variant once
+/// the future returned by the `action` hook for that variant completes.
+///
+/// If the current state has no transition for an event, the event is yielded
+/// as an error, and the machine is left in its current state. The stream ends
+/// when the stream of events ends.
+///
+/// Deferred events and timeouts are not handled by the driver.
+pub fn drive<M, S, F, Fut>(
+    machine: M,
+    events: S,
+    action: F,
+) -> impl Stream<Item = Result<M, M::Event>>
+where
+    M: Dispatch + Clone,
+    S: Stream<Item = M::Event> + Unpin,
+    F: FnMut(&M) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    stream::unfold(
+        (machine, events, action),
+        |(machine, mut events, mut action)| async move {
+            let event = events.next().await?;
+
+            match machine.dispatch(event) {
+                Ok(machine) => {
+                    action(&machine).await;
+
+                    Some((Ok(machine.clone()), (machine, events, action)))
+                }
+                Err((machine, event)) => Some((Err(event), (machine, events, action))),
+            }
+        },
+    )
+}
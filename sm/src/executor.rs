@@ -0,0 +1,265 @@
+//! The executor module contains the `Executor`, which owns a state machine and
+//! a queue of events, and processes those events with run-to-completion
+//! semantics.
+//!
+//! Events are processed strictly one at a time: an event is taken from the
+//! front of the queue, the machine is transitioned, and the (optional) handler
+//! is called with the new machine variant, before the next event is taken from
+//! the queue. Any event the handler raises while handling a transition is
+//! pushed to the back of the queue, instead of being processed re-entrantly.
+//!
+//! Events deferred by the current state of the machine are moved to a separate
+//! queue, and are replayed – in their original order, and before any other
+//! queued events – as soon as the machine reaches a state that no longer
+//! defers them. Using the `sm!` macro, deferred events are declared per state
+//! in a `Defer` block:
+//!
+//! ```text
+//! Defer {
+//!     Loading: Fire, Jump
+//!     Paused: Fire
+//! }
+//! ```
+//!
+//! A state cannot defer an event for which it has a transition.
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! use sm::executor::{Executor, Queue};
+//!
+//! sm! {
+//!     Lock {
+//!         InitialStates { Locked }
+//!
+//!         TurnKey {
+//!             Locked => Unlocked
+//!         }
+//!
+//!         Open {
+//!             Unlocked => Opened
+//!         }
+//!     }
+//! }
+//!
+//! fn main() {
+//! # #[cfg(feature = "alloc")] {
+//!     use std::collections::VecDeque;
+//!     use Lock::*;
+//!
+//!     let machine = Machine::new(Locked).as_enum();
+//!     let mut executor: Executor<_, VecDeque<_>> = Executor::new(machine);
+//!
+//!     executor.push(TurnKey).unwrap();
+//!     executor
+//!         .run(|machine, queue| {
+//!             // Open the door as soon as it is unlocked.
+//!             if let Variant::UnlockedByTurnKey(_) = machine {
+//!                 queue.push(Open.into()).unwrap();
+//!             }
+//!         })
+//!         .unwrap();
+//!
+//!     match executor.machine() {
+//!         Variant::OpenedByOpen(m) => assert_eq!(m.state(), Opened),
+//!         _ => unreachable!(),
+//!     }
+//! # }
+//! }
+//! ```
+//!
+//! The queue is generic over the `Queue` trait. With the `alloc` feature
+//! enabled, `VecDeque` can be used as a growable queue. With the `heapless`
+//! feature enabled, `heapless::Deque` can be used as a fixed capacity queue in
+//! `no_std` environments without an allocator.
+
+use crate::Dispatch;
+
+/// Queue is a first-in, first-out collection of events, used by the
+/// `Executor` to hold events until they are processed.
+pub trait Queue<T> {
+    /// push adds the item to the back of the queue. If the queue has no
+    /// capacity left, the item is returned as an error.
+    fn push(&mut self, item: T) -> Result<(), T>;
+
+    /// pop removes the item at the front of the queue, returning `None` if
+    /// the queue is empty.
+    fn pop(&mut self) -> Option<T>;
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Queue<T> for alloc::collections::VecDeque<T> {
+    fn push(&mut self, item: T) -> Result<(), T> {
+        self.push_back(item);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> Queue<T> for heapless::Deque<T, N> {
+    fn push(&mut self, item: T) -> Result<(), T> {
+        self.push_back(item)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.pop_front()
+    }
+}
+
+/// Executor owns a machine variant and a queue of events, and transitions the
+/// machine by processing the queued events one at a time.
+#[derive(Debug)]
+pub struct Executor<M: Dispatch, Q: Queue<M::Event>> {
+    machine: Option<M>,
+    queue: Q,
+    deferred: Q,
+    deferred_len: usize,
+    replay: bool,
+}
+
+impl<M: Dispatch, Q: Queue<M::Event> + Default> Executor<M, Q> {
+    /// new returns a new executor for the provided machine variant, with empty
+    /// queues.
+    pub fn new(machine: M) -> Self {
+        Self::with_queues(machine, Q::default(), Q::default())
+    }
+}
+
+impl<M: Dispatch, Q: Queue<M::Event>> Executor<M, Q> {
+    /// with_queues returns a new executor for the provided machine variant,
+    /// using the provided queues to hold queued and deferred events.
+    pub fn with_queues(machine: M, queue: Q, deferred: Q) -> Self {
+        Executor {
+            machine: Some(machine),
+            queue,
+            deferred,
+            deferred_len: 0,
+            replay: false,
+        }
+    }
+
+    /// machine returns a reference to the current machine variant.
+    pub fn machine(&self) -> &M {
+        self.machine.as_ref().expect("machine is always present")
+    }
+
+    /// deferred_len returns the number of events deferred by the machine,
+    /// waiting to be replayed.
+    pub fn deferred_len(&self) -> usize {
+        self.deferred_len
+    }
+
+    /// into_inner consumes the executor, and returns the current machine
+    /// variant. Any queued or deferred events are dropped.
+    pub fn into_inner(self) -> M {
+        self.machine.expect("machine is always present")
+    }
+
+    /// push adds an event to the back of the queue. If the queue has no
+    /// capacity left, the event is returned as an error.
+    pub fn push<E: Into<M::Event>>(&mut self, event: E) -> Result<(), M::Event> {
+        self.queue.push(event.into())
+    }
+
+    /// step processes the event at the front of the queue, if any.
+    ///
+    /// This returns `Ok(true)` if an event was processed, `Ok(false)` if the
+    /// queue is empty, and `Err(event)` if the current state of the machine
+    /// has no transition for the event. Unhandled events are removed from the
+    /// queue, and leave the machine in its current state.
+    ///
+    /// Events deferred by the current state are kept, and count as processed.
+    /// If the deferred queue has no capacity left, the event is returned as an
+    /// error.
+    pub fn step(&mut self) -> Result<bool, M::Event> {
+        self.step_with(&mut |_, _| {})
+    }
+
+    /// run processes queued events until the queue is empty, calling the
+    /// handler with the new machine variant after every transition. The
+    /// handler can raise follow-up events by pushing them to the queue, those
+    /// events are processed after all previously queued events.
+    ///
+    /// Processing stops at the first unhandled event, which is returned as an
+    /// error. Calling `run` again continues with the remaining events.
+    pub fn run<F>(&mut self, mut handler: F) -> Result<(), M::Event>
+    where
+        F: FnMut(&M, &mut Q),
+    {
+        while self.step_with(&mut handler)? {}
+
+        Ok(())
+    }
+
+    fn step_with<F>(&mut self, handler: &mut F) -> Result<bool, M::Event>
+    where
+        F: FnMut(&M, &mut Q),
+    {
+        let event = match self.replay().or_else(|| self.queue.pop()) {
+            Some(event) => event,
+            None => return Ok(false),
+        };
+
+        let machine = self.machine.take().expect("machine is always present");
+
+        match machine.dispatch(event) {
+            Ok(machine) => {
+                handler(&machine, &mut self.queue);
+                self.machine = Some(machine);
+                self.replay = self.deferred_len > 0;
+
+                Ok(true)
+            }
+            Err((machine, event)) => {
+                let defers = machine.defers(&event);
+                self.machine = Some(machine);
+
+                if !defers {
+                    return Err(event);
+                }
+
+                self.deferred.push(event)?;
+                self.deferred_len += 1;
+
+                Ok(true)
+            }
+        }
+    }
+
+    /// replay returns the first deferred event that is no longer deferred by
+    /// the current state of the machine, if any. The order of the remaining
+    /// deferred events is preserved.
+    fn replay(&mut self) -> Option<M::Event> {
+        if !self.replay {
+            return None;
+        }
+
+        let machine = self.machine.as_ref().expect("machine is always present");
+        let mut replayed = None;
+
+        for _ in 0..self.deferred_len {
+            let event = self.deferred.pop().expect("deferred event is present");
+
+            if replayed.is_none() && !machine.defers(&event) {
+                replayed = Some(event);
+                continue;
+            }
+
+            // Room was made by popping the event, so this cannot fail.
+            let _ = self.deferred.push(event);
+        }
+
+        // Keep replaying until none of the deferred events can be replayed in
+        // the current state.
+        self.replay = replayed.is_some();
+        if self.replay {
+            self.deferred_len -= 1;
+        }
+
+        replayed
+    }
+}
@@ -114,6 +114,15 @@
 //! # fn main() {}
 //! ```
 //!
+//! Each event can only lead to a single target state from a given state.
+//! Declaring the same transition twice is allowed, but declaring the same event
+//! for the same state with a different target state is a compile error, as the
+//! resulting machine would be nondeterministic.
+//!
+//! Mistakes in the declaration, such as a missing `=>`, a stray `,` or an
+//! `InitialStates` block that is not the first block of the machine, are all
+//! reported at once, instead of one per compilation.
+//!
 //! And we're done. We've defined our state machine structure, and the valid
 //! transitions, and can now use this state machine in our code.
 //!
@@ -423,24 +432,21 @@
 //! This fails with the following compilation error:
 //!
 //! ```text
-//! error[E0599]: no method named `transition` found for type `Lock::Machine<Lock::Broken>` in the current scope
-//!   --> src/lib.rs:360:13
+//! error[E0277]: `Broken` has no transition for event `Lock::TurnKey`, or any other event
+//!   --> src/lib.rs:25:24
 //!    |
-//! 4  | sm! {
-//!    | --- method `transition` not found for this
-//! ...
 //! 25 | let sm = sm.transition(TurnKey);
-//!    |             ^^^^^^^^^^
+//!    |             ---------- ^^^^^^^ invalid transition for `Lock` in state `Broken`
+//!    |             |
+//!    |             required by a bound introduced by this call
 //!    |
-//!    = help: items from traits can only be used if the trait is implemented and in scope
-//!    = note: the following trait defines an item `transition`, perhaps you need to implement it:
-//!            candidate #1: `sm::Transition`
+//!    = note: a machine can only be transitioned using the events declared for its current state
 //! ```
 //!
-//! The error message is not great (and can potentially be improved in the
-//! future), but any error telling you `transition` is not implemented, or the
-//! passed in event type is invalid is an indication that you are trying to
-//! execute an illegal state transition.
+//! The error names the current state of the machine and the event that cannot
+//! be used. For a state that does have transitions, the error lists the events
+//! that are valid in it instead, such as "valid events are: `TurnKey`, `Break`"
+//! for the `Locked` state.
 //!
 //! Finally, we are confined to initialising a new machine in only the states
 //! that we defined in `InitialStates`:
@@ -473,15 +479,664 @@
 //! This results in the following error:
 //!
 //! ```text
-//! error[E0277]: the trait bound `Lock::Broken: sm::InitialState` is not satisfied
-//!   --> src/lib.rs:417:10
+//! error[E0277]: `Lock::Broken` is not an initial state of `Lock`
+//!   --> src/lib.rs:21:23
 //!    |
 //! 21 | let sm = Machine::new(Broken);
-//!    |          ^^^^^^^^^^^^ the trait `sm::InitialState` is not implemented for `Lock::Broken`
+//!    |          ------------ ^^^^^^ cannot initialise `Lock` in this state
+//!    |          |
+//!    |          required by a bound introduced by this call
 //!    |
-//!    = note: required because of the requirements on the impl of `sm::NewMachine<Lock::Broken>` for `Lock::Machine<Lock::Broken>`
+//!    = note: the initial states of `Lock` are: `Locked`, `Unlocked`
+//! ```
+//!
+//! #### Final States
+//!
+//! The macro checks that every state of a machine can be reached from one of
+//! its initial states, and that every event can be triggered from at least one
+//! reachable state. Any violation results in a compile error, pointing at the
+//! offending state or event:
+//!
+//! ```rust,compile_fail
+//! # extern crate sm;
+//! # use sm::sm;
+//! sm! {
+//!     Lock {
+//!         InitialStates { Locked }
+//!
+//!         TurnKey { Locked => Unlocked }
+//!         Repair { Brokne => Locked }
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! States without any outgoing transitions result in a warning, as they are
+//! often the result of a typo. States in which the machine is supposed to end
+//! are marked as such using a `FinalStates` block:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! sm! {
+//!     Lock {
+//!         InitialStates { Locked }
+//!         FinalStates { Broken }
+//!
+//!         TurnKey {
+//!             Locked => Unlocked
+//!             Unlocked => Locked
+//!         }
+//!
+//!         Break {
+//!             Locked, Unlocked => Broken
+//!         }
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! Procedural macros cannot emit warnings on stable Rust, so the macro reports
+//! a dead end by referencing a `#[deprecated]` constant, spanned to the state.
+//! The warning is therefore shown as the use of a deprecated item, with the
+//! actual diagnostic in its note:
+//!
+//! ```text
+//! warning: use of deprecated constant `Lock::_::DEAD_END`: state `Unlocked` has no outgoing transitions, add `FinalStates { Unlocked }` if this is intended
+//!  --> src/main.rs:8:29
+//!   |
+//! 8 |         TurnKey { Locked => Unlocked }
+//!   |                             ^^^^^^^^
+//! ```
+//!
+//! Listing the state in `FinalStates` is the intended way to silence it. As
+//! lint attributes on a macro invocation are ignored, the warning can
+//! otherwise only be allowed for the enclosing module, using
+//! `#![allow(deprecated)]`, which also hides any other deprecation warning in
+//! that module. Use `#![deny(deprecated)]` to turn dead ends into errors.
+//!
+//! #### Grouping States
+//!
+//! States can be grouped using a `Groups` block. Each group generates a marker
+//! trait, implemented by its member states, an `In{Group}` trait, implemented
+//! by the machine in any of those states, and a variant of the `Group` enum,
+//! which is used to check whether a machine variant is in any of the states of
+//! the group. Groups can also be used as the source of a transition, which
+//! declares the transition for each of its member states:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! sm! {
+//!     Enemy {
+//!         InitialStates { Idle }
+//!
+//!         Groups {
+//!             Combat: Attacking, Blocking
+//!         }
+//!
+//!         Spot { Idle => Attacking }
+//!         Block { Attacking => Blocking }
+//!         Retreat { Combat => Idle }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     use Enemy::*;
+//!
+//!     fn in_combat<M: InCombat>(_: &M) -> bool {
+//!         true
+//!     }
+//!
+//!     let sm = Machine::new(Idle).transition(Spot).transition(Block);
+//!     assert!(in_combat(&sm));
+//!     assert!(sm.clone().as_enum().is_in(Group::Combat));
+//!
+//!     let sm = sm.transition(Retreat);
+//!     assert!(!sm.as_enum().is_in(Group::Combat));
+//! }
+//! ```
+//!
+//! #### Named Transitions
+//!
+//! Next to `transition`, every machine has a method for each event it accepts
+//! in its current state, named after the event in snake case. The methods are
+//! only available on machines in states that accept the event, so they double
+//! as a list of the legal next steps of a machine:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! sm! {
+//!     Lock {
+//!         InitialStates { Locked }
+//!
+//!         TurnKey { Locked => Unlocked }
+//!         Break { Locked, Unlocked => Broken }
+//!     }
+//! }
+//!
+//! use Lock::*;
+//!
+//! fn main() {
+//!     let sm = Machine::new(Locked).turn_key();
+//!     assert_eq!(sm.state(), Unlocked);
+//!
+//!     let sm = sm.r#break();
+//!     assert_eq!(sm.state(), Broken);
+//! }
+//! ```
+//!
+//! Events declared as a keyword, such as `Break`, result in a raw identifier.
+//! Imported events take the event itself as an argument. No method is
+//! generated for events that would clash with an existing method of the
+//! machine, such as `State`.
+//!
+//! #### Writing Generic Code
+//!
+//! For each event, the machine module contains a capability trait, named after
+//! the event, which is implemented by every machine that can be transitioned
+//! using that event. This allows writing functions that accept a machine in any
+//! state that accepts the event, with the resulting machine available as
+//! `Next`:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! sm! {
+//!     TurnStile {
+//!         InitialStates { Locked, Unlocked }
+//!
+//!         Coin { Locked, Unlocked => Unlocked }
+//!         Push { Unlocked => Locked }
+//!     }
+//! }
+//!
+//! use TurnStile::*;
+//!
+//! fn insert_coin<M: CanCoin>(m: M) -> M::Next {
+//!     m.transition(Coin)
+//! }
+//!
+//! fn main() {
+//!     assert_eq!(insert_coin(Machine::new(Locked)).state(), Unlocked);
+//!     assert_eq!(insert_coin(Machine::new(Unlocked)).state(), Unlocked);
+//! }
+//! ```
+//!
+//! Transitioning the resulting `Next` machine in generic code, such as in
+//! `M::Next: CanCoin`, requires the `Transition` trait to be in scope. The
+//! capability traits only cover the transitions implementing `Transition`, and
+//! not choices or fallible transitions.
+//!
+//! #### Handling Variants
+//!
+//! Instead of matching against all variants of a machine in a loop, a machine
+//! can generate a handler trait, by adding `Handler` to the machine. The trait
+//! is named after the machine, and has one method for each variant that can
+//! be transitioned further, named after the variant:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! sm! {
+//!     Lock {
+//!         InitialStates { Locked }
+//!
+//!         TurnKey { Locked => Unlocked }
+//!         Break { Unlocked => Broken }
+//!
+//!         Handler
+//!     }
+//! }
+//!
+//! use Lock::*;
+//!
+//! struct Thief;
+//!
+//! impl LockHandler for Thief {
+//!     fn on_initial_locked(&mut self, m: Machine<Locked, sm::NoneEvent>) -> Variant {
+//!         m.transition(TurnKey).as_enum()
+//!     }
+//!
+//!     fn on_unlocked_by_turn_key(&mut self, m: Machine<Unlocked, TurnKey>) -> Variant {
+//!         m.transition(Break).as_enum()
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sm = Machine::new(Locked).as_enum().run(&mut Thief);
+//!     assert!(sm.is(&Broken));
+//! }
+//! ```
+//!
+//! The `run` method keeps calling the handler until the machine ends up in a
+//! state without any transitions, after which the final variant is returned.
+//!
+//! #### Declaring a Machine as a Module
+//!
+//! Instead of using the `sm!` macro, a machine can also be declared as a
+//! module, annotated with the `#[sm::machine]` attribute. The states and
+//! events are declared as variants of the `States` and `Events` enums, with the
+//! initial states marked as `#[initial]`, while the transitions are declared
+//! in a `transitions!` block, using the same syntax as the `sm!` macro:
+//!
+//! ```rust
+//! # extern crate sm;
+//! #[sm::machine]
+//! mod Lock {
+//!     enum States {
+//!         #[initial]
+//!         Locked,
+//!         #[initial]
+//!         Unlocked,
+//!         Broken,
+//!     }
+//!
+//!     enum Events {
+//!         TurnKey,
+//!         Break,
+//!     }
+//!
+//!     transitions! {
+//!         TurnKey {
+//!             Locked => Unlocked
+//!             Unlocked => Locked
+//!         }
+//!
+//!         Break {
+//!             Locked, Unlocked => Broken
+//!         }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     use Lock::*;
+//!
+//!     let sm = Machine::new(Locked).transition(TurnKey);
+//!     assert_eq!(sm.state(), Unlocked);
+//! }
+//! ```
+//!
+//! Both forms generate the same machine. Each state and event used by the
+//! transitions has to be declared in the enums, and each declared state and
+//! event has to be used by a transition. Final states are marked as
+//! `#[final_state]`.
+//!
+//! Any attributes of the module, such as its doc comments, are kept on the
+//! generated module. This also allows lints to be set for a single machine,
+//! for example `#[deny(deprecated)]` to turn its dead ends into errors. As
+//! Rust does not allow a module to have generic parameters, generic machines
+//! can only be declared using the `sm!` macro.
+//!
+//! #### Using Existing Types
+//!
+//! Instead of declaring new states and events, a machine can use existing
+//! types that implement `State` or `Event`, by importing them with `use` at
+//! the top of the machine:
+//!
+//! ```text
+//! Player {
+//!     use crate::input::Jump;
+//!     use crate::physics::Position as Standing;
+//!
+//!     InitialStates { Standing }
+//!
+//!     Jump { Standing => Jumping }
+//! }
+//! ```
+//!
+//! Paths are resolved as they would be in the module containing the macro
+//! invocation, so `use physics::Position;` refers to a module in that scope,
+//! or to another crate named `physics`.
+//!
+//! The macro only generates the machine, its variants and its transitions
+//! around imported types, which allows them to carry data, and to be shared
+//! between machines. Imported initial states implement `InitialState`, and
+//! imported states entered by a transition implement `Enter`, which constructs
+//! the new state, or derive it using `#[derive(Enter)]` when they implement
+//! `Default`.
+//!
+//! #### Generic Machines
+//!
+//! A machine can declare lifetime and type parameters after its name, which
+//! are passed to imported types by importing them with their generic
+//! arguments. Traits used by the bounds of the parameters are imported as
+//! well:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! use sm::{Enter, Event, State};
+//! use std::fmt::Debug;
+//!
+//! pub trait Item: Debug + Clone + Eq {}
+//!
+//! #[derive(Clone, Debug, Eq, PartialEq)]
+//! pub struct Sword;
+//! impl Item for Sword {}
+//!
+//! #[derive(Clone, Debug, Eq, PartialEq)]
+//! pub struct Holding<T>(T);
+//! impl<T: Item> State for Holding<T> {}
+//!
+//! #[derive(Clone, Debug, Eq, PartialEq)]
+//! pub struct Put<T>(T);
+//! impl<T: Item> Event for Put<T> {}
+//!
+//! impl<T: Item, S: State> Enter<S, Put<T>> for Holding<T> {
+//!     fn enter(_: &S, event: &Put<T>) -> Self {
+//!         Holding(event.0.clone())
+//!     }
+//! }
+//!
+//! sm! {
+//!     Slot<T: Item> {
+//!         use crate::Item;
+//!         use crate::Holding<T>;
+//!         use crate::Put<T>;
+//!
+//!         InitialStates { Empty }
+//!
+//!         Put { Empty => Holding }
+//!         Take { Holding => Empty }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     use Slot::*;
+//!
+//!     let sm = Machine::new(Empty).put(Put(Sword));
+//!     assert_eq!(sm.state(), Holding(Sword));
+//!
+//!     let sm: Variant<Sword> = sm.take().as_enum();
+//!     assert!(sm.is(&Empty));
+//! }
 //! ```
 //!
+//! The parameters are added to `Machine`, which becomes `Machine<T, S, E>`,
+//! and to `Variant` and every implementation for them, while `Events` and the
+//! capability traits only have the parameters used by the events. Type
+//! parameters are required to implement `Debug`, `Clone` and `Eq`, like the
+//! states and events carrying them. The names `S`, `E`, `V`, `C`, `H` and `M`
+//! are used by the generated code, and cannot be used as parameters. A generic
+//! machine cannot be embedded in another machine.
+//!
+//! #### Sharing a Context
+//!
+//! Data shared by all states of a machine, such as an entity id or a counter,
+//! is declared as the context of the machine, using a `Context` block naming
+//! its type. The context is passed to `new`, carried over by every transition,
+//! and available through `context` and `context_mut`:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! use sm::{EnterWith, FallibleWith, State};
+//!
+//! #[derive(Clone, Debug, Eq, PartialEq)]
+//! pub struct Stats {
+//!     coins: u32,
+//! }
+//!
+//! #[derive(Clone, Debug, Eq, PartialEq)]
+//! pub struct Unlocked(u32);
+//! impl State for Unlocked {}
+//!
+//! impl EnterWith<Lock::Locked, Lock::Coin, Stats> for Unlocked {
+//!     fn enter_with(_: &Lock::Locked, _: &Lock::Coin, stats: &mut Stats) -> Self {
+//!         stats.coins += 1;
+//!         Unlocked(stats.coins)
+//!     }
+//! }
+//!
+//! impl FallibleWith<Unlocked, Stats> for Lock::Push {
+//!     type Error = ();
+//!
+//!     fn try_transition_with(&self, _: &Unlocked, stats: &Stats) -> Result<(), ()> {
+//!         if stats.coins < 3 { Ok(()) } else { Err(()) }
+//!     }
+//! }
+//!
+//! sm! {
+//!     Lock {
+//!         use crate::Unlocked;
+//!
+//!         InitialStates { Locked }
+//!         FinalStates { Full }
+//!         Context { crate::Stats }
+//!
+//!         Coin { Locked => Unlocked }
+//!         Push { Unlocked => Locked ? Full }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     use Lock::*;
+//!
+//!     let sm = Machine::new(Locked, Stats { coins: 0 }).coin();
+//!     assert_eq!(sm.state(), Unlocked(1));
+//!
+//!     let mut sm = sm.push().unwrap();
+//!     sm.context_mut().coins = 5;
+//!
+//!     let (sm, _) = sm.coin().push().unwrap_err();
+//!     assert_eq!(sm.context(), &Stats { coins: 6 });
+//! }
+//! ```
+//!
+//! The context type is resolved the same way as the `use` declarations of the
+//! machine, and has to implement `Debug`, `Clone` and `Eq`. Imported states
+//! entered by a transition implement `EnterWith` instead of `Enter`, which can
+//! change the context. The events of fallible transitions implement
+//! `FallibleWith`, which can inspect it, and is implemented automatically for
+//! the events implementing `Fallible`. A machine with a context is created
+//! using its inherent `new` method, instead of `Initializer`, and cannot be
+//! embedded in another machine.
+//!
+//! #### Composing Machines
+//!
+//! A machine can be embedded as a state of another machine, using a
+//! `Submachine` block. The block names the embedded machine and the state in
+//! which it is entered. Once the embedded machine reaches any of its final
+//! states, the parent machine is transitioned using the generated `Completed`
+//! event:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! use sm::Dispatch;
+//!
+//! sm! {
+//!     Reload {
+//!         InitialStates { Ejecting }
+//!         FinalStates { Loaded }
+//!
+//!         Insert { Ejecting => Loaded }
+//!     }
+//!
+//!     Weapon {
+//!         InitialStates { Idle }
+//!
+//!         Empty { Idle => Reloading }
+//!
+//!         Submachine(Reload: Ejecting) {
+//!             Reloading => Idle
+//!         }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     use Weapon::*;
+//!
+//!     match Machine::new(Idle).transition(Empty).as_enum() {
+//!         Variant::ReloadingByEmpty(_, reload) => assert!(reload.is(&Reload::Ejecting)),
+//!         _ => unreachable!(),
+//!     }
+//!
+//!     let weapon = Machine::new(Idle).as_enum();
+//!     let weapon = weapon.dispatch(Empty.into()).unwrap();
+//!     let weapon = weapon.dispatch(Events::Reload(Reload::Insert.into())).unwrap();
+//!
+//!     assert!(weapon.is(&Idle));
+//! }
+//! ```
+//!
+//! The variants of the parent machine in the embedding state carry the variant
+//! of the embedded machine. Events of the embedded machine are dispatched
+//! through the parent machine, wrapped in a variant of the parent's `Events`
+//! enum named after the embedded machine.
+//!
+//! As the embedded machine is only carried by the variant, the `Completed`
+//! event is triggered by `dispatch` alone. It cannot be passed to `transition`,
+//! and dispatching it directly is rejected, so the embedded machine cannot be
+//! skipped. Whether a variant is in one of the final states of its machine is
+//! returned by its `is_final` method.
+//!
+//! #### Naming Generated Types
+//!
+//! The states and events of a machine share its module with the generated
+//! types. A state or event named after any of them, such as `Machine` or
+//! `CanTurnKey`, is rejected by the macro, as are two states and events
+//! resulting in the same variant.
+//! The `Machine`, `Variant` and `Events` types can be renamed using a `Names`
+//! block, to free up their names:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! sm! {
+//!     Factory {
+//!         InitialStates { Idle }
+//!         FinalStates { Machine }
+//!         Names { Machine as Factory, Variant as FactoryState }
+//!
+//!         Build { Idle => Machine }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     use Factory::{Build, Idle, Machine};
+//!
+//!     let factory = Factory::Factory::new(Idle).transition(Build);
+//!     assert_eq!(factory.state(), Machine);
+//! }
+//! ```
+//!
+//! A parent machine refers to an embedded machine using its renamed types,
+//! as long as both are declared in the same `sm!` invocation.
+//!
+//! #### Using a Renamed or Re-exported Crate
+//!
+//! The generated code refers to this crate as `::sm`, using fully-qualified
+//! paths, so it does not add anything to the scope of the macro invocation,
+//! nor depend on anything being in it. The same goes for the items of the
+//! prelude, such as `Option` and `Result`, so states and events can be named
+//! `Ok` or `None`. When the crate is renamed, or used through another crate
+//! that re-exports it, its path can be passed to the macro as `crate = path;`,
+//! before the first machine, or to the attribute as
+//! `#[sm::machine(crate = path)]`:
+//!
+//! ```rust
+//! mod engine {
+//!     pub extern crate sm;
+//! }
+//!
+//! engine::sm::sm! {
+//!     crate = crate::engine::sm;
+//!
+//!     Lock {
+//!         InitialStates { Locked }
+//!
+//!         TurnKey { Locked => Unlocked  Unlocked => Locked }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     use Lock::*;
+//!
+//!     let sm = Machine::new(Locked).transition(TurnKey);
+//!     assert_eq!(sm.state(), Unlocked);
+//! }
+//! ```
+//!
+//! The path is resolved the same way as the `use` declarations of a machine,
+//! so a renamed dependency is passed as `crate = state_machines;`.
+//!
+//! The derive macros take the path as a `#[sm(crate = path)]` attribute on the
+//! derived type, as in `#[derive(State)] #[sm(crate = crate::engine::sm)]`,
+//! where it is used exactly as written.
+//!
+//! #### Declaring Machines Locally
+//!
+//! As the expansion of the macro consists of nothing but the modules of its
+//! machines, it can be invoked more than once in the same module, and inside a
+//! function body:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! fn main() {
+//!     sm! {
+//!         Lock {
+//!             InitialStates { Locked }
+//!
+//!             TurnKey { Locked => Unlocked  Unlocked => Locked }
+//!         }
+//!     }
+//!
+//!     let sm = Lock::Machine::new(Lock::Locked).turn_key();
+//!     assert_eq!(sm.state(), Lock::Unlocked);
+//! }
+//! ```
+//!
+//! This includes test functions, so a test can declare the machine it uses
+//! next to its assertions:
+//!
+//! ```text
+//! #[test]
+//! fn test_lock() {
+//!     sm! { Lock { ... } }
+//!
+//!     let sm = Lock::Machine::new(Lock::Locked).turn_key();
+//!     assert_eq!(sm.state(), Lock::Unlocked);
+//! }
+//! ```
+//!
+//! Relative paths in the `use` declarations and `Submachine` blocks of a
+//! machine declared inside a function resolve from the module containing the
+//! function, as the items of the function itself cannot be named from another
+//! module. Embedded machines and imported types therefore have to be declared
+//! outside of the function.
+//!
+//! #### Controlling Visibility
+//!
+//! The module of a machine is private by default. A visibility in front of the
+//! machine, such as `pub` or `pub(crate)`, is given to its module, which allows
+//! exporting a machine from a library. Events can have a visibility as well,
+//! to keep internal events from being triggered outside of it:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! sm! {
+//!     pub Lock {
+//!         InitialStates { Locked }
+//!
+//!         TurnKey { Locked => Unlocked  Unlocked => Locked }
+//!         pub(crate) Reset { Unlocked => Locked }
+//!     }
+//! }
+//! #
+//! # fn main() {}
+//! ```
+//!
+//! The visibility of an event is relative to the module containing the
+//! machine, just like the visibility of the machine itself, and applies to its
+//! named method and capability trait as well. Events without a visibility are
+//! as visible as the machine. With `#[sm::machine]`, the visibility is
+//! declared on the module and the variants of `enum Events` instead.
+//!
 //! #### The End 👋
 //!
 //! And that's it! There's nothing else to it, except a declarative – and easy
@@ -517,16 +1172,24 @@
 )]
 #![deny(clippy::all)]
 
-use core::fmt;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::{fmt, future::Future, time::Duration};
 
 #[cfg(feature = "macro")]
-pub use sm_macro::sm;
+pub use sm_macro::{machine, sm, Enter, Event, InitialState, Machine, State};
+
+#[cfg(feature = "async")]
+pub mod driver;
+pub mod executor;
+pub mod timer;
 
 /// State is a custom [marker trait][m] that allows [unit-like structs][u] to be
 /// used as states in a state machine.
 ///
 /// If you are using the `sm!` macro, then there is no need to interact with
-/// this trait.
+/// this trait. Otherwise, it can be implemented using `#[derive(State)]`.
 ///
 /// [m]: https://doc.rust-lang.org/std/marker/index.html
 /// [u]: https://doc.rust-lang.org/book/second-edition/ch05-01-defining-structs.html#unit-like-structs-without-any-fields
@@ -537,16 +1200,22 @@ pub trait State: fmt::Debug + Eq + Clone {}
 /// `State` trait.
 ///
 /// If you are using the `sm!` macro, then there is no need to interact with
-/// this trait.
+/// this trait. Otherwise, it can be implemented using
+/// `#[derive(InitialState)]`.
 ///
 /// [m]: https://doc.rust-lang.org/std/marker/index.html
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not an initial state of its machine",
+    label = "cannot initialise a machine in this state",
+    note = "only the states listed in `InitialStates {{ ... }}` can be used to initialise a machine"
+)]
 pub trait InitialState: State {}
 
 /// Event is a custom [marker trait][m] that allows [unit-like structs][u] to be
 /// used as states in a state machine.
 ///
 /// If you are using the `sm!` macro, then there is no need to interact with
-/// this trait.
+/// this trait. Otherwise, it can be implemented using `#[derive(Event)]`.
 ///
 /// [m]: https://doc.rust-lang.org/std/marker/index.html
 /// [u]: https://doc.rust-lang.org/book/second-edition/ch05-01-defining-structs.html#unit-like-structs-without-any-fields
@@ -556,7 +1225,9 @@ pub trait Event: fmt::Debug + Eq + Clone {}
 /// current state.
 ///
 /// If you are using the `sm!` macro, then there is no need to interact with
-/// this trait.
+/// this trait. Otherwise, it can be implemented for a tuple struct of the form
+/// `TurnStile<S: State, E: Event>(S, Option<E>)` using `#[derive(Machine)]`,
+/// which also implements `Initializer`.
 pub trait Machine: fmt::Debug + Eq {
     /// State represents the current (static) state of the state machine.
     type State: State;
@@ -592,11 +1263,91 @@ pub trait Initializer<S: InitialState> {
     fn new(state: S) -> Self::Machine;
 }
 
+/// Enter provides the method required to construct a state when it is entered
+/// from another state, using an event.
+///
+/// States declared by the `sm!` macro are unit-like structs, which need no
+/// construction. Existing types can be used as states by importing them into a
+/// machine using `use`, in which case each imported state that is the target
+/// of a transition needs to implement this trait:
+///
+/// ```rust
+/// # extern crate sm;
+/// # use sm::sm;
+/// use sm::{Enter, Event, InitialState, State};
+///
+/// #[derive(Clone, Debug, Eq, PartialEq)]
+/// pub struct Standing(u32);
+/// impl State for Standing {}
+/// impl InitialState for Standing {}
+///
+/// #[derive(Clone, Debug, Eq, PartialEq)]
+/// pub struct Jumping(u32);
+/// impl State for Jumping {}
+///
+/// #[derive(Clone, Debug, Eq, PartialEq)]
+/// pub struct Jump(u32);
+/// impl Event for Jump {}
+///
+/// impl Enter<Standing, Jump> for Jumping {
+///     fn enter(from: &Standing, event: &Jump) -> Self {
+///         Jumping(from.0 + event.0)
+///     }
+/// }
+///
+/// sm! {
+///     Player {
+///         use Standing;
+///         use Jumping;
+///         use Jump;
+///
+///         InitialStates { Standing }
+///
+///         Jump {
+///             Standing => Jumping
+///         }
+///     }
+/// }
+///
+/// fn main() {
+///     use Player::Machine;
+///
+///     let sm = Machine::new(Standing(10)).transition(Jump(5));
+///     assert_eq!(sm.state(), Jumping(15));
+/// }
+/// ```
+///
+/// States that implement `Default` can derive this trait instead, using
+/// `#[derive(Enter)]`, which constructs the state using `Default` whenever it
+/// is entered.
+pub trait Enter<S: State, E: Event>: State {
+    /// enter returns the new state, based on the state that is left and the
+    /// event that triggered the transition.
+    fn enter(from: &S, event: &E) -> Self;
+}
+
+/// EnterWith is the counterpart of `Enter` for machines with a context,
+/// which is passed to the method as well, and can be changed by it.
+///
+/// The transitions of a machine with a context construct the imported states
+/// they enter using this trait instead of `Enter`. `#[derive(Enter)]`
+/// implements both traits, ignoring the context.
+pub trait EnterWith<S: State, E: Event, C>: State {
+    /// enter_with returns the new state, based on the state that is left, the
+    /// event that triggered the transition, and the context of the machine.
+    fn enter_with(from: &S, event: &E, context: &mut C) -> Self;
+}
+
 /// Transition provides the method required to transition from one state to
 /// another.
 ///
 /// If you are using the `sm!` macro, then there is no need to interact with
 /// this trait.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no transition for event `{E}`",
+    label = "invalid transition for the current state of the machine",
+    note = "a machine can only be transitioned using the events declared for its current state"
+)]
 pub trait Transition<E: Event>: fmt::Debug {
     /// Machine represents the machine on which the implemented transformation
     /// should execute.
@@ -607,6 +1358,251 @@ pub trait Transition<E: Event>: fmt::Debug {
     fn transition(self, event: E) -> Self::Machine;
 }
 
+/// TryTransition is the counterpart of `Transition` for fallible transitions,
+/// which end up in either their target state or their error state.
+///
+/// If you are using the `sm!` macro, then there is no need to interact with
+/// this trait. Fallible transitions are declared by adding an error state to
+/// the transition, see `Fallible`.
+pub trait TryTransition<E: Event>: fmt::Debug {
+    /// Machine represents the machine in the target state of the transition.
+    type Machine: Machine;
+
+    /// Failed represents the machine in the error state of the transition.
+    type Failed: Machine;
+
+    /// Error represents the error that caused the transition to fail.
+    type Error: fmt::Debug;
+
+    /// try_transition consumes the state machine and returns either a new
+    /// machine in the target state, or a new machine in the error state
+    /// together with the error, based on the passed in event.
+    fn try_transition(self, event: E) -> Result<Self::Machine, (Self::Failed, Self::Error)>;
+}
+
+/// Accept is the counterpart of `Transition`, implemented by every event for
+/// each machine that can be transitioned using the event.
+///
+/// The machines generated by the `sm!` macro use this trait to provide an
+/// inherent `transition` method for each state, which reports an illegal
+/// transition by listing the events that are valid in that state, instead of
+/// as a missing method. The macro implements it for the events of fallible
+/// transitions as well, for which the returned machine is the `Result` of
+/// `TryTransition`. There is no need to implement this trait yourself.
+pub trait Accept<M>: Event {
+    /// Machine represents the machine returned by the transition.
+    type Machine: fmt::Debug;
+
+    /// accept transitions the passed in machine using this event.
+    fn accept(self, machine: M) -> Self::Machine;
+}
+
+impl<E: Event, M: Transition<E>> Accept<M> for E {
+    type Machine = M::Machine;
+
+    fn accept(self, machine: M) -> Self::Machine {
+        machine.transition(self)
+    }
+}
+
+/// AsyncTransition is the asynchronous counterpart of `Transition`, allowing
+/// the asynchronous side effects of a transition to be awaited before the new
+/// machine is handed out.
+///
+/// This trait is implemented for every type implementing `Transition`.
+pub trait AsyncTransition<E: Event>: Transition<E> + Sized {
+    /// transition_async consumes the state machine and transitions it using
+    /// the passed in event, after which the future returned by the `action`
+    /// hook is awaited, before the new machine is returned.
+    fn transition_async<F, Fut>(self, event: E, action: F) -> impl Future<Output = Self::Machine>
+    where
+        F: FnOnce(&Self::Machine) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        async move {
+            let machine = self.transition(event);
+            action(&machine).await;
+
+            machine
+        }
+    }
+}
+
+impl<E: Event, T: Transition<E>> AsyncTransition<E> for T {}
+
+/// Fallible provides the method required to perform the work of a fallible
+/// transition, which decides whether the machine ends up in the target state
+/// or in the error state.
+///
+/// Using the `sm!` macro, a fallible transition is declared by adding the error
+/// state after a `?`, and implementing this trait for the event:
+///
+/// ```rust
+/// # extern crate sm;
+/// # use sm::sm;
+/// use sm::Fallible;
+///
+/// sm! {
+///     Session {
+///         InitialStates { Idle }
+///
+///         Connect {
+///             Idle => Connected ? Failed
+///         }
+///     }
+/// }
+///
+/// impl Fallible<Session::Idle> for Session::Connect {
+///     type Error = &'static str;
+///
+///     fn try_transition(&self, _: &Session::Idle) -> Result<(), Self::Error> {
+///         Err("connection refused")
+///     }
+/// }
+///
+/// fn main() {
+///     use Session::*;
+///
+///     match Machine::new(Idle).transition(Connect) {
+///         Ok(m) => assert_eq!(m.state(), Connected),
+///         Err((m, error)) => {
+///             assert_eq!(m.state(), Failed);
+///             assert_eq!(error, "connection refused");
+///         }
+///     }
+/// }
+/// ```
+///
+/// The machine implements `TryTransition` for the event, instead of
+/// `Transition`, and its `transition` method returns the result of
+/// `TryTransition`. When taken by `Dispatch`, the machine moves to the error
+/// state, but the error itself is dropped.
+pub trait Fallible<S: State>: Event {
+    /// Error represents the error returned when the work fails.
+    type Error: fmt::Debug;
+
+    /// try_transition performs the work of transitioning from the passed in
+    /// state using this event, returning an error if the work failed.
+    fn try_transition(&self, state: &S) -> Result<(), Self::Error>;
+}
+
+/// FallibleWith is the counterpart of `Fallible` for machines with a context,
+/// which is passed to the method as well.
+///
+/// Events implementing `Fallible` implement this trait automatically, ignoring
+/// the context.
+pub trait FallibleWith<S: State, C>: Event {
+    /// Error represents the error returned when the work fails.
+    type Error: fmt::Debug;
+
+    /// try_transition_with performs the work of transitioning from the passed
+    /// in state using this event, returning an error if the work failed.
+    fn try_transition_with(&self, state: &S, context: &C) -> Result<(), Self::Error>;
+}
+
+impl<T: Fallible<S>, S: State, C> FallibleWith<S, C> for T {
+    type Error = T::Error;
+
+    fn try_transition_with(&self, state: &S, _: &C) -> Result<(), Self::Error> {
+        self.try_transition(state)
+    }
+}
+
+/// Choice provides the method required to transition from one state to one of
+/// several possible states, selected at runtime.
+///
+/// If you are using the `sm!` macro, then there is no need to interact with
+/// this trait. Choices are declared by separating the possible target states
+/// with a `|`, see `Choose`.
+pub trait Choice<E: Event>: fmt::Debug {
+    /// Machine represents the enum of machines that the implemented choice
+    /// can result in.
+    type Machine;
+
+    /// choose consumes the state machine and returns a new machine in the
+    /// target state selected by the event.
+    fn choose(self, event: E) -> Self::Machine;
+}
+
+/// Choose provides the method required to select the target state of a choice
+/// transition, based on the event and the current state.
+///
+/// Using the `sm!` macro, a choice is declared by separating the possible
+/// target states with a `|`, and implementing this trait for the event. The
+/// selected branch is a generated enum, named after the event and the source
+/// state, with one variant per target state. The returned machine is a
+/// generated enum as well, with one variant per target state holding the
+/// machine in that state:
+///
+/// ```rust
+/// # extern crate sm;
+/// # use sm::sm;
+/// use sm::Choose;
+///
+/// sm! {
+///     Player {
+///         InitialStates { Aiming }
+///
+///         Attack {
+///             Aiming => Hit | Miss
+///         }
+///     }
+/// }
+///
+/// impl Choose<Player::Aiming> for Player::Attack {
+///     type Branch = Player::AttackFromAimingBranch;
+///
+///     fn choose(&self, _: &Player::Aiming) -> Self::Branch {
+///         Player::AttackFromAimingBranch::Hit
+///     }
+/// }
+///
+/// fn main() {
+///     use Player::*;
+///
+///     match Machine::new(Aiming).transition(Attack) {
+///         AttackFromAiming::Hit(m) => assert_eq!(m.state(), Hit),
+///         AttackFromAiming::Miss(_) => unreachable!(),
+///     }
+/// }
+/// ```
+///
+/// The machine implements `Choice` for the event, instead of `Transition`,
+/// and its `transition` method returns the generated enum of machines. When
+/// taken by `Dispatch`, the machine moves to the selected target state.
+pub trait Choose<S: State>: Event {
+    /// Branch represents the generated enum of the target states of the
+    /// choice.
+    type Branch;
+
+    /// choose selects the target state of the transition from the passed in
+    /// state using this event.
+    fn choose(&self, state: &S) -> Self::Branch;
+}
+
+/// ChooseWith is the counterpart of `Choose` for machines with a context,
+/// which is passed to the method as well.
+///
+/// Events implementing `Choose` implement this trait automatically, ignoring
+/// the context.
+pub trait ChooseWith<S: State, C>: Event {
+    /// Branch represents the generated enum of the target states of the
+    /// choice.
+    type Branch;
+
+    /// choose_with selects the target state of the transition from the passed
+    /// in state using this event.
+    fn choose_with(&self, state: &S, context: &C) -> Self::Branch;
+}
+
+impl<T: Choose<S>, S: State, C> ChooseWith<S, C> for T {
+    type Branch = T::Branch;
+
+    fn choose_with(&self, state: &S, _: &C) -> Self::Branch {
+        self.choose(state)
+    }
+}
+
 /// AsEnum provides the method to convert a state machine instance to an enum
 /// type.
 ///
@@ -622,6 +1618,41 @@ pub trait AsEnum: fmt::Debug {
     fn as_enum(self) -> Self::Enum;
 }
 
+/// Dispatch provides the method to transition a machine enum variant using an
+/// event enum variant, allowing events only known at runtime to be processed
+/// one after the other.
+///
+/// If you are using the `sm!` macro, then there is no need to interact with
+/// this trait.
+pub trait Dispatch: fmt::Debug + Sized {
+    /// Event is an enum that represents all events known to the state machine.
+    type Event;
+
+    /// dispatch consumes the machine variant and returns a new variant, based
+    /// on the passed in event. If the current state has no transition for the
+    /// event, both the unchanged variant and the unhandled event are returned
+    /// as an error.
+    fn dispatch(self, event: Self::Event) -> Result<Self, (Self, Self::Event)>;
+
+    /// defers returns `true` if the current state of the machine defers the
+    /// passed in event, meaning the event should be kept around until the
+    /// machine reaches a state that no longer defers it.
+    ///
+    /// By default, no events are deferred.
+    fn defers(&self, _event: &Self::Event) -> bool {
+        false
+    }
+
+    /// timeout returns the duration after which the current state of the
+    /// machine times out, together with the event that triggers the timeout
+    /// transition.
+    ///
+    /// By default, states never time out.
+    fn timeout(&self) -> Option<(Duration, Self::Event)> {
+        None
+    }
+}
+
 /// NoneEvent is a semi-private event struct that is used to allow the
 /// `Initializer` trait implementations to provide a simple API to initialise a
 /// new machine.
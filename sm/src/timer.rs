@@ -0,0 +1,210 @@
+//! The timer module contains the `Timer`, which owns a state machine and a
+//! `Clock`, and triggers timeout transitions once the machine has been in a
+//! state for long enough.
+//!
+//! Using the `sm!` macro, timeout transitions are declared using an `After`
+//! block, with a duration in `ms`, `s`, `m` or `h`. When the machine has been
+//! in one of the listed states for the given duration, it is transitioned
+//! using the generated `Timeout` event:
+//!
+//! ```rust
+//! # extern crate sm;
+//! # use sm::sm;
+//! use sm::timer::{ManualClock, Timer};
+//! use std::time::Duration;
+//!
+//! sm! {
+//!     Player {
+//!         InitialStates { Idle }
+//!
+//!         Hit {
+//!             Idle => Stunned
+//!         }
+//!
+//!         After(3s) {
+//!             Stunned => Idle
+//!         }
+//!     }
+//! }
+//!
+//! fn main() {
+//!     use Player::*;
+//!
+//!     let clock = ManualClock::new();
+//!     let mut timer = Timer::new(Machine::new(Idle).as_enum(), &clock);
+//!
+//!     timer.dispatch(Hit).unwrap();
+//!
+//!     clock.advance(Duration::from_secs(2));
+//!     assert!(!timer.update());
+//!
+//!     clock.advance(Duration::from_secs(1));
+//!     assert!(timer.update());
+//!
+//!     match timer.machine() {
+//!         Variant::IdleByTimeout(m) => assert_eq!(m.state(), Idle),
+//!         _ => unreachable!(),
+//!     }
+//! }
+//! ```
+//!
+//! The clock is user-supplied, which allows the timer to run against the wall
+//! clock, the number of elapsed game ticks, or a `ManualClock` to advance time
+//! manually in deterministic tests.
+//!
+//! For fixed-step loops, timeouts can instead be declared as a number of ticks
+//! using an `AfterTicks` block, which does not need a timer or a clock:
+//!
+//! ```text
+//! AfterTicks(30) {
+//!     Invulnerable => Vulnerable
+//! }
+//! ```
+//!
+//! Each machine then keeps its own tick counter, which is decremented by
+//! calling `tick()` on the machine variant. Once the counter reaches zero, the
+//! machine is transitioned using the generated `TickTimeout` event. The number
+//! of ticks left in the current state is returned by `remaining_ticks()`.
+
+use crate::Dispatch;
+use core::{cell::Cell, time::Duration};
+
+/// Clock provides the current time to a `Timer`.
+pub trait Clock {
+    /// now returns the time elapsed since an arbitrary, but fixed, point in
+    /// time. The returned value should never decrease.
+    fn now(&self) -> Duration;
+}
+
+impl<C: Clock> Clock for &C {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+/// ManualClock is a clock that only advances when told to, which makes it
+/// useful for deterministic tests and simulations.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ManualClock(Cell<Duration>);
+
+impl ManualClock {
+    /// new returns a new clock, starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// advance moves the clock forward by the provided duration.
+    pub fn advance(&self, duration: Duration) {
+        self.0.set(self.0.get() + duration);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.0.get()
+    }
+}
+
+/// Timer owns a machine variant and a clock, and transitions the machine once
+/// the timeout of its current state has expired.
+#[derive(Debug)]
+pub struct Timer<M: Dispatch, C: Clock> {
+    machine: Option<M>,
+    clock: C,
+    entered: Duration,
+}
+
+impl<M: Dispatch, C: Clock> Timer<M, C> {
+    /// new returns a new timer for the provided machine variant. The current
+    /// state of the machine is considered to be entered at the time of
+    /// creation.
+    pub fn new(machine: M, clock: C) -> Self {
+        let entered = clock.now();
+
+        Timer {
+            machine: Some(machine),
+            clock,
+            entered,
+        }
+    }
+
+    /// machine returns a reference to the current machine variant.
+    pub fn machine(&self) -> &M {
+        self.machine.as_ref().expect("machine is always present")
+    }
+
+    /// clock returns a reference to the clock used by the timer.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// into_inner consumes the timer, and returns the current machine variant.
+    pub fn into_inner(self) -> M {
+        self.machine.expect("machine is always present")
+    }
+
+    /// remaining returns the time left before the current state of the machine
+    /// times out, or `None` if the current state has no timeout.
+    pub fn remaining(&self) -> Option<Duration> {
+        let (after, _) = self.machine().timeout()?;
+        let elapsed = self.clock.now() - self.entered;
+
+        Some(after.checked_sub(elapsed).unwrap_or_default())
+    }
+
+    /// dispatch transitions the machine using the passed in event, restarting
+    /// the timeout of the new state. If the current state has no transition
+    /// for the event, the event is returned as an error.
+    pub fn dispatch<E: Into<M::Event>>(&mut self, event: E) -> Result<(), M::Event> {
+        let machine = self.machine.take().expect("machine is always present");
+
+        match machine.dispatch(event.into()) {
+            Ok(machine) => {
+                self.machine = Some(machine);
+                self.entered = self.clock.now();
+
+                Ok(())
+            }
+            Err((machine, event)) => {
+                self.machine = Some(machine);
+
+                Err(event)
+            }
+        }
+    }
+
+    /// update checks the timeout of the current state, and transitions the
+    /// machine if the timeout has expired. This returns `true` if a timeout
+    /// transition took place.
+    ///
+    /// At most one timeout transition takes place per update. The timeout of
+    /// the new state starts at the moment the previous timeout expired, not at
+    /// the moment of the update, so timeouts do not drift if updates are late.
+    pub fn update(&mut self) -> bool {
+        let (after, event) = match self.machine().timeout() {
+            Some(timeout) => timeout,
+            None => return false,
+        };
+
+        let expired_at = self.entered + after;
+        if self.clock.now() < expired_at {
+            return false;
+        }
+
+        let machine = self.machine.take().expect("machine is always present");
+
+        match machine.dispatch(event) {
+            Ok(machine) => {
+                self.machine = Some(machine);
+                self.entered = expired_at;
+
+                true
+            }
+            Err((machine, _)) => {
+                self.machine = Some(machine);
+
+                false
+            }
+        }
+    }
+}
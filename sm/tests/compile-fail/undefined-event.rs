@@ -5,7 +5,7 @@ sm!{
     Lock {
         InitialStates { Locked }
 
-        TurnKey { Unlocked => Locked }
+        TurnKey { Locked => Unlocked }
     }
 }
 
@@ -15,5 +15,4 @@ fn main() {
 
     sm.transition(Invalid);
     //~^ ERROR cannot find value `Invalid` in this scope
-    //~| ERROR no method named `transition` found for type `Lock::Machine<Lock::Locked, sm::NoneEvent>` in the current scope
 }
@@ -16,5 +16,5 @@ fn main() {
     let sm = Machine::new(Unlocked);
 
     sm.transition(TurnKey);
-    //~^ ERROR no method named `transition` found for type `Lock::Machine<Lock::Unlocked, sm::NoneEvent>` in the current scope
+    //~^ ERROR `Unlocked` has no transition for event `Lock::TurnKey`, or any other event
 }
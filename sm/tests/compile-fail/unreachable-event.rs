@@ -0,0 +1,16 @@
+extern crate sm;
+use sm::sm;
+
+sm!{
+    Lock {
+        InitialStates { Unlocked }
+
+        TurnKey {
+        //~^ ERROR event `TurnKey` can never be triggered, as none of its source states (`Locked`) are reachable
+            Locked => Unlocked
+            //~^ ERROR state `Locked` is not reachable from any of the initial states
+        }
+    }
+}
+
+fn main() {}
@@ -1,11 +1,11 @@
-//! This is a support crate that contains the function-like procedural macro to
-//! build state machines using the [sm] crate. All documentation lives in that
-//! crate.
+//! This is a support crate that contains the function-like, attribute and
+//! derive procedural macros to build state machines using the [sm] crate. All
+//! documentation lives in that crate.
 //!
 //! [sm]: https://docs.rs/sm
 
 // quote! macro needs a higher recursion limit
-#![recursion_limit = "512"]
+#![recursion_limit = "1024"]
 #![forbid(
     future_incompatible,
     macro_use_extern_crate,
@@ -33,10 +33,15 @@
 
 extern crate proc_macro;
 
-use crate::sm::machine::Machines;
+use crate::sm::{
+    attribute::MachineModule, derive, error::Errors, import::CratePath, machine::Machines,
+};
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::parse_macro_input;
+use syn::{
+    parse::{ParseStream, Parser},
+    parse_macro_input, DeriveInput,
+};
 
 mod sm;
 
@@ -45,7 +50,91 @@ mod sm;
 /// See the main crate documentation for more details.
 #[proc_macro]
 pub fn sm(input: TokenStream) -> TokenStream {
-    let machines: Machines = parse_macro_input!(input as Machines);
+    let mut errors = Errors::default();
+    let parser = |input: ParseStream<'_>| Machines::parse_with(input, &mut errors);
+    let result = parser.parse(input);
 
-    quote!(#machines).into()
+    match errors.check(result) {
+        Some(machines) if errors.is_empty() => quote!(#machines).into(),
+        _ => quote!(#errors).into(),
+    }
+}
+
+/// Generate the state machine described by the annotated module.
+///
+/// See the main crate documentation for more details.
+#[proc_macro_attribute]
+pub fn machine(attr: TokenStream, item: TokenStream) -> TokenStream {
+    // `#[sm::machine(crate = ::sm)]`
+    //                ^^^^^^^^^^^^
+    let krate = if attr.is_empty() {
+        CratePath::default()
+    } else {
+        parse_macro_input!(attr as CratePath)
+    };
+
+    let mut errors = Errors::default();
+    let parser = |input: ParseStream<'_>| MachineModule::parse_with(input, &mut errors);
+    let result = parser.parse(item);
+
+    match errors.check(result) {
+        Some(mut module) if errors.is_empty() => {
+            module.0.krate = krate;
+            let machines = Machines(vec![module.0]);
+
+            quote!(#machines).into()
+        }
+        _ => quote!(#errors).into(),
+    }
+}
+
+/// Derive the `State` marker trait.
+#[proc_macro_derive(State, attributes(sm))]
+pub fn derive_state(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive::marker(&input, "State")
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Derive the `InitialState` marker trait.
+#[proc_macro_derive(InitialState, attributes(sm))]
+pub fn derive_initial_state(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive::marker(&input, "InitialState")
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Derive the `Event` marker trait.
+#[proc_macro_derive(Event, attributes(sm))]
+pub fn derive_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive::marker(&input, "Event")
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Derive the `Enter` and `EnterWith` traits for a state implementing
+/// `Default`.
+#[proc_macro_derive(Enter, attributes(sm))]
+pub fn derive_enter(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive::enter(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Derive the `Machine` and `Initializer` traits for a machine tuple struct.
+#[proc_macro_derive(Machine, attributes(sm))]
+pub fn derive_machine(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive::machine(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
 }
@@ -0,0 +1,503 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use std::fmt;
+use syn::{
+    braced,
+    parse::{Parse, ParseStream, Parser, Result},
+    Attribute, Error, Ident, Token,
+};
+
+use crate::sm::{
+    error::Errors,
+    generics::Generics,
+    import::{Import, Imports},
+    initial_state::{InitialState, InitialStates},
+    machine::Machine,
+    state::State,
+    visibility::Visibility,
+};
+
+/// MachineModule is the module form of a machine, as used by the
+/// `#[sm::machine]` attribute. It is parsed into the same `Machine` as the one
+/// used by the `sm!` macro.
+#[derive(Debug, PartialEq)]
+pub(crate) struct MachineModule(pub Machine);
+
+impl Parse for MachineModule {
+    /// example machine module tokens:
+    ///
+    /// ```text
+    /// /// A turnstile.
+    /// #[allow(deprecated)]
+    /// mod TurnStile {
+    ///     use crate::input::Push;
+    ///
+    ///     enum States { #[initial] Locked, Unlocked, #[final_state] Broken }
+    ///     enum Events { Push, Coin, Break }
+    ///
+    ///     transitions! {
+    ///         Push { ... }
+    ///         Coin { ... }
+    ///         Break { ... }
+    ///     }
+    /// }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut errors = Errors::default();
+        let module = MachineModule::parse_with(input, &mut errors);
+
+        errors.finish(module)
+    }
+}
+
+impl MachineModule {
+    /// parse_with parses a machine module, adding any problems found in its
+    /// transitions, or in the states and events they use, to `errors`. See the
+    /// `Parse` implementation for an example.
+    pub(crate) fn parse_with(input: ParseStream<'_>, errors: &mut Errors) -> Result<Self> {
+        // `/// A turnstile.`
+        //  ^^^^^^^^^^^^^^^^
+        let attributes = Attributes(Attribute::parse_outer(input)?);
+
+        // `pub mod TurnStile { ... }`
+        //  ^^^ ^^^ ^^^^^^^^^
+        let visibility: Visibility = input.parse()?;
+        let _: Token![mod] = input.parse()?;
+        let name: Ident = input.parse()?;
+
+        // A module cannot be declared with generic parameters, so Rust rejects
+        // them before the attribute is expanded. They are rejected here as
+        // well, for any input that does reach the attribute.
+        if input.peek(Token![<]) {
+            return Err(input.error(
+                "a machine module cannot have generic parameters, use the `sm!` macro for a generic machine",
+            ));
+        }
+
+        // `mod TurnStile { ... }`
+        //                  ^^^
+        let block_module;
+        braced!(block_module in input);
+
+        let mut imports = Imports(Vec::new());
+        let mut states: Option<Vec<Declared>> = None;
+        let mut events: Option<Vec<Declared>> = None;
+        let mut transitions: Option<(Ident, TokenStream)> = None;
+
+        while !block_module.is_empty() {
+            if block_module.peek(Token![use]) {
+                // `use crate::input::Push;`
+                //  ^^^^^^^^^^^^^^^^^^^^^^^
+                imports.0.push(Import::parse(&block_module)?);
+            } else if block_module.peek(Token![enum]) {
+                // `enum States { ... }`
+                //  ^^^^ ^^^^^^
+                let _: Token![enum] = block_module.parse()?;
+                let block_name: Ident = block_module.parse()?;
+
+                let declared = if block_name == "States" {
+                    &mut states
+                } else if block_name == "Events" {
+                    &mut events
+                } else {
+                    return Err(Error::new(
+                        block_name.span(),
+                        "expected `enum States { ... }` or `enum Events { ... }`",
+                    ));
+                };
+
+                if declared.is_some() {
+                    return Err(Error::new(
+                        block_name.span(),
+                        format!("`enum {}` is declared more than once", block_name),
+                    ));
+                }
+
+                // `enum States { #[initial] Locked, Unlocked }`
+                //                ^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+                *declared = Some(Declared::parse_block(&block_module)?);
+            } else {
+                // `transitions! { ... }`
+                //  ^^^^^^^^^^^^
+                let block_name: Ident = block_module.parse()?;
+
+                if block_name != "transitions" {
+                    return Err(Error::new(
+                        block_name.span(),
+                        "expected `use`, `enum States`, `enum Events` or `transitions! { ... }`",
+                    ));
+                }
+
+                if transitions.is_some() {
+                    return Err(Error::new(
+                        block_name.span(),
+                        "`transitions!` is declared more than once",
+                    ));
+                }
+
+                let _: Token![!] = block_module.parse()?;
+
+                // `transitions! { ... }`
+                //                 ^^^
+                let block_transitions;
+                braced!(block_transitions in block_module);
+                transitions = Some((block_name, block_transitions.parse()?));
+            }
+        }
+
+        let (block_name, transitions) = transitions.ok_or_else(|| {
+            Error::new(
+                name.span(),
+                "expected `transitions! { ... }` in machine module",
+            )
+        })?;
+        let states = states.ok_or_else(|| {
+            Error::new(
+                name.span(),
+                "expected `enum States { ... }` in machine module",
+            )
+        })?;
+        let events = events.ok_or_else(|| {
+            Error::new(
+                name.span(),
+                "expected `enum Events { ... }` in machine module",
+            )
+        })?;
+
+        let initial_states = InitialStates(
+            states
+                .iter()
+                .filter(|s| s.initial)
+                .map(|s| InitialState {
+                    name: s.name.clone(),
+                })
+                .collect(),
+        );
+
+        if initial_states.0.is_empty() {
+            return Err(Error::new(
+                block_name.span(),
+                "expected at least one state marked as `#[initial]`",
+            ));
+        }
+
+        // `transitions! { Push { ... } Coin { ... } }`
+        //                 ^^^^^^^^^^^^^^^^^^^^^^^^^
+        let before = errors.len();
+        let parser = |input: ParseStream<'_>| {
+            Machine::parse_blocks(
+                name,
+                Generics::default(),
+                imports,
+                Some(initial_states),
+                input,
+                errors,
+            )
+        };
+        let mut machine = parser.parse2(transitions)?;
+        let parsed = errors.len() == before;
+
+        machine.attributes = attributes;
+        machine.visibility = visibility;
+        machine.event_visibilities.extend(
+            events
+                .iter()
+                .filter(|e| e.visibility != Visibility::Inherited)
+                .map(|e| (e.name.clone(), e.visibility.clone())),
+        );
+
+        for state in states
+            .iter()
+            .filter(|s| s.visibility != Visibility::Inherited)
+        {
+            errors.push(Error::new(
+                state.name.span(),
+                format!(
+                    "state `{}` cannot have a visibility, only machines and events can",
+                    state.name
+                ),
+            ));
+        }
+
+        // All states and events used by the transitions have to be declared,
+        // except for events generated by the machine itself.
+        for state in &machine.states().0 {
+            if !states.iter().any(|s| s.name == state.name) {
+                errors.push(Error::new(
+                    state.name.span(),
+                    format!("state `{}` is not declared in `enum States`", state.name),
+                ));
+            }
+        }
+
+        for event in &machine.events().0 {
+            if !machine.generates(&event.name) && !events.iter().any(|e| e.name == event.name) {
+                errors.push(Error::new(
+                    event.name.span(),
+                    format!("event `{}` is not declared in `enum Events`", event.name),
+                ));
+            }
+        }
+
+        // Unused declarations are only reported if the transitions have no
+        // other problems, as a transition that failed to parse might use them.
+        if parsed {
+            for state in &states {
+                if !machine.states().0.iter().any(|s| s.name == state.name) {
+                    errors.push(Error::new(
+                        state.name.span(),
+                        format!("state `{}` is declared but never used", state.name),
+                    ));
+                }
+            }
+
+            for event in &events {
+                if !machine.events().0.iter().any(|e| e.name == event.name) {
+                    errors.push(Error::new(
+                        event.name.span(),
+                        format!("event `{}` is declared but never used", event.name),
+                    ));
+                }
+            }
+        }
+
+        machine
+            .final_states
+            .0
+            .extend(states.iter().filter(|s| s.final_state).map(|s| State {
+                name: s.name.clone(),
+            }));
+
+        Ok(MachineModule(machine))
+    }
+}
+
+/// Declared is a state or event declared as a variant of the `States` or
+/// `Events` enum of a machine module.
+#[derive(Clone, Debug, PartialEq)]
+struct Declared {
+    visibility: Visibility,
+    name: Ident,
+    initial: bool,
+    final_state: bool,
+}
+
+impl Declared {
+    /// example declared variants tokens:
+    ///
+    /// ```text
+    /// { #[initial] Locked, Unlocked, #[final_state] Broken }
+    /// { Push, pub(crate) Coin }
+    /// ```
+    fn parse_block(input: ParseStream<'_>) -> Result<Vec<Self>> {
+        let mut declared: Vec<Declared> = Vec::new();
+
+        let block_variants;
+        braced!(block_variants in input);
+
+        while !block_variants.is_empty() {
+            // `#[initial] Locked,`
+            //  ^^^^^^^^^^
+            let mut initial = false;
+            let mut final_state = false;
+            for attr in Attribute::parse_outer(&block_variants)? {
+                if attr.path.is_ident("initial") && attr.tts.is_empty() {
+                    initial = true;
+                } else if attr.path.is_ident("final_state") && attr.tts.is_empty() {
+                    final_state = true;
+                } else if !attr.path.is_ident("doc") {
+                    return Err(Error::new_spanned(
+                        attr,
+                        "expected `#[initial]` or `#[final_state]`",
+                    ));
+                }
+            }
+
+            // `pub(crate) Coin,`
+            //  ^^^^^^^^^^
+            let visibility: Visibility = block_variants.parse()?;
+
+            // `#[initial] Locked,`
+            //             ^^^^^^
+            let name: Ident = block_variants.parse()?;
+
+            if !block_variants.is_empty() && !block_variants.peek(Token![,]) {
+                return Err(block_variants.error("expected unit variant"));
+            }
+
+            if declared.iter().any(|d| d.name == name) {
+                return Err(Error::new(
+                    name.span(),
+                    format!("`{}` is declared more than once", name),
+                ));
+            }
+
+            declared.push(Declared {
+                visibility,
+                name,
+                initial,
+                final_state,
+            });
+
+            // `#[initial] Locked,`
+            //                   ^
+            if block_variants.peek(Token![,]) {
+                let _: Token![,] = block_variants.parse()?;
+            }
+        }
+
+        Ok(declared)
+    }
+}
+
+/// Attributes are the outer attributes of a machine module, such as its doc
+/// comments, which are added to the generated module.
+#[derive(Default)]
+pub(crate) struct Attributes(pub Vec<Attribute>);
+
+impl ToTokens for Attributes {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let attributes = &self.0;
+
+        tokens.extend(quote! { #(#attributes)* });
+    }
+}
+
+impl fmt::Debug for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Attributes({})", self.into_token_stream())
+    }
+}
+
+impl PartialEq for Attributes {
+    fn eq(&self, other: &Attributes) -> bool {
+        self.into_token_stream().to_string() == other.into_token_stream().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+    use syn;
+
+    #[test]
+    fn test_machine_module_parse() {
+        let mut left: MachineModule = syn::parse2(quote! {
+            /// A turnstile.
+            #[allow(deprecated)]
+            mod TurnStile {
+                enum States { #[initial] Locked, Unlocked, #[final_state] Broken }
+                enum Events { Push, Coin, Break }
+
+                transitions! {
+                    Push { Unlocked => Locked }
+                    Coin { Locked => Unlocked }
+                    Break { Locked => Broken }
+                }
+            }
+        })
+        .unwrap();
+
+        let right: Machine = syn::parse2(quote! {
+            TurnStile {
+                InitialStates { Locked }
+                FinalStates { Broken }
+
+                Push { Unlocked => Locked }
+                Coin { Locked => Unlocked }
+                Break { Locked => Broken }
+            }
+        })
+        .unwrap();
+
+        let attributes = std::mem::take(&mut left.0.attributes);
+        assert_eq!(
+            attributes.into_token_stream().to_string(),
+            quote! {
+                #[doc = r" A turnstile."]
+                #[allow(deprecated)]
+            }
+            .to_string()
+        );
+        assert_eq!(left.0, right);
+    }
+
+    #[test]
+    fn test_machine_module_parse_generics() {
+        let result: Result<MachineModule> = syn::parse2(quote! {
+            mod Slot<T> {
+                enum States { #[initial] Empty, Full }
+                enum Events { Insert }
+
+                transitions! {
+                    Insert { Empty => Full }
+                }
+            }
+        });
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "a machine module cannot have generic parameters, use the `sm!` macro for a generic machine"
+        );
+    }
+
+    #[test]
+    fn test_machine_module_parse_visibility() {
+        let left: MachineModule = syn::parse2(quote! {
+            pub mod TurnStile {
+                enum States { #[initial] Locked, Unlocked }
+                enum Events { Push, pub(crate) Coin }
+
+                transitions! {
+                    Push { Unlocked => Locked }
+                    Coin { Locked => Unlocked }
+                }
+            }
+        })
+        .unwrap();
+
+        let right: Machine = syn::parse2(quote! {
+            pub TurnStile {
+                InitialStates { Locked }
+
+                Push { Unlocked => Locked }
+                pub(crate) Coin { Locked => Unlocked }
+            }
+        })
+        .unwrap();
+
+        assert_eq!(left.0, right);
+
+        let result: Result<MachineModule> = syn::parse2(quote! {
+            mod TurnStile {
+                enum States { #[initial] Locked, pub Unlocked }
+                enum Events { Push, Coin }
+
+                transitions! {
+                    Push { Unlocked => Locked }
+                    Coin { Locked => Unlocked }
+                }
+            }
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_machine_module_parse_undeclared() {
+        let result: Result<MachineModule> = syn::parse2(quote! {
+            mod TurnStile {
+                enum States { #[initial] Locked, Unlocked }
+                enum Events { Push }
+
+                transitions! {
+                    Push { Unlocked => Locked }
+                    Coin { Locked => Unlocked }
+                }
+            }
+        });
+
+        assert!(result.is_err());
+    }
+}
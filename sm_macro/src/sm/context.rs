@@ -0,0 +1,113 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream, Result},
+    Ident, Path,
+};
+
+use crate::sm::{generics::Arguments, import::resolve};
+
+/// Context is the type of the data shared by all states of a machine, which
+/// is stored in the machine next to its state, and carried over by every
+/// transition.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Context {
+    pub global: bool,
+    pub path: Vec<Ident>,
+    pub arguments: Arguments,
+}
+
+impl Parse for Context {
+    /// example context tokens:
+    ///
+    /// ```text
+    /// Context { crate::player::Stats<T> }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // `Context { ... }`
+        //  ^^^^^^^
+        let block_name: Ident = input.parse()?;
+
+        if block_name != "Context" {
+            return Err(input.error("expected `Context { ... }` block"));
+        }
+
+        // `Context { ... }`
+        //            ^^^
+        let block_context;
+        braced!(block_context in input);
+
+        // `Context { crate::player::Stats<T> }`
+        //            ^^^^^^^^^^^^^^^^^^^
+        let path = Path::parse_mod_style(&block_context)?;
+        let global = path.leading_colon.is_some();
+        let path: Vec<Ident> = path.segments.into_iter().map(|s| s.ident).collect();
+
+        // `Context { crate::player::Stats<T> }`
+        //                                ^^^
+        let arguments: Arguments = block_context.parse()?;
+
+        if !block_context.is_empty() {
+            return Err(block_context.error("expected a single type, such as `crate::Stats`"));
+        }
+
+        Ok(Context {
+            global,
+            path,
+            arguments,
+        })
+    }
+}
+
+impl ToTokens for Context {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let path = resolve(self.global, &self.path);
+        let arguments = &self.arguments;
+
+        tokens.extend(quote! { #path #arguments });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn;
+
+    #[test]
+    fn test_context_parse() {
+        let cases: Vec<(TokenStream, TokenStream)> = vec![
+            (quote! { Context { crate::Stats } }, quote! { crate::Stats }),
+            (
+                quote! { Context { player::Stats<T> } },
+                quote! { self::scope::player::Stats<T> },
+            ),
+            (
+                quote! { Context { ::std::string::String } },
+                quote! { ::std::string::String },
+            ),
+        ];
+
+        for (tokens, expected) in cases {
+            let context: Context = syn::parse2(tokens).unwrap();
+            assert_eq!(format!("{}", quote!(#context)), format!("{}", expected));
+        }
+    }
+
+    #[test]
+    fn test_context_parse_errors() {
+        let cases = vec![
+            (quote! { Context { } }, "unexpected end of input"),
+            (
+                quote! { Context { Stats, Config } },
+                "expected a single type",
+            ),
+        ];
+
+        for (tokens, expected) in cases {
+            let error = syn::parse2::<Context>(tokens).err().unwrap();
+
+            assert!(error.to_string().starts_with(expected), "{}", error);
+        }
+    }
+}
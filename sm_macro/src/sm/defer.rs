@@ -0,0 +1,123 @@
+use syn::{
+    braced,
+    parse::{Parse, ParseStream, Result},
+    Ident, Token,
+};
+
+use crate::sm::{event::Event, state::State};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Deferred(pub Vec<Defer>);
+
+impl Parse for Deferred {
+    /// example deferred events tokens:
+    ///
+    /// ```text
+    /// Defer { Loading: Fire, Jump  Paused: Fire }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut deferred: Vec<Defer> = Vec::new();
+
+        // `Defer { ... }`
+        //  ^^^^^
+        let block_name: Ident = input.parse()?;
+
+        if block_name != "Defer" {
+            return Err(input.error("expected `Defer { ... }` block"));
+        }
+
+        // `Defer { ... }`
+        //          ^^^
+        let block_defer;
+        braced!(block_defer in input);
+
+        while !block_defer.is_empty() {
+            // `Defer { Loading: Fire, Jump }`
+            //          ^^^^^^^
+            let state = State::parse(&block_defer)?;
+
+            // `Defer { Loading: Fire, Jump }`
+            //                 ^
+            let _: Token![:] = block_defer.parse()?;
+
+            loop {
+                // `Defer { Loading: Fire, Jump }`
+                //                   ^^^^  ^^^^
+                let event = Event::parse(&block_defer)?;
+                deferred.push(Defer {
+                    state: state.clone(),
+                    event,
+                });
+
+                // `Defer { Loading: Fire, Jump }`
+                //                       ^
+                if !block_defer.peek(Token![,]) {
+                    break;
+                }
+
+                let _: Token![,] = block_defer.parse()?;
+
+                // `Defer { Loading: Fire,  Paused: Fire }`
+                //                          ^^^^^^
+                if block_defer.is_empty() || block_defer.peek2(Token![:]) {
+                    break;
+                }
+            }
+        }
+
+        Ok(Deferred(deferred))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Defer {
+    pub state: State,
+    pub event: Event,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+    use syn::{self, parse_quote};
+
+    #[test]
+    fn test_deferred_parse() {
+        let left: Deferred = syn::parse2(quote! {
+            Defer {
+                Loading: Fire, Jump
+                Paused: Fire,
+            }
+        })
+        .unwrap();
+
+        let right = Deferred(vec![
+            Defer {
+                state: State {
+                    name: parse_quote! { Loading },
+                },
+                event: Event {
+                    name: parse_quote! { Fire },
+                },
+            },
+            Defer {
+                state: State {
+                    name: parse_quote! { Loading },
+                },
+                event: Event {
+                    name: parse_quote! { Jump },
+                },
+            },
+            Defer {
+                state: State {
+                    name: parse_quote! { Paused },
+                },
+                event: Event {
+                    name: parse_quote! { Fire },
+                },
+            },
+        ]);
+
+        assert_eq!(left, right);
+    }
+}
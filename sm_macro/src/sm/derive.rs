@@ -0,0 +1,296 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parenthesized,
+    parse::{ParseStream, Parser},
+    parse_quote, Data, DeriveInput, Error, Fields, GenericParam, Ident, Result,
+};
+
+use crate::sm::import::CratePath;
+
+/// krate returns the path to the `sm` crate, as passed to the derived type
+/// using `#[sm(crate = path)]`, or `::sm` if the attribute is missing.
+fn krate(input: &DeriveInput) -> Result<TokenStream> {
+    let mut krate: Option<CratePath> = None;
+
+    for attr in &input.attrs {
+        if attr.path.segments.len() != 1 || attr.path.segments[0].ident != "sm" {
+            continue;
+        }
+
+        if krate.is_some() {
+            return Err(Error::new(
+                attr.path.segments[0].ident.span(),
+                "duplicate `#[sm(...)]` attribute",
+            ));
+        }
+
+        // `#[sm(crate = ::engine::sm)]`
+        //      ^^^^^^^^^^^^^^^^^^^^^^^
+        let parser = |input: ParseStream<'_>| {
+            let content;
+            parenthesized!(content in input);
+            let path: CratePath = content.parse()?;
+
+            if !content.is_empty() {
+                return Err(content.error("unexpected token after the crate path"));
+            }
+
+            Ok(path)
+        };
+
+        krate = Some(parser.parse2(attr.tts.clone())?);
+    }
+
+    Ok(krate.unwrap_or_default().unresolved())
+}
+
+/// marker returns the implementation of the passed in marker trait (`State`,
+/// `Event` or `InitialState`) for the derived type.
+pub(crate) fn marker(input: &DeriveInput, name: &str) -> Result<TokenStream> {
+    let krate = krate(input)?;
+    let ty = &input.ident;
+    let trait_name = Ident::new(name, ty.span());
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #krate::#trait_name for #ty #ty_generics #where_clause {}
+    })
+}
+
+/// enter returns the implementations of the `Enter` and `EnterWith` traits for
+/// the derived state, which is constructed using `Default` whenever it is
+/// entered, regardless of the state that is left, the event or the context.
+pub(crate) fn enter(input: &DeriveInput) -> Result<TokenStream> {
+    let krate = krate(input)?;
+    let ty = &input.ident;
+
+    for param in &input.generics.params {
+        if let GenericParam::Type(param) = param {
+            if param.ident == "S" || param.ident == "E" || param.ident == "C" {
+                return Err(Error::new(
+                    param.ident.span(),
+                    format!(
+                        "`{}` is reserved for the parameters of `Enter`",
+                        param.ident
+                    ),
+                ));
+            }
+        }
+    }
+
+    let mut enter_generics = input.generics.clone();
+    enter_generics.params.push(parse_quote!(S: #krate::State));
+    enter_generics.params.push(parse_quote!(E: #krate::Event));
+
+    let mut enter_with_generics = enter_generics.clone();
+    enter_with_generics.params.push(parse_quote!(C));
+
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (enter_impl_generics, _, _) = enter_generics.split_for_impl();
+    let (enter_with_impl_generics, _, _) = enter_with_generics.split_for_impl();
+
+    Ok(quote! {
+        impl #enter_impl_generics #krate::Enter<S, E> for #ty #ty_generics #where_clause {
+            fn enter(_: &S, _: &E) -> Self {
+                ::core::default::Default::default()
+            }
+        }
+
+        impl #enter_with_impl_generics #krate::EnterWith<S, E, C> for #ty #ty_generics #where_clause {
+            fn enter_with(_: &S, _: &E, _: &mut C) -> Self {
+                ::core::default::Default::default()
+            }
+        }
+    })
+}
+
+/// machine returns the implementations of the `Machine` and `Initializer`
+/// traits for the derived machine, which has to be a tuple struct of the form
+/// `TurnStile<S: State, E: Event>(S, Option<E>)`.
+pub(crate) fn machine(input: &DeriveInput) -> Result<TokenStream> {
+    let krate = krate(input)?;
+    let ty = &input.ident;
+    let expected = || {
+        Error::new(
+            ty.span(),
+            format!(
+                "expected a tuple struct of the form `{}<S: State, E: Event>(S, Option<E>)`",
+                ty
+            ),
+        )
+    };
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Unnamed(fields) => fields,
+            _ => return Err(expected()),
+        },
+        _ => return Err(expected()),
+    };
+
+    let params = input.generics.params.iter().collect::<Vec<_>>();
+    let (state, event) = match (params.as_slice(), fields.unnamed.len()) {
+        ([GenericParam::Type(state), GenericParam::Type(event)], 2) => (&state.ident, &event.ident),
+        _ => return Err(expected()),
+    };
+
+    Ok(quote! {
+        impl<#state: #krate::State, #event: #krate::Event> #krate::Machine for #ty<#state, #event> {
+            type State = #state;
+            type Event = #event;
+
+            fn state(&self) -> Self::State {
+                self.0.clone()
+            }
+
+            fn trigger(&self) -> ::core::option::Option<Self::Event> {
+                self.1.clone()
+            }
+        }
+
+        impl<#state: #krate::InitialState> #krate::Initializer<#state> for #ty<#state, #krate::NoneEvent> {
+            type Machine = #ty<#state, #krate::NoneEvent>;
+
+            fn new(state: #state) -> Self::Machine {
+                #ty(state, ::core::option::Option::None)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker() {
+        let input: DeriveInput = parse_quote! {
+            struct Position<T: Copy>(T, T);
+        };
+
+        let left = quote! {
+            impl<T: Copy> ::sm::State for Position<T> {}
+        };
+
+        let right = marker(&input, "State").unwrap();
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+
+    #[test]
+    fn test_marker_crate_path() {
+        let input: DeriveInput = parse_quote! {
+            #[sm(crate = crate::engine::sm)]
+            struct Locked;
+        };
+
+        let left = quote! {
+            impl crate::engine::sm::InitialState for Locked {}
+        };
+
+        let right = marker(&input, "InitialState").unwrap();
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+
+    #[test]
+    fn test_marker_crate_path_invalid() {
+        let cases: Vec<DeriveInput> = vec![
+            parse_quote! {
+                #[sm(krate = ::sm)]
+                struct Locked;
+            },
+            parse_quote! {
+                #[sm(crate = ::sm, ::engine)]
+                struct Locked;
+            },
+            parse_quote! {
+                #[sm(crate = ::sm)]
+                #[sm(crate = ::engine::sm)]
+                struct Locked;
+            },
+        ];
+
+        for input in cases {
+            assert!(marker(&input, "State").is_err());
+        }
+    }
+
+    #[test]
+    fn test_enter() {
+        let input: DeriveInput = parse_quote! {
+            struct Empty<T: Item>(Vec<T>);
+        };
+
+        let left = quote! {
+            impl<T: Item, S: ::sm::State, E: ::sm::Event> ::sm::Enter<S, E> for Empty<T> {
+                fn enter(_: &S, _: &E) -> Self {
+                    ::core::default::Default::default()
+                }
+            }
+
+            impl<T: Item, S: ::sm::State, E: ::sm::Event, C> ::sm::EnterWith<S, E, C> for Empty<T> {
+                fn enter_with(_: &S, _: &E, _: &mut C) -> Self {
+                    ::core::default::Default::default()
+                }
+            }
+        };
+
+        let right = enter(&input).unwrap();
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+
+    #[test]
+    fn test_enter_reserved() {
+        let input: DeriveInput = parse_quote! {
+            struct Empty<S>(Vec<S>);
+        };
+
+        assert!(enter(&input).is_err());
+    }
+
+    #[test]
+    fn test_machine() {
+        let input: DeriveInput = parse_quote! {
+            struct TurnStile<S: State, E: Event>(S, Option<E>);
+        };
+
+        let left = quote! {
+            impl<S: ::sm::State, E: ::sm::Event> ::sm::Machine for TurnStile<S, E> {
+                type State = S;
+                type Event = E;
+
+                fn state(&self) -> Self::State {
+                    self.0.clone()
+                }
+
+                fn trigger(&self) -> ::core::option::Option<Self::Event> {
+                    self.1.clone()
+                }
+            }
+
+            impl<S: ::sm::InitialState> ::sm::Initializer<S> for TurnStile<S, ::sm::NoneEvent> {
+                type Machine = TurnStile<S, ::sm::NoneEvent>;
+
+                fn new(state: S) -> Self::Machine {
+                    TurnStile(state, ::core::option::Option::None)
+                }
+            }
+        };
+
+        let right = machine(&input).unwrap();
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+
+    #[test]
+    fn test_machine_invalid() {
+        let input: DeriveInput = parse_quote! {
+            struct TurnStile<S: State>(S);
+        };
+
+        assert!(machine(&input).is_err());
+    }
+}
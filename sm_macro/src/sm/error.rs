@@ -0,0 +1,75 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::{parse::Result, Error};
+
+/// Errors collects the errors found while parsing and validating machines, so
+/// that all of them can be reported at once, instead of only the first one.
+#[derive(Debug, Default)]
+pub(crate) struct Errors(Vec<Error>);
+
+impl Errors {
+    pub(crate) fn push(&mut self, error: Error) {
+        self.0.push(error);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// check returns the value of the passed in result, or collects its error
+    /// and returns `None`.
+    pub(crate) fn check<T>(&mut self, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.push(err);
+                None
+            }
+        }
+    }
+
+    /// finish returns the passed in result if no errors were collected, or the
+    /// first collected error otherwise. This is used where only a single error
+    /// can be returned, such as in the `Parse` implementations.
+    pub(crate) fn finish<T>(self, result: Result<T>) -> Result<T> {
+        match self.0.into_iter().next() {
+            Some(err) => Err(err),
+            None => result,
+        }
+    }
+}
+
+impl ToTokens for Errors {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        for err in &self.0 {
+            tokens.extend(err.to_compile_error());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+
+    #[test]
+    fn test_errors_finish() {
+        let mut errors = Errors::default();
+        assert_eq!(errors.finish(Ok(1)).unwrap(), 1);
+
+        errors = Errors::default();
+        assert_eq!(errors.check(Ok(1)), Some(1));
+        assert_eq!(
+            errors.check::<()>(Err(Error::new(Span::call_site(), "first"))),
+            None
+        );
+        errors.push(Error::new(Span::call_site(), "second"));
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors.finish(Ok(1)).unwrap_err().to_string(), "first");
+    }
+}
@@ -5,22 +5,58 @@ use syn::{
     Ident,
 };
 
+use crate::sm::{
+    generics::Generics,
+    import::{CratePath, Imports},
+    names::TypeNames,
+    visibility::{self, Visibility},
+};
+
 #[allow(unused)]
 #[derive(Debug)]
 pub(crate) struct Events(pub Vec<Event>);
 
 impl ToTokens for Events {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        for event in &self.0 {
-            event.to_tokens(tokens);
+        let imports = Imports(Vec::new());
+
+        self.to_tokens_with(tokens, &imports, &CratePath::default(), &[]);
+        self.enum_to_tokens(
+            tokens,
+            &[],
+            &imports,
+            &TypeNames::default().events,
+            &Generics::default(),
+        );
+    }
+}
+
+impl Events {
+    /// to_tokens_with generates the events that are not imported, with their
+    /// declared visibility.
+    pub(crate) fn to_tokens_with(
+        &self,
+        tokens: &mut TokenStream,
+        imports: &Imports,
+        krate: &CratePath,
+        visibilities: &[(Ident, Visibility)],
+    ) {
+        let local = self
+            .0
+            .iter()
+            .filter(|e| !imports.contains(&e.name))
+            .collect::<Vec<_>>();
+
+        for event in &local {
+            event.to_tokens_with(tokens, krate, &visibility::of(visibilities, &event.name));
 
             let name = &event.name;
-            for other in &self.0 {
+            for other in &local {
                 let other = &other.name;
                 let eq = name == other;
 
                 tokens.extend(quote! {
-                    impl PartialEq<#other> for #name {
+                    impl ::core::cmp::PartialEq<#other> for #name {
                         fn eq(&self, _: & #other) -> bool {
                             #eq
                         }
@@ -29,6 +65,49 @@ impl ToTokens for Events {
             }
         }
     }
+
+    /// enum_to_tokens generates an enum of all events named `events`, adding
+    /// a variant to the enum for each of the passed in nested event enums. The
+    /// enum has the passed in generic parameters, which are those used by the
+    /// imported events.
+    pub(crate) fn enum_to_tokens(
+        &self,
+        tokens: &mut TokenStream,
+        nested: &[(Ident, TokenStream)],
+        imports: &Imports,
+        events: &Ident,
+        generics: &Generics,
+    ) {
+        let names = &self.0.iter().map(|e| &e.name).collect::<Vec<_>>();
+        let types = &self
+            .0
+            .iter()
+            .map(|e| imports.ty(&e.name))
+            .collect::<Vec<_>>();
+        let (variants, types_again) = (names, types);
+        let enums = &vec![events; names.len()];
+        let enums_again = enums;
+        let impl_generics = vec![generics; names.len()];
+        let applied = vec![generics.apply(); names.len()];
+        let nested_variants = nested.iter().map(|(name, _)| name).collect::<Vec<_>>();
+        let nested_types = nested.iter().map(|(_, ty)| ty).collect::<Vec<_>>();
+
+        tokens.extend(quote! {
+            #[derive(Debug, Eq, PartialEq, Clone)]
+            pub enum #events #generics {
+                #(#variants(#types)),*
+                #(, #nested_variants(#nested_types))*
+            }
+
+            #(
+                impl #impl_generics ::core::convert::From<#types> for #enums #applied {
+                    fn from(event: #types_again) -> Self {
+                        #enums_again::#variants(event)
+                    }
+                }
+            )*
+        });
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -51,12 +130,25 @@ impl Parse for Event {
 
 impl ToTokens for Event {
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.to_tokens_with(tokens, &CratePath::default(), &Visibility::Public);
+    }
+}
+
+impl Event {
+    /// to_tokens_with generates the event with the passed in visibility,
+    /// implementing the `Event` trait of the crate at the passed in path.
+    pub(crate) fn to_tokens_with(
+        &self,
+        tokens: &mut TokenStream,
+        krate: &CratePath,
+        visibility: &Visibility,
+    ) {
         let name = &self.name;
 
         tokens.extend(quote! {
             #[derive(Clone, Copy, Debug, Eq)]
-            pub struct #name;
-            impl Event for #name {}
+            #visibility struct #name;
+            impl #krate::Event for #name {}
         });
     }
 }
@@ -86,7 +178,7 @@ mod tests {
         let left = quote! {
             #[derive(Clone, Copy, Debug, Eq)]
             pub struct Push;
-            impl Event for Push {}
+            impl ::sm::Event for Push {}
         };
 
         let mut right = TokenStream::new();
@@ -109,15 +201,15 @@ mod tests {
         let left = quote! {
             #[derive(Clone, Copy, Debug, Eq)]
             pub struct Push;
-            impl Event for Push {}
+            impl ::sm::Event for Push {}
 
-            impl PartialEq<Push> for Push {
+            impl ::core::cmp::PartialEq<Push> for Push {
                 fn eq(&self, _: &Push) -> bool {
                     true
                 }
             }
 
-            impl PartialEq<Coin> for Push {
+            impl ::core::cmp::PartialEq<Coin> for Push {
                 fn eq(&self, _: &Coin) -> bool {
                     false
                 }
@@ -125,19 +217,37 @@ mod tests {
 
             #[derive(Clone, Copy, Debug, Eq)]
             pub struct Coin;
-            impl Event for Coin {}
+            impl ::sm::Event for Coin {}
 
-            impl PartialEq<Push> for Coin {
+            impl ::core::cmp::PartialEq<Push> for Coin {
                 fn eq(&self, _: & Push) -> bool {
                     false
                 }
             }
 
-            impl PartialEq<Coin> for Coin {
+            impl ::core::cmp::PartialEq<Coin> for Coin {
                 fn eq(&self, _: & Coin) -> bool {
                     true
                 }
             }
+
+            #[derive(Debug, Eq, PartialEq, Clone)]
+            pub enum Events {
+                Push(Push),
+                Coin(Coin)
+            }
+
+            impl ::core::convert::From<Push> for Events {
+                fn from(event: Push) -> Self {
+                    Events::Push(event)
+                }
+            }
+
+            impl ::core::convert::From<Coin> for Events {
+                fn from(event: Coin) -> Self {
+                    Events::Coin(event)
+                }
+            }
         };
 
         let mut right = TokenStream::new();
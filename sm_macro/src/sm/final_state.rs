@@ -0,0 +1,81 @@
+use syn::{
+    braced,
+    parse::{Parse, ParseStream, Result},
+    punctuated::Punctuated,
+    Ident, Token,
+};
+
+use crate::sm::state::State;
+
+/// FinalStates are the states in which a machine is allowed to end, without
+/// any outgoing transitions.
+#[derive(Debug, PartialEq)]
+pub(crate) struct FinalStates(pub Vec<State>);
+
+impl FinalStates {
+    /// contains returns whether the passed in state is marked as final.
+    pub(crate) fn contains(&self, state: &Ident) -> bool {
+        self.0.iter().any(|s| &s.name == state)
+    }
+}
+
+impl Parse for FinalStates {
+    /// example final states tokens:
+    ///
+    /// ```text
+    /// FinalStates { Broken, Recycled }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut final_states: Vec<State> = Vec::new();
+
+        // `FinalStates { ... }`
+        //  ^^^^^^^^^^^
+        let block_name: Ident = input.parse()?;
+
+        if block_name != "FinalStates" {
+            return Err(input.error("expected `FinalStates { ... }` block"));
+        }
+
+        // `FinalStates { ... }`
+        //                ^^^
+        let block_final_states;
+        braced!(block_final_states in input);
+
+        // `FinalStates { Broken, Recycled }`
+        //                ^^^^^^  ^^^^^^^^
+        let punctuated_final_states: Punctuated<Ident, Token![,]> =
+            block_final_states.parse_terminated(Ident::parse)?;
+
+        for name in punctuated_final_states {
+            final_states.push(State { name });
+        }
+
+        Ok(FinalStates(final_states))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+    use syn::{parse2, parse_quote};
+
+    #[test]
+    fn test_final_states_parse() {
+        let left: FinalStates = parse2(quote! {
+            FinalStates { Broken, Recycled }
+        })
+        .unwrap();
+
+        let right = FinalStates(vec![
+            State {
+                name: parse_quote! { Broken },
+            },
+            State {
+                name: parse_quote! { Recycled },
+            },
+        ]);
+
+        assert_eq!(left, right);
+    }
+}
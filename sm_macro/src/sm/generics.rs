@@ -0,0 +1,379 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use std::fmt;
+use syn::{
+    parse::{Parse, ParseStream, Result},
+    AngleBracketedGenericArguments, Error, GenericParam, Ident, Token,
+};
+
+/// RESERVED are the names of the type parameters used by the generated code,
+/// which therefore cannot be used as the generic parameters of a machine.
+const RESERVED: &[&str] = &["S", "E", "V", "C", "H", "M"];
+
+/// Generics are the generic parameters of a machine, such as `<T: Item>`.
+/// They are added to the generated machine, its variants and every
+/// implementation for it, while the other generated types and implementations
+/// only get the parameters used by the types they refer to.
+#[derive(Clone, Default)]
+pub(crate) struct Generics(pub Vec<GenericParam>);
+
+impl Parse for Generics {
+    /// example generics tokens:
+    ///
+    /// ```text
+    /// <'a, T: Item>
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        if !input.peek(Token![<]) {
+            return Ok(Generics::default());
+        }
+
+        let generics: syn::Generics = input.parse()?;
+
+        for param in &generics.params {
+            match param {
+                GenericParam::Type(param) if RESERVED.iter().any(|r| param.ident == r) => {
+                    return Err(Error::new(
+                        param.ident.span(),
+                        format!(
+                            "generic parameter `{}` is used by the generated code, the names {} are reserved",
+                            param.ident,
+                            RESERVED
+                                .iter()
+                                .map(|r| format!("`{}`", r))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    ));
+                }
+                GenericParam::Type(param) if param.default.is_some() => {
+                    return Err(Error::new(
+                        param.ident.span(),
+                        format!("generic parameter `{}` cannot have a default", param.ident),
+                    ));
+                }
+                GenericParam::Const(param) => {
+                    return Err(Error::new(
+                        param.ident.span(),
+                        format!(
+                            "const parameter `{}` is not supported, only lifetimes and types are",
+                            param.ident
+                        ),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        if input.peek(Token![where]) {
+            return Err(input.error(
+                "a machine cannot have a `where` clause, declare the bounds on its parameters instead",
+            ));
+        }
+
+        Ok(Generics(generics.params.into_iter().collect()))
+    }
+}
+
+impl ToTokens for Generics {
+    /// to_tokens generates the parameters in angle brackets, as declared by a
+    /// type or implementation, or nothing if there are none.
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        if !self.is_empty() {
+            let params = self.params();
+
+            tokens.extend(quote! { <#params> });
+        }
+    }
+}
+
+impl fmt::Debug for Generics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Generics({})", self.into_token_stream())
+    }
+}
+
+impl PartialEq for Generics {
+    fn eq(&self, other: &Generics) -> bool {
+        self.into_token_stream().to_string() == other.into_token_stream().to_string()
+    }
+}
+
+impl Generics {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// params returns the parameters with their bounds, each followed by a
+    /// comma, to be put in front of the parameters of the generated code. Type
+    /// parameters are also bound by the traits required by the states and
+    /// events, which the generated types derive.
+    pub(crate) fn params(&self) -> TokenStream {
+        let mut tokens = TokenStream::new();
+
+        for param in &self.0 {
+            match param {
+                GenericParam::Type(param) => {
+                    let ident = &param.ident;
+                    let bounds = param.bounds.iter();
+
+                    tokens.extend(quote! {
+                        #ident: #(#bounds +)* ::core::fmt::Debug + ::core::clone::Clone + ::core::cmp::Eq,
+                    });
+                }
+                param => tokens.extend(quote! { #param, }),
+            }
+        }
+
+        tokens
+    }
+
+    /// args returns the names of the parameters, each followed by a comma, to
+    /// be put in front of the arguments of a generated type.
+    pub(crate) fn args(&self) -> TokenStream {
+        let mut tokens = TokenStream::new();
+
+        for param in &self.0 {
+            match param {
+                GenericParam::Type(param) => param.ident.to_tokens(&mut tokens),
+                GenericParam::Lifetime(param) => param.lifetime.to_tokens(&mut tokens),
+                GenericParam::Const(param) => param.ident.to_tokens(&mut tokens),
+            }
+
+            tokens.extend(quote! { , });
+        }
+
+        tokens
+    }
+
+    /// apply returns the names of the parameters in angle brackets, as used
+    /// to refer to a generated type, or nothing if there are none.
+    pub(crate) fn apply(&self) -> TokenStream {
+        if self.is_empty() {
+            return TokenStream::new();
+        }
+
+        let args = self.args();
+
+        quote! { <#args> }
+    }
+
+    /// phantom returns the field that marks the parameters as used by the
+    /// machine, or nothing if there are none.
+    pub(crate) fn phantom(&self) -> TokenStream {
+        if self.is_empty() {
+            return TokenStream::new();
+        }
+
+        let types = self.0.iter().map(|param| match param {
+            GenericParam::Lifetime(param) => {
+                let lifetime = &param.lifetime;
+                quote! { &#lifetime () }
+            }
+            GenericParam::Type(param) => param.ident.clone().into_token_stream(),
+            GenericParam::Const(param) => param.ident.clone().into_token_stream(),
+        });
+
+        quote! { , ::core::marker::PhantomData<(#(#types,)*)> }
+    }
+
+    /// phantom_new returns the value of the field returned by `phantom`.
+    pub(crate) fn phantom_new(&self) -> TokenStream {
+        if self.is_empty() {
+            return TokenStream::new();
+        }
+
+        quote! { , ::core::marker::PhantomData }
+    }
+
+    /// bounds_use returns whether the bounds of the parameters refer to the
+    /// passed in name, such as a trait imported by the machine.
+    pub(crate) fn bounds_use(&self, name: &Ident) -> bool {
+        let mut names = Vec::new();
+        collect_names(self.params(), &mut names);
+
+        names.contains(&name.to_string())
+    }
+
+    /// used_by returns the parameters used by the passed in types, including
+    /// the parameters used by the bounds of those parameters.
+    pub(crate) fn used_by(&self, types: &[&TokenStream]) -> Generics {
+        let mut names = Vec::new();
+        for ty in types {
+            collect_names((*ty).clone(), &mut names);
+        }
+
+        let mut used = vec![false; self.0.len()];
+
+        loop {
+            let mut changed = false;
+
+            for (i, param) in self.0.iter().enumerate() {
+                if used[i] || !names.contains(&name(param)) {
+                    continue;
+                }
+
+                used[i] = true;
+                changed = true;
+                collect_names(param.into_token_stream(), &mut names);
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        Generics(
+            self.0
+                .iter()
+                .zip(used)
+                .filter(|(_, used)| *used)
+                .map(|(param, _)| param.clone())
+                .collect(),
+        )
+    }
+}
+
+/// name returns the name of the parameter, as it appears in a type.
+fn name(param: &GenericParam) -> String {
+    match param {
+        GenericParam::Type(param) => param.ident.to_string(),
+        GenericParam::Lifetime(param) => format!("'{}", param.lifetime.ident),
+        GenericParam::Const(param) => param.ident.to_string(),
+    }
+}
+
+/// collect_names adds the identifiers and lifetimes in the passed in tokens to
+/// `names`.
+fn collect_names(tokens: TokenStream, names: &mut Vec<String>) {
+    let mut lifetime = false;
+
+    for tt in tokens {
+        match tt {
+            TokenTree::Group(group) => collect_names(group.stream(), names),
+            TokenTree::Ident(ident) if lifetime => names.push(format!("'{}", ident)),
+            TokenTree::Ident(ident) => names.push(ident.to_string()),
+            TokenTree::Punct(punct) => {
+                lifetime = punct.as_char() == '\'';
+                continue;
+            }
+            TokenTree::Literal(_) => {}
+        }
+
+        lifetime = false;
+    }
+}
+
+/// Arguments are the generic arguments of an imported type, such as the `<T>`
+/// in `use crate::inventory::Holding<T>;`, which are added wherever the
+/// generated code refers to the type.
+#[derive(Clone, Default)]
+pub(crate) struct Arguments(pub Option<AngleBracketedGenericArguments>);
+
+impl Parse for Arguments {
+    /// example arguments tokens:
+    ///
+    /// ```text
+    /// <'a, T>
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        if input.peek(Token![<]) {
+            Ok(Arguments(Some(input.parse()?)))
+        } else {
+            Ok(Arguments(None))
+        }
+    }
+}
+
+impl ToTokens for Arguments {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.0.to_tokens(tokens);
+    }
+}
+
+impl fmt::Debug for Arguments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Arguments({})", self.into_token_stream())
+    }
+}
+
+impl PartialEq for Arguments {
+    fn eq(&self, other: &Arguments) -> bool {
+        self.into_token_stream().to_string() == other.into_token_stream().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn;
+
+    #[test]
+    fn test_generics_parse() {
+        let generics: Generics = syn::parse2(quote! { <'a, T: Item> }).unwrap();
+
+        assert_eq!(
+            format!("{}", generics.params()),
+            format!(
+                "{}",
+                quote! { 'a, T: Item + ::core::fmt::Debug + ::core::clone::Clone + ::core::cmp::Eq, }
+            )
+        );
+        assert_eq!(
+            format!("{}", generics.args()),
+            format!("{}", quote! { 'a, T, })
+        );
+        assert_eq!(
+            format!("{}", generics.phantom()),
+            format!("{}", quote! { , ::core::marker::PhantomData<(&'a (), T,)> })
+        );
+
+        let generics: Generics = syn::parse2(quote! {}).unwrap();
+        assert!(generics.is_empty());
+        assert!(generics.phantom().is_empty());
+    }
+
+    #[test]
+    fn test_generics_parse_errors() {
+        let cases = vec![
+            (
+                quote! { <S> },
+                "generic parameter `S` is used by the generated code",
+            ),
+            (
+                quote! { <T = u32> },
+                "generic parameter `T` cannot have a default",
+            ),
+            (
+                quote! { <const N: usize> },
+                "const parameter `N` is not supported",
+            ),
+            (
+                quote! { <T> where T: Item },
+                "a machine cannot have a `where` clause",
+            ),
+        ];
+
+        for (tokens, expected) in cases {
+            let error = syn::parse2::<Generics>(tokens).err().unwrap();
+
+            assert!(error.to_string().starts_with(expected), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_generics_used_by() {
+        let generics: Generics = syn::parse2(quote! { <'a, T: Into<U>, U, W> }).unwrap();
+        let holding = quote! { Holding<'a, T> };
+        let push = quote! { Push };
+
+        let used = generics.used_by(&[&holding, &push]);
+        assert_eq!(
+            format!("{}", used.args()),
+            format!("{}", quote! { 'a, T, U, })
+        );
+
+        let used = generics.used_by(&[&push]);
+        assert!(used.is_empty());
+    }
+}
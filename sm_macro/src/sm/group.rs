@@ -0,0 +1,275 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    braced,
+    parse::{Parse, ParseStream, Result},
+    Ident, Token,
+};
+
+use crate::sm::{
+    generics::Generics,
+    import::{CratePath, Imports},
+    names::TypeNames,
+    state::State,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Groups(pub Vec<Group>);
+
+impl Groups {
+    /// find returns the group with the passed in name, if any.
+    pub(crate) fn find(&self, name: &Ident) -> Option<&Group> {
+        self.0.iter().find(|g| &g.name == name)
+    }
+
+    /// expand returns the member states of the group with the name of the
+    /// passed in state, or the state itself if it does not name a group.
+    pub(crate) fn expand(&self, state: &State) -> Vec<State> {
+        match self.find(&state.name) {
+            Some(group) => group.states.clone(),
+            None => vec![state.clone()],
+        }
+    }
+}
+
+impl Parse for Groups {
+    /// example groups tokens:
+    ///
+    /// ```text
+    /// Groups { Combat: Attacking, Blocking  Moving: Walking }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut groups: Vec<Group> = Vec::new();
+
+        // `Groups { ... }`
+        //  ^^^^^^
+        let block_name: Ident = input.parse()?;
+
+        if block_name != "Groups" {
+            return Err(input.error("expected `Groups { ... }` block"));
+        }
+
+        // `Groups { ... }`
+        //           ^^^
+        let block_groups;
+        braced!(block_groups in input);
+
+        while !block_groups.is_empty() {
+            // `Groups { Combat: Attacking, Blocking }`
+            //           ^^^^^^
+            let name: Ident = block_groups.parse()?;
+
+            // `Groups { Combat: Attacking, Blocking }`
+            //                 ^
+            let _: Token![:] = block_groups.parse()?;
+
+            let mut states: Vec<State> = Vec::new();
+
+            loop {
+                // `Groups { Combat: Attacking, Blocking }`
+                //                   ^^^^^^^^^  ^^^^^^^^
+                states.push(State::parse(&block_groups)?);
+
+                // `Groups { Combat: Attacking, Blocking }`
+                //                            ^
+                if !block_groups.peek(Token![,]) {
+                    break;
+                }
+
+                let _: Token![,] = block_groups.parse()?;
+
+                // `Groups { Combat: Attacking,  Moving: Walking }`
+                //                               ^^^^^^
+                if block_groups.is_empty() || block_groups.peek2(Token![:]) {
+                    break;
+                }
+            }
+
+            groups.push(Group { name, states });
+        }
+
+        Ok(Groups(groups))
+    }
+}
+
+impl ToTokens for Groups {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let machine = TypeNames::default().machine;
+
+        self.to_tokens_with(
+            tokens,
+            &Imports(Vec::new()),
+            &machine,
+            &CratePath::default(),
+            &Generics::default(),
+        );
+    }
+}
+
+impl Groups {
+    /// to_tokens_with generates the group traits and the `Group` enum, with
+    /// the traits bound by the `State` trait of the crate at the passed in
+    /// path. Imported states are referred to using their generic arguments.
+    pub(crate) fn to_tokens_with(
+        &self,
+        tokens: &mut TokenStream,
+        imports: &Imports,
+        machine: &Ident,
+        krate: &CratePath,
+        generics: &Generics,
+    ) {
+        if self.0.is_empty() {
+            return;
+        }
+
+        let names = self.0.iter().map(|g| &g.name).collect::<Vec<_>>();
+
+        for group in &self.0 {
+            group.to_tokens_with(tokens, imports, machine, krate, generics);
+        }
+
+        tokens.extend(quote! {
+            #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+            pub enum Group {
+                #(#names),*
+            }
+        });
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Group {
+    pub name: Ident,
+    pub states: Vec<State>,
+}
+
+impl Group {
+    /// contains returns whether the passed in state is a member of the group.
+    pub(crate) fn contains(&self, state: &Ident) -> bool {
+        self.states.iter().any(|s| &s.name == state)
+    }
+
+    /// capability returns the name of the capability trait of the group,
+    /// which is implemented by the machine in any of the states of the group.
+    pub(crate) fn capability(&self) -> Ident {
+        Ident::new(&format!("In{}", self.name), self.name.span())
+    }
+}
+
+impl ToTokens for Group {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let machine = TypeNames::default().machine;
+
+        self.to_tokens_with(
+            tokens,
+            &Imports(Vec::new()),
+            &machine,
+            &CratePath::default(),
+            &Generics::default(),
+        );
+    }
+}
+
+impl Group {
+    /// to_tokens_with generates the marker trait of the group, implemented by
+    /// each of its member states, with the generic parameters used by the
+    /// state, and the capability trait of the group, implemented by the
+    /// machine in any of those states.
+    pub(crate) fn to_tokens_with(
+        &self,
+        tokens: &mut TokenStream,
+        imports: &Imports,
+        machine: &Ident,
+        krate: &CratePath,
+        generics: &Generics,
+    ) {
+        let name = &self.name;
+        let capability = self.capability();
+        let (params, args) = (generics.params(), generics.args());
+        let names = vec![name; self.states.len()];
+        let states = self
+            .states
+            .iter()
+            .map(|s| imports.ty(&s.name))
+            .collect::<Vec<_>>();
+        let states_generics = states
+            .iter()
+            .map(|s| generics.used_by(&[s]))
+            .collect::<Vec<_>>();
+
+        tokens.extend(quote! {
+            pub trait #name: #krate::State {}
+
+            #(impl #states_generics #names for #states {})*
+
+            pub trait #capability: #krate::Machine {}
+
+            impl<#params S: #name, E: #krate::Event> #capability for #machine<#args S, E> {}
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::{self, parse_quote};
+
+    #[test]
+    fn test_groups_parse() {
+        let left: Groups = syn::parse2(quote! {
+            Groups { Combat: Attacking, Blocking  Moving: Walking }
+        })
+        .unwrap();
+
+        let right = Groups(vec![
+            Group {
+                name: parse_quote! { Combat },
+                states: vec![
+                    State {
+                        name: parse_quote! { Attacking },
+                    },
+                    State {
+                        name: parse_quote! { Blocking },
+                    },
+                ],
+            },
+            Group {
+                name: parse_quote! { Moving },
+                states: vec![State {
+                    name: parse_quote! { Walking },
+                }],
+            },
+        ]);
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_groups_to_tokens() {
+        let groups: Groups = syn::parse2(quote! {
+            Groups { Combat: Attacking, Blocking }
+        })
+        .unwrap();
+
+        let left = quote! {
+            pub trait Combat: ::sm::State {}
+
+            impl Combat for Attacking {}
+            impl Combat for Blocking {}
+
+            pub trait InCombat: ::sm::Machine {}
+
+            impl<S: Combat, E: ::sm::Event> InCombat for Machine<S, E> {}
+
+            #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+            pub enum Group {
+                Combat
+            }
+        };
+
+        let mut right = TokenStream::new();
+        groups.to_tokens(&mut right);
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+}
@@ -0,0 +1,360 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream, Result},
+    Ident, Path, Token,
+};
+
+use crate::sm::generics::Arguments;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Imports(pub Vec<Import>);
+
+impl Imports {
+    /// contains returns whether the passed in state or event name refers to an
+    /// imported type.
+    pub(crate) fn contains(&self, name: &Ident) -> bool {
+        self.0.iter().any(|i| &i.name == name)
+    }
+
+    /// ty returns the type of the passed in state or event, including the
+    /// generic arguments of an imported type.
+    pub(crate) fn ty(&self, name: &Ident) -> TokenStream {
+        match self.0.iter().find(|i| &i.name == name) {
+            Some(import) => {
+                let arguments = &import.arguments;
+                quote! { #name #arguments }
+            }
+            None => quote! { #name },
+        }
+    }
+}
+
+impl Parse for Imports {
+    /// example imports tokens:
+    ///
+    /// ```text
+    /// use crate::input::Jump;
+    /// use crate::physics::Position as Standing;
+    /// use crate::inventory::Holding<T>;
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut imports: Vec<Import> = Vec::new();
+
+        while input.peek(Token![use]) {
+            imports.push(Import::parse(input)?);
+        }
+
+        Ok(Imports(imports))
+    }
+}
+
+impl ToTokens for Imports {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        for import in &self.0 {
+            import.to_tokens(tokens);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Import {
+    pub global: bool,
+    pub path: Vec<Ident>,
+    pub arguments: Arguments,
+    pub name: Ident,
+}
+
+impl Parse for Import {
+    /// example import tokens:
+    ///
+    /// ```text
+    /// use crate::input::Jump as Jump;
+    /// use crate::inventory::Holding<T>;
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // `use crate::input::Jump as Jump;`
+        //  ^^^
+        let _: Token![use] = input.parse()?;
+
+        // `use crate::input::Jump as Jump;`
+        //      ^^^^^^^^^^^^^^^^^^
+        let path = Path::parse_mod_style(input)?;
+        let global = path.leading_colon.is_some();
+        let path: Vec<Ident> = path.segments.into_iter().map(|s| s.ident).collect();
+
+        // `use crate::inventory::Holding<T>;`
+        //                               ^^^
+        let arguments: Arguments = input.parse()?;
+
+        // `use crate::input::Jump as Jump;`
+        //                         ^^ ^^^^
+        let name = if input.peek(Token![as]) {
+            let _: Token![as] = input.parse()?;
+            input.parse()?
+        } else {
+            path.last().expect("path has at least one segment").clone()
+        };
+
+        // `use crate::input::Jump as Jump;`
+        //                                ^
+        let _: Token![;] = input.parse()?;
+
+        Ok(Import {
+            global,
+            path,
+            arguments,
+            name,
+        })
+    }
+}
+
+impl ToTokens for Import {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        // A type in the scope of the macro invocation cannot be re-exported
+        // through the `scope` module, as it is only visible to the machine.
+        let path = match self.path.as_slice() {
+            [ty] if !self.global => quote! { super::#ty },
+            path => resolve(self.global, path),
+        };
+        let name = &self.name;
+
+        tokens.extend(quote! {
+            pub use #path as #name;
+        });
+    }
+}
+
+/// CratePath is the path to the `sm` crate, as used by the generated code. It
+/// defaults to `::sm`, and can be changed using `crate = path`, for when the
+/// crate is renamed, or re-exported by another crate.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CratePath {
+    pub global: bool,
+    pub path: Vec<Ident>,
+}
+
+impl Default for CratePath {
+    fn default() -> Self {
+        CratePath {
+            global: true,
+            path: vec![Ident::new("sm", Span::call_site())],
+        }
+    }
+}
+
+impl Parse for CratePath {
+    /// example crate path tokens:
+    ///
+    /// ```text
+    /// crate = ::engine::sm
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        // `crate = ::engine::sm`
+        //  ^^^^^ ^
+        let _: Token![crate] = input.parse()?;
+        let _: Token![=] = input.parse()?;
+
+        // `crate = ::engine::sm`
+        //          ^^^^^^^^^^^^
+        let path = Path::parse_mod_style(input)?;
+        let global = path.leading_colon.is_some();
+        let path: Vec<Ident> = path.segments.into_iter().map(|s| s.ident).collect();
+
+        Ok(CratePath { global, path })
+    }
+}
+
+impl CratePath {
+    /// unresolved returns the path as it was written, for code that is
+    /// generated in the scope of the item it was passed to, such as the
+    /// implementations of the derive macros.
+    pub(crate) fn unresolved(&self) -> TokenStream {
+        let first = &self.path[0];
+        let rest = &self.path[1..];
+
+        if self.global {
+            quote! { ::#first #(::#rest)* }
+        } else {
+            quote! { #first #(::#rest)* }
+        }
+    }
+}
+
+impl ToTokens for CratePath {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(resolve(self.global, &self.path));
+    }
+}
+
+/// resolve returns the passed in path, as seen from within the module of a
+/// generated machine.
+///
+/// A path starting with a name, such as `physics::Position`, is resolved
+/// through the `scope` module, as that name can refer to an item in the scope
+/// of the macro invocation as well as to another crate.
+pub(crate) fn resolve(global: bool, path: &[Ident]) -> TokenStream {
+    let first = &path[0];
+    let rest = &path[1..];
+
+    if global {
+        quote! { ::#first #(::#rest)* }
+    } else if first == "crate" {
+        quote! { #first #(::#rest)* }
+    } else if first == "self" {
+        quote! { super #(::#rest)* }
+    } else if first == "super" {
+        quote! { super::#first #(::#rest)* }
+    } else {
+        quote! { self::scope::#first #(::#rest)* }
+    }
+}
+
+/// scope returns the `scope` module of a generated machine, which makes the
+/// first names of the passed in paths available to `resolve`, whether they
+/// refer to an item in the scope of the macro invocation, or to another crate.
+///
+/// The items of the invocation scope are imported into this module rather
+/// than into the module of the machine, so that they cannot shadow the names
+/// used by the generated code.
+#[allow(single_use_lifetimes)]
+pub(crate) fn scope<'a>(paths: impl IntoIterator<Item = (bool, &'a [Ident])>) -> TokenStream {
+    let mut names: Vec<&Ident> = Vec::new();
+
+    for (global, path) in paths {
+        let first = &path[0];
+
+        if global || first == "crate" || first == "self" || first == "super" {
+            continue;
+        }
+
+        if !names.contains(&first) {
+            names.push(first);
+        }
+    }
+
+    if names.is_empty() {
+        return TokenStream::new();
+    }
+
+    quote! {
+        mod scope {
+            #[allow(unused_imports)]
+            pub(super) use super::super::*;
+            #(
+                #[allow(unused_imports)]
+                pub(super) use #names;
+            )*
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::{self, parse_quote};
+
+    #[test]
+    fn test_imports_parse() {
+        let left: Imports = syn::parse2(quote! {
+            use crate::input::Jump;
+            use physics::Position as Standing;
+            use inventory::Holding<T>;
+        })
+        .unwrap();
+
+        let right = Imports(vec![
+            Import {
+                global: false,
+                path: vec![
+                    Ident::new("crate", Span::call_site()),
+                    parse_quote! { input },
+                    parse_quote! { Jump },
+                ],
+                arguments: Arguments::default(),
+                name: parse_quote! { Jump },
+            },
+            Import {
+                global: false,
+                path: vec![parse_quote! { physics }, parse_quote! { Position }],
+                arguments: Arguments::default(),
+                name: parse_quote! { Standing },
+            },
+            Import {
+                global: false,
+                path: vec![parse_quote! { inventory }, parse_quote! { Holding }],
+                arguments: Arguments(Some(parse_quote! { <T> })),
+                name: parse_quote! { Holding },
+            },
+        ]);
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_import_to_tokens() {
+        let import: Import = syn::parse2(quote! {
+            use physics::Position as Standing;
+        })
+        .unwrap();
+
+        let left = quote! {
+            pub use self::scope::physics::Position as Standing;
+        };
+
+        let mut right = TokenStream::new();
+        import.to_tokens(&mut right);
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+
+    #[test]
+    fn test_crate_path() {
+        let cases: Vec<(TokenStream, TokenStream)> = vec![
+            (quote! { crate = ::engine::sm }, quote! { ::engine::sm }),
+            (quote! { crate = crate::sm }, quote! { crate::sm }),
+            (
+                quote! { crate = engine::sm },
+                quote! { self::scope::engine::sm },
+            ),
+            (quote! { crate = self::sm }, quote! { super::sm }),
+            (quote! { crate = super::sm }, quote! { super::super::sm }),
+        ];
+
+        for (tokens, expected) in cases {
+            let path: CratePath = syn::parse2(tokens).unwrap();
+            assert_eq!(format!("{}", quote!(#path)), format!("{}", expected));
+        }
+
+        let path = CratePath::default();
+        assert_eq!(format!("{}", quote!(#path)), format!("{}", quote! { ::sm }));
+    }
+
+    #[test]
+    fn test_scope() {
+        let ident = |name| Ident::new(name, Span::call_site());
+        let paths: Vec<(bool, Vec<Ident>)> = vec![
+            (false, vec![ident("physics"), ident("Position")]),
+            (false, vec![ident("crate"), ident("Jump")]),
+            (true, vec![ident("engine"), ident("sm")]),
+            (false, vec![ident("physics"), ident("Speed")]),
+            (false, vec![ident("game"), ident("Falling")]),
+        ];
+
+        let left = quote! {
+            mod scope {
+                #[allow(unused_imports)]
+                pub(super) use super::super::*;
+                #[allow(unused_imports)]
+                pub(super) use physics;
+                #[allow(unused_imports)]
+                pub(super) use game;
+            }
+        };
+
+        let right = scope(paths.iter().map(|(g, p)| (*g, p.as_slice())));
+
+        assert_eq!(format!("{}", left), format!("{}", right));
+        assert!(scope(vec![(true, paths[2].1.as_slice())]).is_empty());
+    }
+}
@@ -7,6 +7,8 @@ use syn::{
     Ident, Token,
 };
 
+use crate::sm::import::CratePath;
+
 #[derive(Debug, PartialEq)]
 pub(crate) struct InitialStates(pub Vec<InitialState>);
 
@@ -47,8 +49,16 @@ impl Parse for InitialStates {
 
 impl ToTokens for InitialStates {
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.to_tokens_with(tokens, &CratePath::default());
+    }
+}
+
+impl InitialStates {
+    /// to_tokens_with generates the initial states, implementing the traits
+    /// of the crate at the passed in path.
+    pub(crate) fn to_tokens_with(&self, tokens: &mut TokenStream, krate: &CratePath) {
         for state in &self.0 {
-            state.to_tokens(tokens);
+            state.to_tokens_with(tokens, krate);
         }
     }
 }
@@ -73,10 +83,18 @@ impl Parse for InitialState {
 
 impl ToTokens for InitialState {
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.to_tokens_with(tokens, &CratePath::default());
+    }
+}
+
+impl InitialState {
+    /// to_tokens_with generates the `InitialState` implementation of the state,
+    /// for the trait of the crate at the passed in path.
+    pub(crate) fn to_tokens_with(&self, tokens: &mut TokenStream, krate: &CratePath) {
         let name = &self.name;
 
         tokens.extend(quote! {
-            impl InitialState for #name {}
+            impl #krate::InitialState for #name {}
         });
     }
 }
@@ -104,7 +122,7 @@ mod tests {
         };
 
         let left = quote! {
-            impl InitialState for Unlocked {}
+            impl ::sm::InitialState for Unlocked {}
         };
 
         let mut right = TokenStream::new();
@@ -144,8 +162,8 @@ mod tests {
         ]);
 
         let left = quote! {
-            impl InitialState for Locked {}
-            impl InitialState for Unlocked {}
+            impl ::sm::InitialState for Locked {}
+            impl ::sm::InitialState for Unlocked {}
         };
 
         let mut right = TokenStream::new();
@@ -1,37 +1,132 @@
-use proc_macro2::{Span, TokenStream};
-use quote::{quote, ToTokens};
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::{
     braced,
-    parse::{Parse, ParseStream, Result},
-    parse_quote, Ident,
+    parse::{Parse, ParseStream, Parser, Result},
+    token, Error, Ident, Token,
 };
 
 use crate::sm::{
+    attribute::Attributes,
+    context::Context,
+    defer::{Defer, Deferred},
+    error::Errors,
     event::{Event, Events},
-    initial_state::InitialStates,
+    final_state::FinalStates,
+    generics::Generics,
+    group::Groups,
+    import::{scope, CratePath, Import, Imports},
+    initial_state::{InitialState, InitialStates},
+    names::{self, snake_case, TypeNames},
     state::{State, States},
-    transition::Transitions,
+    submachine::{Submachine, Submachines},
+    ticks::{TickTimeout, TickTimeouts},
+    timeout::{Timeout, Timeouts},
+    transition::{Fields, Transition, Transitions},
+    visibility::{self, Visibility},
 };
 
 #[derive(Debug, PartialEq)]
-pub(crate) struct Machines(Vec<Machine>);
+pub(crate) struct Machines(pub Vec<Machine>);
 
 impl Parse for Machines {
     /// example machines tokens:
     ///
     /// ```text
+    /// crate = ::sm;
+    ///
     /// TurnStile { ... }
     /// Lock { ... }
     /// MyStateMachine { ... }
     /// ```
     fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut errors = Errors::default();
+        let machines = Machines::parse_with(input, &mut errors);
+
+        errors.finish(machines)
+    }
+}
+
+impl Machines {
+    /// parse_with parses the machines, adding the problems found in each of
+    /// them to `errors`, so that a machine that fails to parse does not hide
+    /// the problems in the machines that follow it.
+    pub(crate) fn parse_with(input: ParseStream<'_>, errors: &mut Errors) -> Result<Self> {
         let mut machines: Vec<Machine> = Vec::new();
 
+        // `crate = ::sm;`
+        //  ^^^^^^^^^^^^^
+        let krate = if input.peek(Token![crate]) && input.peek2(Token![=]) {
+            let krate: CratePath = input.parse()?;
+            let _: Token![;] = input.parse()?;
+
+            Some(krate)
+        } else {
+            None
+        };
+
         while !input.is_empty() {
             // `TurnStile { ... }`
             //  ^^^^^^^^^^^^^^^^^
-            let machine = Machine::parse(input)?;
-            machines.push(machine);
+            let tokens = next_block(input)?;
+            let parser = |input: ParseStream<'_>| Machine::parse_with(input, errors);
+            let result = parser.parse2(tokens);
+
+            if let Some(machine) = errors.check(result) {
+                machines.push(machine);
+            }
+        }
+
+        // Submachines declared alongside their parent might use different type
+        // names, which the parent needs to refer to them. Other submachines are
+        // expected to use the default names.
+        let names = machines
+            .iter()
+            .map(|m| {
+                let embeddable = if !m.generics.is_empty() {
+                    Err(format!(
+                        "generic machine `{}` cannot be embedded in another machine",
+                        m.name
+                    ))
+                } else if m.context.is_some() {
+                    Err(format!(
+                        "machine `{}` has a context, and cannot be embedded in another machine",
+                        m.name
+                    ))
+                } else {
+                    Ok(())
+                };
+
+                (m.name.clone(), m.names.clone(), embeddable)
+            })
+            .collect::<Vec<_>>();
+
+        for machine in &mut machines {
+            if let Some(krate) = &krate {
+                machine.krate = krate.clone();
+            }
+
+            for submachine in &mut machine.submachines.0 {
+                let sibling = match submachine.path.as_slice() {
+                    [name] => Some(name),
+                    [module, name] if module == "self" => Some(name),
+                    _ => None,
+                };
+
+                let found = names
+                    .iter()
+                    .find(|(name, _, _)| !submachine.global && Some(name) == sibling);
+
+                if let Some((_, names, embeddable)) = found {
+                    submachine.names = names.clone();
+
+                    // The generic arguments and the context of an embedded
+                    // machine cannot be derived from its parent.
+                    if let Err(message) = embeddable {
+                        errors.push(Error::new(submachine.name().span(), message));
+                    }
+                }
+            }
         }
 
         Ok(Machines(machines))
@@ -40,10 +135,6 @@ impl Parse for Machines {
 
 impl ToTokens for Machines {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        tokens.extend(quote! {
-            use sm::{AsEnum, Initializer, Machine as M, Transition};
-        });
-
         for machine in &self.0 {
             machine.to_tokens(tokens);
         }
@@ -52,13 +143,28 @@ impl ToTokens for Machines {
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Machine {
+    pub attributes: Attributes,
+    pub visibility: Visibility,
     pub name: Ident,
+    pub generics: Generics,
+    pub imports: Imports,
     pub initial_states: InitialStates,
+    pub final_states: FinalStates,
     pub transitions: Transitions,
+    pub deferred: Deferred,
+    pub timeouts: Timeouts,
+    pub tick_timeouts: TickTimeouts,
+    pub submachines: Submachines,
+    pub groups: Groups,
+    pub names: TypeNames,
+    pub context: Option<Context>,
+    pub krate: CratePath,
+    pub event_visibilities: Vec<(Ident, Visibility)>,
+    pub handler: bool,
 }
 
 impl Machine {
-    fn states(&self) -> States {
+    pub(crate) fn states(&self) -> States {
         let mut states: Vec<State> = Vec::new();
 
         for t in &self.transitions.0 {
@@ -69,6 +175,12 @@ impl Machine {
             if !states.iter().any(|s| s.name == t.to.name) {
                 states.push(t.to.clone());
             }
+
+            if let Some(error) = &t.error {
+                if !states.iter().any(|s| s.name == error.name) {
+                    states.push(error.clone());
+                }
+            }
         }
 
         for i in &self.initial_states.0 {
@@ -82,7 +194,7 @@ impl Machine {
         States(states)
     }
 
-    fn events(&self) -> Events {
+    pub(crate) fn events(&self) -> Events {
         let mut events: Vec<Event> = Vec::new();
 
         for t in &self.transitions.0 {
@@ -93,215 +205,2795 @@ impl Machine {
 
         Events(events)
     }
-}
 
-impl Parse for Machine {
-    /// example machine tokens:
-    ///
-    /// ```text
-    /// TurnStile {
-    ///     InitialStates { ... }
-    ///
-    ///     Push { ... }
-    ///     Coin { ... }
-    /// }
-    /// ```
-    fn parse(input: ParseStream<'_>) -> Result<Self> {
-        // `TurnStile { ... }`
-        //  ^^^^^^^^^
-        let name: Ident = input.parse()?;
+    /// generates returns whether the passed in event is generated by one of
+    /// the `After`, `AfterTicks` or `Submachine` blocks of the machine.
+    pub(crate) fn generates(&self, event: &Ident) -> bool {
+        (event == "Timeout" && !self.timeouts.0.is_empty())
+            || (event == "TickTimeout" && !self.tick_timeouts.0.is_empty())
+            || (event == "Completed" && !self.submachines.0.is_empty())
+    }
 
-        // `TurnStile { ... }`
-        //              ^^^
-        let block_machine;
-        braced!(block_machine in input);
+    fn submachine(&self, state: &Ident) -> Option<&Submachine> {
+        self.submachines.0.iter().find(|s| &s.state.name == state)
+    }
 
-        // `InitialStates { ... }`
-        //  ^^^^^^^^^^^^^^^^^^^^^
-        let initial_states = InitialStates::parse(&block_machine)?;
+    /// completes returns whether the passed in transition is triggered by the
+    /// `Completed` event of a submachine, which is only used by the dispatch
+    /// of the machine variant.
+    fn completes(&self, t: &Transition) -> bool {
+        t.event.name == "Completed" && self.submachine(&t.from.name).is_some()
+    }
 
-        // `Push { ... }`
-        //  ^^^^^^^^^^^^
-        let transitions = Transitions::parse(&block_machine)?;
+    /// events_generics returns the generic parameters of the machine that are
+    /// used by its events, which are the parameters of the `Events` enum.
+    fn events_generics(&self) -> Generics {
+        let events = self
+            .events()
+            .0
+            .iter()
+            .map(|e| self.imports.ty(&e.name))
+            .collect::<Vec<_>>();
+
+        self.generics.used_by(&events.iter().collect::<Vec<_>>())
+    }
 
-        Ok(Machine {
-            name,
-            initial_states,
-            transitions,
-        })
+    /// capability_events returns the events that have a capability trait,
+    /// which are the events used by at least one transition that is neither a
+    /// choice, fallible nor the completion of a submachine.
+    fn capability_events(&self) -> Vec<Ident> {
+        self.events()
+            .0
+            .into_iter()
+            .filter(|e| {
+                self.transitions.0.iter().any(|t| {
+                    t.event.name == e.name && !t.choice && t.error.is_none() && !self.completes(t)
+                })
+            })
+            .map(|e| e.name)
+            .collect()
     }
-}
 
-impl ToTokens for Machine {
-    fn to_tokens(&self, tokens: &mut TokenStream) {
+    /// diagnostics returns the hidden `diagnostics` module of the machine, and
+    /// the implementations of its traits. The traits bound the `new` method and
+    /// the `transition` method of each state, so that using them incorrectly
+    /// is reported in terms of the machine and its states, rather than in terms
+    /// of the traits of the crate.
+    fn diagnostics(&self) -> TokenStream {
+        let krate = &self.krate;
         let name = &self.name;
-        let initial_states = &self.initial_states;
-        let states = &self.states();
-        let events = &self.events();
-        let machine_enum = MachineEnum { machine: &self };
-        let transitions = &self.transitions;
+        let machine = &self.names.machine;
+        let generics = &self.generics;
+        let (params, args) = (generics.params(), generics.args());
+        let mut initial_states: Vec<&Ident> = Vec::new();
+
+        // Duplicate initial states are already reported by the conflicting
+        // `InitialState` implementations, so they are only implemented once.
+        for state in &self.initial_states.0 {
+            if !initial_states.contains(&&state.name) {
+                initial_states.push(&state.name);
+            }
+        }
 
-        tokens.extend(quote! {
-            #[allow(non_snake_case)]
-            mod #name {
-                use sm::{AsEnum, Event, InitialState, Initializer, Machine as M, NoneEvent, State, Transition};
+        let message = format!("`{{Self}}` is not an initial state of `{}`", name);
+        let label = format!("cannot initialise `{}` in this state", name);
+        let note = format!(
+            "the initial states of `{}` are: {}",
+            name,
+            initial_states
+                .iter()
+                .map(|s| format!("`{}`", s))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let initial = quote! {
+            #[diagnostic::on_unimplemented(message = #message, label = #label, note = #note)]
+            pub trait Initial {}
+        };
+        let mut traits = TokenStream::new();
+        let mut impls = TokenStream::new();
 
-                #[derive(Debug, Eq, PartialEq, Clone)]
-                pub struct Machine<S: State, E: Event>(S, Option<E>);
+        for state in initial_states {
+            let state = self.imports.ty(state);
+            let used = generics.used_by(&[&state]);
 
-                impl<S: State, E: Event> M for Machine<S, E> {
-                    type State = S;
-                    type Event = E;
+            impls.extend(quote! {
+                impl #used diagnostics::Initial for #state {}
+            });
+        }
 
-                    fn state(&self) -> Self::State {
-                        self.0.clone()
-                    }
+        for state in self.states().0 {
+            let mut events: Vec<&Ident> = Vec::new();
+
+            // Submachines are completed by the dispatch of the variant, so
+            // only the other transitions are valid events for `transition`.
+            for t in &self.transitions.0 {
+                if t.from.name == state.name
+                    && !self.completes(t)
+                    && !events.contains(&&t.event.name)
+                {
+                    events.push(&t.event.name);
+                }
+            }
 
-                    fn trigger(&self) -> Option<Self::Event> {
-                        self.1.clone()
+            let message = if events.is_empty() {
+                format!(
+                    "`{}` has no transition for event `{{Self}}`, or any other event",
+                    state.name
+                )
+            } else {
+                format!(
+                    "`{}` has no transition for event `{{Self}}`; valid events are: {}",
+                    state.name,
+                    events
+                        .iter()
+                        .map(|e| format!("`{}`", e))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+            let label = format!(
+                "invalid transition for `{}` in state `{}`",
+                name, state.name
+            );
+            let note = match self.submachine(&state.name) {
+                Some(submachine) => format!(
+                    "`Completed` is only triggered by `{}::dispatch`, once the `{}` submachine of `{}` reaches one of its final states",
+                    self.names.variant,
+                    submachine.name(),
+                    state.name
+                ),
+                None => "a machine can only be transitioned using the events declared for its current state".to_string(),
+            };
+            let trait_name = &state.name;
+            let from = self.imports.ty(&state.name);
+
+            traits.extend(quote! {
+                #[diagnostic::on_unimplemented(
+                    message = #message,
+                    label = #label,
+                    note = #note
+                )]
+                pub trait #trait_name<M> {
+                    type Machine;
+
+                    fn accept(self, machine: M) -> Self::Machine;
+                }
+            });
+
+            impls.extend(quote! {
+                impl<#params E: #krate::Event> #machine<#args #from, E> {
+                    pub fn transition<V: diagnostics::transition::#trait_name<Self>>(self, event: V) -> V::Machine {
+                        diagnostics::transition::#trait_name::accept(event, self)
                     }
                 }
+            });
 
-                impl<S: InitialState> Initializer<S> for Machine<S, NoneEvent> {
-                    type Machine = Machine<S, NoneEvent>;
+            for event in events {
+                let event = self.imports.ty(event);
 
-                    fn new(state: S) -> Self::Machine {
-                        Machine(state, Option::None)
+                impls.extend(quote! {
+                    impl<#params E: #krate::Event> diagnostics::transition::#trait_name<#machine<#args #from, E>> for #event {
+                        type Machine = <#event as #krate::Accept<#machine<#args #from, E>>>::Machine;
+
+                        fn accept(self, machine: #machine<#args #from, E>) -> Self::Machine {
+                            #krate::Accept::accept(self, machine)
+                        }
                     }
-                }
+                });
+            }
+        }
 
-                #states
-                #initial_states
-                #events
-                #machine_enum
-                #transitions
+        quote! {
+            #[doc(hidden)]
+            pub mod diagnostics {
+                #initial
+
+                // The traits of the states are kept in a module of their own,
+                // so that they cannot collide with the `Initial` trait.
+                pub mod transition {
+                    #traits
+                }
             }
-        });
+
+            #impls
+        }
     }
-}
 
-#[derive(Debug)]
-#[allow(single_use_lifetimes)]
-struct MachineEnum<'a> {
-    machine: &'a Machine,
-}
+    /// generated returns the names of the items generated in the module of the
+    /// machine, along with a description of each of them.
+    fn generated(&self) -> Vec<Name> {
+        let mut generated = Vec::new();
+
+        for (default, name) in self.names.all().iter() {
+            if *name == default {
+                generated.push(Name {
+                    name: (*name).clone(),
+                    description: format!("the generated `{}` type", name),
+                    hint: Some(format!(
+                        "which can be renamed using `Names {{ {} as ... }}`",
+                        default
+                    )),
+                    renamed: None,
+                });
+            } else {
+                generated.push(Name {
+                    name: (*name).clone(),
+                    description: format!("the generated `{}` type, renamed to `{}`", default, name),
+                    hint: None,
+                    renamed: Some(format!("`{}` is renamed to `{}`", default, name)),
+                });
+            }
+        }
 
-#[allow(single_use_lifetimes)]
-impl<'a> ToTokens for MachineEnum<'a> {
-    fn to_tokens(&self, tokens: &mut TokenStream) {
-        let mut variants = Vec::new();
-        let mut states = Vec::new();
-        let mut events = Vec::new();
+        let mut push = |name: &str, description: String| {
+            generated.push(Name {
+                name: Ident::new(name, Span::call_site()),
+                description,
+                hint: None,
+                renamed: None,
+            });
+        };
 
-        for s in &self.machine.initial_states.0 {
-            let name = s.name.clone();
-            let none = parse_quote! { NoneEvent };
-            let variant = Ident::new(&format!("Initial{}", name), Span::call_site());
+        push("IsState", "the generated `IsState` trait".to_string());
 
-            variants.push(variant);
-            states.push(name);
-            events.push(none);
+        if !self.tick_timeouts.0.is_empty() {
+            push("Ticks", "the generated `Ticks` trait".to_string());
         }
 
-        for t in &self.machine.transitions.0 {
-            let state = t.to.name.clone();
-            let event = t.event.name.clone();
-            let variant = Ident::new(&format!("{}By{}", state, event), Span::call_site());
+        if !self.groups.0.is_empty() {
+            push("Group", "the generated `Group` enum".to_string());
+        }
+
+        if self.handler {
+            let name = format!("{}Handler", self.name);
+            push(&name, format!("the generated `{}` trait", name));
+        }
+
+        for group in &self.groups.0 {
+            let name = group.capability().to_string();
+            push(
+                &name,
+                format!("the `{}` trait generated for group `{}`", name, group.name),
+            );
+        }
+
+        for event in self.capability_events() {
+            let name = format!("Can{}", event);
+            push(
+                &name,
+                format!("the `{}` trait generated for event `{}`", name, event),
+            );
+        }
 
-            if variants.contains(&variant) {
+        let choices = self.transitions.0.iter().filter(|t| t.choice);
+        for (i, t) in choices.clone().enumerate() {
+            // Each choice has a single enum, shared by all of its targets.
+            if choices
+                .clone()
+                .take(i)
+                .any(|c| c.event.name == t.event.name && c.from.name == t.from.name)
+            {
                 continue;
             }
 
-            variants.push(variant);
-            states.push(state);
-            events.push(event);
+            let name = format!("{}From{}", t.event.name, t.from.name);
+            push(
+                &name,
+                format!(
+                    "the `{}` enum generated for the choice of event `{}` in state `{}`",
+                    name, t.event.name, t.from.name
+                ),
+            );
+
+            let branch = format!("{}Branch", name);
+            push(
+                &branch,
+                format!(
+                    "the `{}` enum generated for the branches of the choice of event `{}` in state `{}`",
+                    branch, t.event.name, t.from.name
+                ),
+            );
         }
 
-        let variants = &variants;
-        let states = &states;
-        let events = &events;
+        generated
+    }
 
-        tokens.extend(quote! {
-            #[derive(Debug, Clone)]
-            pub enum Variant {
-                #(#variants(Machine<#states, #events>)),*
+    /// reachable returns the names of the states that can be reached from
+    /// any of the initial states of the machine.
+    fn reachable(&self) -> Vec<&Ident> {
+        let mut reachable = self
+            .initial_states
+            .0
+            .iter()
+            .map(|s| &s.name)
+            .collect::<Vec<_>>();
+
+        let mut i = 0;
+        while i < reachable.len() {
+            let from = reachable[i];
+
+            for t in self.transitions.0.iter().filter(|t| &t.from.name == from) {
+                for to in Some(&t.to).into_iter().chain(&t.error) {
+                    if !reachable.contains(&&to.name) {
+                        reachable.push(&to.name);
+                    }
+                }
             }
 
-            #(
-                impl AsEnum for Machine<#states, #events> {
-                    type Enum = Variant;
+            i += 1;
+        }
 
-                    fn as_enum(self) -> Self::Enum {
-                        Variant::#variants(self)
-                    }
+        reachable
+    }
+
+    /// dead_ends returns the states that have no outgoing transitions, without
+    /// being marked as final.
+    fn dead_ends(&self) -> Vec<State> {
+        self.states()
+            .0
+            .into_iter()
+            .filter(|s| {
+                !self.final_states.contains(&s.name)
+                    && !self.transitions.0.iter().any(|t| t.from.name == s.name)
+            })
+            .collect()
+    }
+
+    /// validate adds an error to `errors` for every problem found in the
+    /// machine.
+    fn validate(&self, errors: &mut Errors) {
+        let before = errors.len();
+
+        for transition in &self.transitions.0 {
+            for to in Some(&transition.to).into_iter().chain(&transition.error) {
+                if self.groups.find(&to.name).is_some() {
+                    errors.push(Error::new(
+                        to.name.span(),
+                        format!("group `{}` cannot be the target of a transition", to.name),
+                    ));
                 }
-            )*
+            }
+        }
+
+        for (i, group) in self.groups.0.iter().enumerate() {
+            if self.groups.0[..i].iter().any(|g| g.name == group.name) {
+                errors.push(Error::new(
+                    group.name.span(),
+                    format!("group `{}` is declared more than once", group.name),
+                ));
+            }
+
+            // A group used as a target is already reported above, and is only
+            // listed as a state because of that.
+            let target = self.transitions.0.iter().any(|t| {
+                t.to.name == group.name || t.error.as_ref().is_some_and(|e| e.name == group.name)
+            });
+
+            if !target && self.states().0.iter().any(|s| s.name == group.name) {
+                errors.push(Error::new(
+                    group.name.span(),
+                    format!("group `{}` has the same name as a state", group.name),
+                ));
+            }
+
+            for state in &group.states {
+                if !self.states().0.iter().any(|s| s.name == state.name) {
+                    errors.push(Error::new(
+                        state.name.span(),
+                        format!("unknown state `{}` in group `{}`", state.name, group.name),
+                    ));
+                }
+            }
+        }
+
+        for (i, import) in self.imports.0.iter().enumerate() {
+            if self.imports.0[..i].iter().any(|s| s.name == import.name) {
+                errors.push(Error::new(
+                    import.name.span(),
+                    format!("type `{}` is imported more than once", import.name),
+                ));
+            }
+
+            // Traits used by the bounds of the generic parameters are imported
+            // as well, as the bounds are part of the generated module.
+            if !self.states().0.iter().any(|s| s.name == import.name)
+                && !self.events().0.iter().any(|e| e.name == import.name)
+                && !self.generics.bounds_use(&import.name)
+            {
+                errors.push(Error::new(
+                    import.name.span(),
+                    format!(
+                        "imported type `{}` is not used as a state or event",
+                        import.name
+                    ),
+                ));
+            }
+        }
+
+        for (i, transition) in self.transitions.0.iter().enumerate() {
+            if transition.choice
+                && self.transitions.0[..i].iter().any(|t| {
+                    t.choice
+                        && t.event.name == transition.event.name
+                        && t.from.name == transition.from.name
+                        && t.to.name == transition.to.name
+                })
+            {
+                errors.push(Error::new(
+                    transition.to.name.span(),
+                    format!(
+                        "choice for event `{}` in state `{}` lists state `{}` more than once",
+                        transition.event.name, transition.from.name, transition.to.name
+                    ),
+                ));
+            }
+        }
+
+        for d in &self.deferred.0 {
+            if !self.events().0.iter().any(|e| e.name == d.event.name) {
+                errors.push(Error::new(
+                    d.event.name.span(),
+                    format!("cannot defer unknown event `{}`", d.event.name),
+                ));
+            }
+
+            if self
+                .transitions
+                .0
+                .iter()
+                .any(|t| t.from.name == d.state.name && t.event.name == d.event.name)
+            {
+                errors.push(Error::new(
+                    d.event.name.span(),
+                    format!(
+                        "cannot defer event `{}` in state `{}`, which has a transition for it",
+                        d.event.name, d.state.name
+                    ),
+                ));
+            }
+        }
+
+        for (i, timeout) in self.timeouts.0.iter().enumerate() {
+            if self.timeouts.0[..i]
+                .iter()
+                .any(|t| t.state.name == timeout.state.name)
+            {
+                errors.push(Error::new(
+                    timeout.state.name.span(),
+                    format!("state `{}` has more than one timeout", timeout.state.name),
+                ));
+            }
+        }
+
+        for (i, submachine) in self.submachines.0.iter().enumerate() {
+            if self.submachines.0[..i]
+                .iter()
+                .any(|s| s.state.name == submachine.state.name)
+            {
+                errors.push(Error::new(
+                    submachine.state.name.span(),
+                    format!(
+                        "state `{}` has more than one submachine",
+                        submachine.state.name
+                    ),
+                ));
+            }
+        }
+
+        for (i, timeout) in self.tick_timeouts.0.iter().enumerate() {
+            if self.tick_timeouts.0[..i]
+                .iter()
+                .any(|t| t.state.name == timeout.state.name)
+            {
+                errors.push(Error::new(
+                    timeout.state.name.span(),
+                    format!(
+                        "state `{}` has more than one tick timeout",
+                        timeout.state.name
+                    ),
+                ));
+            }
+        }
+
+        // The same event can only lead to a single target state, except for
+        // choices, which are checked for duplicate targets above. Both of the
+        // conflicting transitions are reported, at their target states.
+        let mut reported: Vec<&Transition> = Vec::new();
+
+        for (i, transition) in self.transitions.0.iter().enumerate() {
+            let previous = self.transitions.0[..i].iter().find(|t| {
+                t.event.name == transition.event.name
+                    && t.from.name == transition.from.name
+                    && !(t.choice && transition.choice)
+            });
+
+            if let Some(previous) = previous {
+                if !reported.contains(&previous) {
+                    reported.push(previous);
+
+                    errors.push(Error::new(
+                        previous.to.name.span(),
+                        format!(
+                            "nondeterministic transition, event `{}` in state `{}` leads to both {} and {}",
+                            transition.event.name,
+                            transition.from.name,
+                            previous.target(),
+                            transition.target()
+                        ),
+                    ));
+                }
+
+                errors.push(Error::new(
+                    transition.to.name.span(),
+                    format!(
+                        "`{}` already transitions to {} on `{}`",
+                        transition.from.name,
+                        previous.target(),
+                        transition.event.name
+                    ),
+                ));
+            }
+        }
+
+        for state in &self.final_states.0 {
+            if !self.states().0.iter().any(|s| s.name == state.name) {
+                errors.push(Error::new(
+                    state.name.span(),
+                    format!("unknown state `{}` in `FinalStates`", state.name),
+                ));
+            }
+        }
+
+        self.validate_names(errors);
+        self.validate_variants(errors);
+
+        // Reachability is only checked for an otherwise valid machine, as the
+        // problems above tend to make states unreachable as well.
+        if errors.len() > before {
+            return;
+        }
+
+        // A machine can only be constructed in one of its initial states, so
+        // states that cannot be reached from there are never used, and neither
+        // are the events that only transition from those states.
+        let reachable = self.reachable();
+
+        for event in &self.events().0 {
+            let sources = self
+                .transitions
+                .0
+                .iter()
+                .filter(|t| t.event.name == event.name)
+                .map(|t| &t.from.name)
+                .collect::<Vec<_>>();
+
+            if !sources.iter().any(|s| reachable.contains(s)) {
+                errors.push(Error::new(
+                    event.name.span(),
+                    format!(
+                        "event `{}` can never be triggered, as none of its source states ({}) are reachable",
+                        event.name,
+                        sources
+                            .iter()
+                            .map(|s| format!("`{}`", s))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                ));
+            }
+        }
+
+        for state in &self.states().0 {
+            if !reachable.contains(&&state.name) {
+                errors.push(Error::new(
+                    state.name.span(),
+                    format!(
+                        "state `{}` is not reachable from any of the initial states",
+                        state.name
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// validate_names adds an error for every name that is used more than once
+    /// in the module of the machine, as its states, events, groups and generated
+    /// items all share the same namespace. Names of the prelude, such as `Ok`,
+    /// can be used, as the generated code does not depend on the prelude.
+    fn validate_names(&self, errors: &mut Errors) {
+        let user = |kind: &str, name: &Ident| Name {
+            name: name.clone(),
+            description: format!("{} `{}`", kind, name),
+            hint: None,
+            renamed: None,
+        };
+        let mut taken: Vec<Name> = Vec::new();
+
+        let generated = self.generated();
+        let events = self
+            .events()
+            .0
+            .iter()
+            .map(|e| user("event", &e.name))
+            .collect::<Vec<_>>();
+
+        for name in generated.into_iter().chain(events) {
+            if let Some(err) = name.collision(&taken) {
+                errors.push(err);
+            }
+
+            taken.push(name);
+        }
+
+        // States with the same name as a group are already reported, so groups
+        // and states are only checked against the names taken above.
+        let groups = self.groups.0.iter().map(|g| user("group", &g.name));
+        let states = self
+            .states()
+            .0
+            .iter()
+            .map(|s| user("state", &s.name))
+            .collect::<Vec<_>>();
+
+        for name in groups.chain(states) {
+            if let Some(err) = name.collision(&taken) {
+                errors.push(err);
+            }
+        }
+    }
+
+    /// validate_variants adds an error for every variant of the generated
+    /// variant enum that would be generated for more than one combination of
+    /// state and event, e.g. `AByBByC` for state `AByB` entered by event `C`,
+    /// and state `A` entered by event `BByC`. Events are checked against the
+    /// submachines as well, as both result in a variant of the events enum.
+    fn validate_variants(&self, errors: &mut Errors) {
+        for (i, submachine) in self.submachines.0.iter().enumerate() {
+            let name = submachine.name();
+            let event = self.events().0.into_iter().find(|e| &e.name == name);
+
+            if self.submachines.0[..i].iter().any(|s| s.name() == name) {
+                continue;
+            }
+
+            if let Some(event) = event {
+                errors.push(Error::new(
+                    event.name.span(),
+                    format!(
+                        "event `{}` and submachine `{}` both result in the `{}::{}` variant, rename one of them",
+                        name, name, self.names.events, name
+                    ),
+                ));
+            }
+        }
+
+        let mut variants: Vec<(Ident, &Ident, Option<&Ident>)> = Vec::new();
+
+        for s in &self.initial_states.0 {
+            let variant = Ident::new(&format!("Initial{}", s.name), Span::call_site());
+            variants.push((variant, &s.name, None));
+        }
+
+        for t in &self.transitions.0 {
+            for to in Some(&t.to).into_iter().chain(&t.error) {
+                let variant = format!("{}By{}", to.name, t.event.name);
+                variants.push((
+                    Ident::new(&variant, Span::call_site()),
+                    &to.name,
+                    Some(&t.event.name),
+                ));
+            }
+        }
+
+        let describe = |state: &Ident, event: Option<&Ident>| match event {
+            Some(event) => format!("state `{}` entered by event `{}`", state, event),
+            None => format!("initial state `{}`", state),
+        };
+
+        for (i, current) in variants.iter().enumerate() {
+            let (variant, state, event) = current;
+
+            // The same state entered by the same event from different source
+            // states results in a single variant.
+            if variants[..i].contains(current) {
+                continue;
+            }
+
+            if let Some((_, other_state, other_event)) =
+                variants[..i].iter().find(|(v, _, _)| v == variant)
+            {
+                errors.push(Error::new(
+                    state.span(),
+                    format!(
+                        "{} and {} both result in the `{}::{}` variant, rename one of them",
+                        describe(other_state, *other_event),
+                        describe(state, *event),
+                        self.names.variant,
+                        variant
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Name is a name used in the module of a machine, as checked for collisions
+/// by `validate_names`.
+#[derive(Debug)]
+struct Name {
+    name: Ident,
+    /// description describes the item using the name, such as "event `Coin`".
+    description: String,
+    /// hint describes how the item can be renamed, if it can be renamed.
+    hint: Option<String>,
+    /// renamed describes the rename that resulted in the name, for a generated
+    /// type that is renamed using a `Names` block.
+    renamed: Option<String>,
+}
+
+impl Name {
+    /// collision returns an error if the name is already taken by one of the
+    /// passed in names.
+    fn collision(&self, taken: &[Name]) -> Option<Error> {
+        let other = taken.iter().find(|n| n.name == self.name)?;
+
+        // A collision caused by a rename is described in terms of the rename,
+        // also when the renamed type is taken first, and clashes with one of
+        // the generated types that keep their default name, which have a hint.
+        let message = match (&self.renamed, &other.renamed) {
+            (Some(renamed), _) => format!(
+                "{}, which is already the name of {}",
+                renamed, other.description
+            ),
+            (None, Some(renamed)) if self.hint.is_some() => format!(
+                "{}, which is already the name of {}",
+                renamed, self.description
+            ),
+            _ => match other.hint.as_ref().or(self.hint.as_ref()) {
+                Some(hint) => format!(
+                    "{} has the same name as {}, {}",
+                    self.description, other.description, hint
+                ),
+                None => format!(
+                    "{} has the same name as {}",
+                    self.description, other.description
+                ),
+            },
+        };
+
+        Some(Error::new(self.name.span(), message))
+    }
+}
+
+impl Parse for Machine {
+    /// example machine tokens:
+    ///
+    /// ```text
+    /// TurnStile {
+    ///     use crate::input::Push;
+    ///
+    ///     InitialStates { ... }
+    ///
+    ///     Push { ... }
+    ///     Coin { ... }
+    ///
+    ///     After(3s) { ... }
+    ///     AfterTicks(30) { ... }
+    ///     Defer { ... }
+    ///     Submachine(Reload: Ejecting) { ... }
+    ///     Groups { ... }
+    ///     Context { ... }
+    ///
+    ///     FinalStates { ... }
+    ///     Handler
+    /// }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut errors = Errors::default();
+        let machine = Machine::parse_with(input, &mut errors);
+
+        errors.finish(machine)
+    }
+}
+
+impl Machine {
+    /// parse_with parses a machine, adding any problems found in its blocks to
+    /// `errors`. See the `Parse` implementation for an example.
+    pub(crate) fn parse_with(input: ParseStream<'_>, errors: &mut Errors) -> Result<Self> {
+        // `pub TurnStile { ... }`
+        //  ^^^
+        let visibility: Visibility = input.parse()?;
+
+        // `pub TurnStile { ... }`
+        //      ^^^^^^^^^
+        let name: Ident = input.parse()?;
+
+        // `Slot<T: Item> { ... }`
+        //      ^^^^^^^^^
+        let generics: Generics = input.parse()?;
+
+        // `TurnStile { ... }`
+        //              ^^^
+        let block_machine;
+        braced!(block_machine in input);
+
+        // `use crate::input::Push;`
+        //  ^^^^^^^^^^^^^^^^^^^^^^
+        let imports = Imports::parse(&block_machine)?;
+
+        let mut machine =
+            Machine::parse_blocks(name, generics, imports, None, &block_machine, errors)?;
+        machine.visibility = visibility;
+
+        Ok(machine)
+    }
+
+    /// example machine blocks tokens, for the given name, generics, imports
+    /// and initial states:
+    ///
+    /// ```text
+    /// InitialStates { ... }
+    ///
+    /// Push { ... }
+    /// Coin { ... }
+    ///
+    /// After(3s) { ... }
+    /// AfterTicks(30) { ... }
+    /// Defer { ... }
+    /// Submachine(Reload: Ejecting) { ... }
+    /// Groups { ... }
+    /// Context { ... }
+    ///
+    /// FinalStates { ... }
+    /// Handler
+    /// ```
+    ///
+    /// The `InitialStates` block has to be the first block, unless the initial
+    /// states are passed in. A block that fails to parse is added to `errors`
+    /// and skipped, after which parsing continues with the next block. The
+    /// machine is only validated if all of its blocks were parsed.
+    pub(crate) fn parse_blocks(
+        name: Ident,
+        generics: Generics,
+        mut imports: Imports,
+        mut initial_states: Option<InitialStates>,
+        block_machine: ParseStream<'_>,
+        errors: &mut Errors,
+    ) -> Result<Self> {
+        let before = errors.len();
+        let mut transitions = Transitions(Vec::new());
+        let mut deferred = Deferred(Vec::new());
+        let mut timeouts = Timeouts(Vec::new());
+        let mut tick_timeouts = TickTimeouts(Vec::new());
+        let mut submachines = Submachines(Vec::new());
+        let mut groups = Groups(Vec::new());
+        let mut final_states = FinalStates(Vec::new());
+        let mut names: Option<TypeNames> = None;
+        let mut context: Option<Context> = None;
+        let mut event_visibilities: Vec<(Ident, Visibility)> = Vec::new();
+        let mut handler = false;
+        let mut misnamed = false;
+
+        let mut first: Option<Span> = None;
+
+        while !block_machine.is_empty() {
+            if block_machine.peek(Token![use]) {
+                // `use crate::input::Push;`
+                //  ^^^^^^^^^^^^^^^^^^^^^^
+                let import = Import::parse(block_machine)?;
+
+                errors.push(Error::new(
+                    import.name.span(),
+                    "`use` declarations have to come before all blocks of the machine",
+                ));
+
+                imports.0.push(import);
+                continue;
+            }
+
+            // `pub(crate) Reset { ... }`
+            //  ^^^^^^^^^^
+            let visibility: Visibility = block_machine.parse()?;
+
+            let block_name = match block_machine.fork().parse::<Ident>() {
+                Ok(block_name) => block_name,
+                Err(_) => {
+                    // `Push { ... }, Coin { ... }`
+                    //              ^
+                    let token: TokenTree = block_machine.parse()?;
+
+                    errors.push(Error::new(
+                        token.span(),
+                        format!(
+                            "unexpected `{}`, expected a block such as `Push {{ ... }}`",
+                            token
+                        ),
+                    ));
+                    continue;
+                }
+            };
+
+            let is_first = first.is_none();
+            if is_first {
+                first = Some(block_name.span());
+            }
+
+            let event = block_name != "Handler" && names::block(&block_name).is_none();
+            if visibility != Visibility::Inherited && !event {
+                errors.push(Error::new(
+                    block_name.span(),
+                    format!(
+                        "`{}` cannot have a visibility, only machines and events can",
+                        block_name
+                    ),
+                ));
+            }
+
+            if block_name == "Handler" && !block_machine.peek2(token::Brace) {
+                // `Handler`
+                //  ^^^^^^^
+                let _: Ident = block_machine.parse()?;
+
+                if handler {
+                    errors.push(Error::new(
+                        block_name.span(),
+                        "`Handler` is declared more than once",
+                    ));
+                }
+
+                handler = true;
+                continue;
+            }
+
+            // Blocks such as `After(3s) { ... }` take arguments, which sets them
+            // apart from an event with the same name.
+            let reserved = names::block(&block_name).filter(|_| block_machine.peek2(token::Brace));
+
+            // `After(3s) { ... }`
+            //  ^^^^^^^^^^^^^^^^^
+            let tokens = next_block(block_machine)?;
+
+            // A first block that is neither `InitialStates` nor declares any
+            // transitions is most likely a misnamed `InitialStates` block, such
+            // as `Initial { Locked }`, which is skipped instead of reporting its
+            // states as transitions with a missing `=>`.
+            if is_first && initial_states.is_none() && event && !has_arrow(&tokens) {
+                errors.push(Error::new(
+                    block_name.span(),
+                    "expected `InitialStates { ... }` block",
+                ));
+
+                misnamed = true;
+                continue;
+            }
+
+            if block_name == "InitialStates" {
+                // `InitialStates { ... }`
+                //  ^^^^^^^^^^^^^^^^^^^^^
+                if initial_states.is_some() {
+                    errors.push(Error::new(
+                        block_name.span(),
+                        format!("the initial states of `{}` are already declared", name),
+                    ));
+                    continue;
+                }
+
+                if !is_first {
+                    errors.push(Error::new(
+                        block_name.span(),
+                        "`InitialStates { ... }` has to be the first block of the machine, after any `use` declarations",
+                    ));
+                }
+
+                initial_states = Some(
+                    errors
+                        .check(syn::parse2(tokens))
+                        .unwrap_or_else(|| InitialStates(Vec::new())),
+                );
+            } else if block_name == "FinalStates" {
+                // `FinalStates { ... }`
+                //  ^^^^^^^^^^^^^^^^^^^
+                if let Some(f) = errors.check(syn::parse2::<FinalStates>(tokens)) {
+                    final_states.0.extend(f.0);
+                }
+            } else if block_name == "Groups" {
+                // `Groups { ... }`
+                //  ^^^^^^^^^^^^^^
+                if let Some(g) = errors.check(syn::parse2::<Groups>(tokens)) {
+                    groups.0.extend(g.0);
+                }
+            } else if block_name == "Names" {
+                // `Names { ... }`
+                //  ^^^^^^^^^^^^^
+                if names.is_some() {
+                    errors.push(Error::new(
+                        block_name.span(),
+                        "`Names { ... }` is declared more than once",
+                    ));
+                }
+
+                names = errors.check(syn::parse2::<TypeNames>(tokens)).or(names);
+            } else if block_name == "Context" {
+                // `Context { ... }`
+                //  ^^^^^^^^^^^^^^^
+                if context.is_some() {
+                    errors.push(Error::new(
+                        block_name.span(),
+                        "`Context { ... }` is declared more than once",
+                    ));
+                }
+
+                context = errors.check(syn::parse2::<Context>(tokens)).or(context);
+            } else if block_name == "Defer" {
+                // `Defer { ... }`
+                //  ^^^^^^^^^^^^^
+                if let Some(d) = errors.check(syn::parse2::<Deferred>(tokens)) {
+                    deferred.0.extend(d.0);
+                }
+            } else if let Some(block) = reserved {
+                // `After { ... }`
+                //  ^^^^^
+                errors.push(Error::new(
+                    block_name.span(),
+                    format!(
+                        "`{}` is reserved for the `{}` block, and cannot be used as an event",
+                        block_name, block
+                    ),
+                ));
+            } else if block_name == "After" {
+                // `After(3s) { ... }`
+                //  ^^^^^^^^^^^^^^^^^
+                let parser = |input: ParseStream<'_>| Timeouts::parse_block(input, errors);
+                let result = parser.parse2(tokens);
+
+                if let Some((t, timeout_transitions)) = errors.check(result) {
+                    timeouts.0.extend(t.0);
+                    transitions.0.extend(timeout_transitions.0);
+                }
+            } else if block_name == "AfterTicks" {
+                // `AfterTicks(30) { ... }`
+                //  ^^^^^^^^^^^^^^^^^^^^^^
+                let parser = |input: ParseStream<'_>| TickTimeouts::parse_block(input, errors);
+                let result = parser.parse2(tokens);
+
+                if let Some((t, timeout_transitions)) = errors.check(result) {
+                    tick_timeouts.0.extend(t.0);
+                    transitions.0.extend(timeout_transitions.0);
+                }
+            } else if block_name == "Submachine" {
+                // `Submachine(Reload: Ejecting) { ... }`
+                //  ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+                let parser = |input: ParseStream<'_>| Submachines::parse_block(input, errors);
+                let result = parser.parse2(tokens);
+
+                if let Some((s, completed_transitions)) = errors.check(result) {
+                    submachines.0.extend(s.0);
+                    transitions.0.extend(completed_transitions.0);
+                }
+            } else {
+                // `pub(crate) Push { ... }`
+                //  ^^^^^^^^^^
+                if visibility != Visibility::Inherited {
+                    let declared = event_visibilities
+                        .iter()
+                        .find(|(e, _)| e == &block_name)
+                        .map(|(_, v)| v);
+
+                    if imports.contains(&block_name) {
+                        errors.push(Error::new(
+                            block_name.span(),
+                            format!(
+                                "imported event `{}` cannot have a visibility, as it is declared elsewhere",
+                                block_name
+                            ),
+                        ));
+                    } else if declared.is_some() && declared != Some(&visibility) {
+                        errors.push(Error::new(
+                            block_name.span(),
+                            format!(
+                                "event `{}` is declared with a different visibility before",
+                                block_name
+                            ),
+                        ));
+                    } else if declared.is_none() {
+                        event_visibilities.push((block_name.clone(), visibility));
+                    }
+                }
+
+                // `Push { ... }`
+                //  ^^^^^^^^^^^^
+                let parser = |input: ParseStream<'_>| Transitions::parse_block(input, errors);
+                let result = parser.parse2(tokens);
+
+                if let Some(t) = errors.check(result) {
+                    transitions.0.extend(t.0);
+                }
+            }
+        }
+
+        let initial_states = initial_states.unwrap_or_else(|| {
+            if !misnamed {
+                errors.push(Error::new(
+                    first.unwrap_or_else(|| name.span()),
+                    "expected `InitialStates { ... }` block",
+                ));
+            }
+
+            InitialStates(Vec::new())
+        });
+
+        // Groups used as the source of a transition are expanded into one
+        // transition for each of their member states. Transitions that are
+        // declared more than once are only generated once, except for choices
+        // listing the same target state twice, which are rejected by `validate`.
+        let mut expanded: Vec<Transition> = Vec::new();
+        for t in transitions.0 {
+            let group = groups.find(&t.from.name).is_some();
+
+            for from in groups.expand(&t.from) {
+                let transition = Transition { from, ..t.clone() };
+
+                if (t.choice && !group) || !expanded.contains(&transition) {
+                    expanded.push(transition);
+                }
+            }
+        }
+        let transitions = Transitions(expanded);
+        let deferred = Deferred(
+            deferred
+                .0
+                .into_iter()
+                .flat_map(|d| {
+                    groups
+                        .expand(&d.state)
+                        .into_iter()
+                        .map(move |state| Defer { state, ..d.clone() })
+                })
+                .collect(),
+        );
+        let timeouts = Timeouts(
+            timeouts
+                .0
+                .into_iter()
+                .flat_map(|t| {
+                    groups
+                        .expand(&t.state)
+                        .into_iter()
+                        .map(move |state| Timeout { state, ..t.clone() })
+                })
+                .collect(),
+        );
+        let tick_timeouts = TickTimeouts(
+            tick_timeouts
+                .0
+                .into_iter()
+                .flat_map(|t| {
+                    groups
+                        .expand(&t.state)
+                        .into_iter()
+                        .map(move |state| TickTimeout { state, ..t.clone() })
+                })
+                .collect(),
+        );
+        let final_states = FinalStates(
+            final_states
+                .0
+                .iter()
+                .flat_map(|s| groups.expand(s))
+                .collect(),
+        );
+        let submachines = Submachines(
+            submachines
+                .0
+                .into_iter()
+                .flat_map(|s| {
+                    groups
+                        .expand(&s.state)
+                        .into_iter()
+                        .map(move |state| Submachine { state, ..s.clone() })
+                })
+                .collect(),
+        );
+
+        let names = names.unwrap_or_default();
+        let machine = Machine {
+            attributes: Attributes::default(),
+            visibility: Visibility::Inherited,
+            name,
+            generics,
+            imports,
+            initial_states,
+            final_states,
+            transitions,
+            deferred,
+            timeouts,
+            tick_timeouts,
+            submachines,
+            groups,
+            names,
+            context,
+            krate: CratePath::default(),
+            event_visibilities,
+            handler,
+        };
+
+        // Validating a machine with blocks that failed to parse would mostly
+        // report problems caused by the missing blocks.
+        if errors.len() == before {
+            machine.validate(errors);
+        }
+
+        Ok(machine)
+    }
+}
+
+/// has_arrow returns whether the body of the passed in block contains a `=>`,
+/// as used by the transitions of a block.
+fn has_arrow(block: &TokenStream) -> bool {
+    let body = block.clone().into_iter().find_map(|tt| match tt {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => Some(group.stream()),
+        _ => None,
+    });
+
+    let mut equals = false;
+    for tt in body.into_iter().flatten() {
+        match tt {
+            TokenTree::Punct(punct) if equals && punct.as_char() == '>' => return true,
+            TokenTree::Punct(punct) => equals = punct.as_char() == '=',
+            _ => equals = false,
+        }
+    }
+
+    false
+}
+
+/// next_block returns the tokens of the next block, up to and including its
+/// body between curly braces, so that the block can be parsed on its own, and
+/// skipped if it fails to parse.
+fn next_block(input: ParseStream<'_>) -> Result<TokenStream> {
+    input.step(|cursor| {
+        let mut tokens = TokenStream::new();
+        let mut rest = *cursor;
+
+        while let Some((tt, next)) = rest.token_tree() {
+            let body = match &tt {
+                TokenTree::Group(group) => group.delimiter() == Delimiter::Brace,
+                _ => false,
+            };
+
+            tokens.extend(Some(tt));
+            rest = next;
+
+            if body {
+                return Ok((tokens, rest));
+            }
+        }
+
+        let name = tokens.clone().into_iter().next();
+        Err(Error::new_spanned(
+            &tokens,
+            format!(
+                "expected `{{ ... }}` after `{}`",
+                name.map(|n| n.to_string()).unwrap_or_default()
+            ),
+        ))
+    })
+}
+
+impl ToTokens for Machine {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let attributes = &self.attributes;
+        let visibility = &self.visibility;
+        let name = &self.name;
+        let imports = &self.imports;
+        let krate = &self.krate;
+        let scope = scope(
+            self.imports
+                .0
+                .iter()
+                .filter(|i| i.path.len() > 1)
+                .map(|i| (i.global, i.path.as_slice()))
+                .chain(self.context.iter().map(|c| (c.global, c.path.as_slice())))
+                .chain(
+                    self.submachines
+                        .0
+                        .iter()
+                        .map(|s| (s.global, s.path.as_slice())),
+                )
+                .chain(Some((krate.global, krate.path.as_slice()))),
+        );
+        let states = &self.states();
+        let machine_enum = MachineEnum { machine: &self };
+        let machine_type = &self.names.machine;
+        let generics = &self.generics;
+        let params = &generics.params();
+        let args = &generics.args();
+        let phantom = generics.phantom();
+        let phantom_new = generics.phantom_new();
+
+        let mut events = TokenStream::new();
+        let mut nested: Vec<(Ident, TokenStream)> = Vec::new();
+
+        for submachine in &self.submachines.0 {
+            let name = submachine.name();
+            let module = submachine.module();
+            let child_events = &submachine.names.events;
+
+            if !nested.iter().any(|(n, _)| n == name) {
+                nested.push((name.clone(), quote! { #module::#child_events }));
+            }
+        }
+
+        self.events()
+            .to_tokens_with(&mut events, &self.imports, krate, &self.event_visibilities);
+        self.events().enum_to_tokens(
+            &mut events,
+            &nested,
+            &self.imports,
+            &self.names.events,
+            &self.events_generics(),
+        );
+        let mut transitions = TokenStream::new();
+
+        let mut fields = TokenStream::new();
+        let mut ticks_bound = TokenStream::new();
+        let mut ticks_new = TokenStream::new();
+        let mut ticks = TokenStream::new();
+
+        // The transitions completing a submachine are generated separately,
+        // so that they cannot be triggered without the submachine.
+        let (completed, uncompleted): (Vec<Transition>, Vec<Transition>) = self
+            .transitions
+            .0
+            .iter()
+            .cloned()
+            .partition(|t| self.completes(t));
+        let (completed, uncompleted) = (Transitions(completed), Transitions(uncompleted));
+        let methods = uncompleted.methods(
+            &self.imports,
+            machine_type,
+            krate,
+            generics,
+            &self.event_visibilities,
+        );
+
+        // Proc macros cannot emit warnings on stable Rust, so states without
+        // any outgoing transitions are reported by using a deprecated constant,
+        // spanned to the first occurrence of the state.
+        let dead_ends = self
+            .dead_ends()
+            .into_iter()
+            .map(|state| {
+                let span = state.name.span();
+                let note = format!(
+                    "state `{0}` has no outgoing transitions, add `FinalStates {{ {0} }}` if this is intended",
+                    state.name
+                );
+
+                quote_spanned! { span=>
+                    const _: () = {
+                        #[deprecated(note = #note)]
+                        const DEAD_END: () = ();
+                        DEAD_END
+                    };
+                }
+            })
+            .collect::<TokenStream>();
+
+        // Each event has a capability trait, implemented by any machine that
+        // can be transitioned using the event, which allows writing generic
+        // code without naming the state of the machine.
+        let mut capabilities = TokenStream::new();
+        for event in self.capability_events() {
+            let capability = Ident::new(&format!("Can{}", event), event.span());
+            let visibility = visibility::of(&self.event_visibilities, &event);
+            let event = self.imports.ty(&event);
+            let used = generics.used_by(&[&event]);
+            let (used_params, used_apply) = (used.params(), used.apply());
+
+            capabilities.extend(quote! {
+                #visibility trait #capability #used:
+                    #krate::Transition<#event, Machine = <Self as #capability #used_apply>::Next>
+                {
+                    type Next;
+                }
+
+                impl<#used_params M: #krate::Transition<#event>> #capability #used_apply for M {
+                    type Next = M::Machine;
+                }
+            });
+        }
+
+        // Imported states and events are defined by the user, so only the
+        // states and events declared by the machine itself are generated.
+        let local_states = States(
+            states
+                .0
+                .iter()
+                .filter(|s| !self.imports.contains(&s.name))
+                .cloned()
+                .collect(),
+        );
+        let initial_states = InitialStates(
+            self.initial_states
+                .0
+                .iter()
+                .filter(|s| !self.imports.contains(&s.name))
+                .cloned()
+                .collect::<Vec<InitialState>>(),
+        );
+
+        let mut state_impls = TokenStream::new();
+        local_states.to_tokens_with(&mut state_impls, krate);
+        initial_states.to_tokens_with(&mut state_impls, krate);
+
+        let diagnostics = self.diagnostics();
+
+        let mut groups = TokenStream::new();
+        self.groups
+            .to_tokens_with(&mut groups, &self.imports, machine_type, krate, generics);
+
+        // Events that are less visible than the machine are still part of the
+        // generated enums, which is allowed on purpose, as it keeps them from
+        // being constructed outside of their visibility.
+        let mut private_interfaces = TokenStream::new();
+        if self
+            .event_visibilities
+            .iter()
+            .any(|(_, v)| v.nested() != Visibility::Public)
+        {
+            private_interfaces = quote! { #[allow(private_interfaces)] };
+        }
+
+        let carried = Fields {
+            ticks: !self.tick_timeouts.0.is_empty(),
+            context: self.context.as_ref(),
+        };
+
+        uncompleted.to_tokens_with(
+            &mut transitions,
+            &self.imports,
+            machine_type,
+            krate,
+            generics,
+            carried,
+        );
+        completed.completed_to_tokens_with(
+            &mut transitions,
+            &self.imports,
+            machine_type,
+            krate,
+            generics,
+            carried,
+        );
+
+        if !self.tick_timeouts.0.is_empty() {
+            fields = quote! { , u32 };
+            ticks_bound = quote! { + Ticks };
+            ticks_new = quote! { , S::TICKS };
+
+            let names = states
+                .0
+                .iter()
+                .map(|s| self.imports.ty(&s.name))
+                .collect::<Vec<_>>();
+            let names_generics = names
+                .iter()
+                .map(|name| generics.used_by(&[name]))
+                .collect::<Vec<_>>();
+            let counts = states
+                .0
+                .iter()
+                .map(|s| {
+                    self.tick_timeouts
+                        .0
+                        .iter()
+                        .find(|t| t.state.name == s.name)
+                        .map_or(0, |t| t.ticks)
+                })
+                .collect::<Vec<_>>();
+
+            ticks = quote! {
+                pub trait Ticks: #krate::State {
+                    const TICKS: u32;
+                }
+
+                #(
+                    impl #names_generics Ticks for #names {
+                        const TICKS: u32 = #counts;
+                    }
+                )*
+
+                impl<#params S: Ticks, E: #krate::Event> #machine_type<#args S, E> {
+                    pub fn remaining_ticks(&self) -> ::core::option::Option<u32> {
+                        if S::TICKS == 0 {
+                            ::core::option::Option::None
+                        } else {
+                            ::core::option::Option::Some(self.2)
+                        }
+                    }
+                }
+            };
+        }
+
+        // A machine with a context cannot implement `Initializer`, as it has
+        // to be created with its initial context.
+        let mut context_field = TokenStream::new();
+        let mut accessors = TokenStream::new();
+        let initializer = match &self.context {
+            Some(context) => {
+                let index = carried.index();
+                context_field = quote! { , #context };
+                accessors = quote! {
+                    pub fn context(&self) -> &#context {
+                        &self.#index
+                    }
+
+                    pub fn context_mut(&mut self) -> &mut #context {
+                        &mut self.#index
+                    }
+                };
+
+                quote! {
+                    impl<#params S: #krate::State + diagnostics::Initial #ticks_bound> #machine_type<#args S, #krate::NoneEvent> {
+                        pub fn new(state: S, context: #context) -> Self {
+                            #machine_type(state, ::core::option::Option::None #ticks_new, context #phantom_new)
+                        }
+                    }
+                }
+            }
+            None => quote! {
+                impl<#params S: #krate::InitialState #ticks_bound> #krate::Initializer<S> for #machine_type<#args S, #krate::NoneEvent> {
+                    type Machine = #machine_type<#args S, #krate::NoneEvent>;
+
+                    fn new(state: S) -> Self::Machine {
+                        #machine_type(state, ::core::option::Option::None #ticks_new #phantom_new)
+                    }
+                }
+
+                // The method of the trait implemented above is made available
+                // without importing the trait, so that using the machine does
+                // not depend on what is in scope. It is bound by the `Initial`
+                // trait of the machine instead, for a readable error.
+                impl<#params S: #krate::State + diagnostics::Initial #ticks_bound> #machine_type<#args S, #krate::NoneEvent> {
+                    pub fn new(state: S) -> Self {
+                        #machine_type(state, ::core::option::Option::None #ticks_new #phantom_new)
+                    }
+                }
+            },
+        };
+
+        tokens.extend(quote! {
+            #attributes
+            #[allow(non_snake_case)]
+            #private_interfaces
+            #visibility mod #name {
+                #scope
+                #imports
+
+                #[derive(Debug, Eq, PartialEq, Clone)]
+                pub struct #machine_type<#params S: #krate::State, E: #krate::Event>(S, ::core::option::Option<E> #fields #context_field #phantom);
+
+                impl<#params S: #krate::State, E: #krate::Event> #krate::Machine for #machine_type<#args S, E> {
+                    type State = S;
+                    type Event = E;
+
+                    fn state(&self) -> Self::State {
+                        self.0.clone()
+                    }
+
+                    fn trigger(&self) -> ::core::option::Option<Self::Event> {
+                        self.1.clone()
+                    }
+                }
+
+                #initializer
+
+                impl<#params S: #krate::State, E: #krate::Event> #machine_type<#args S, E> {
+                    pub fn state(&self) -> S {
+                        #krate::Machine::state(self)
+                    }
+
+                    pub fn trigger(&self) -> ::core::option::Option<E> {
+                        #krate::Machine::trigger(self)
+                    }
+
+                    pub fn as_enum(self) -> <Self as #krate::AsEnum>::Enum
+                    where
+                        Self: #krate::AsEnum,
+                    {
+                        #krate::AsEnum::as_enum(self)
+                    }
+
+                    #accessors
+                }
+
+                #state_impls
+                #diagnostics
+                #events
+                #machine_enum
+                #groups
+                #transitions
+                #methods
+                #capabilities
+                #ticks
+                #dead_ends
+            }
+        });
+    }
+}
+
+#[derive(Debug)]
+#[allow(single_use_lifetimes)]
+struct MachineEnum<'a> {
+    machine: &'a Machine,
+}
+
+#[allow(single_use_lifetimes)]
+impl<'a> ToTokens for MachineEnum<'a> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let krate = &self.machine.krate;
+        let machine_type = &self.machine.names.machine;
+        let variant_type = &self.machine.names.variant;
+        let events_type = &self.machine.names.events;
+        let imports = &self.machine.imports;
+        let generics = &self.machine.generics;
+        let args = &generics.args();
+        let apply = &generics.apply();
+        let variant_ty = &quote! { #variant_type #apply };
+        let events_generics = self.machine.events_generics().apply();
+        let events_ty = &quote! { #events_type #events_generics };
+
+        let mut variants = Vec::new();
+        let mut states = Vec::new();
+        let mut events = Vec::new();
+
+        for s in &self.machine.initial_states.0 {
+            let name = s.name.clone();
+            let none = quote! { #krate::NoneEvent };
+            let variant = Ident::new(&format!("Initial{}", name), Span::call_site());
+
+            variants.push(variant);
+            states.push(name);
+            events.push(none);
+        }
+
+        for t in &self.machine.transitions.0 {
+            for to in Some(&t.to).into_iter().chain(&t.error) {
+                let state = to.name.clone();
+                let event = t.event.name.clone();
+                let variant = Ident::new(&format!("{}By{}", state, event), Span::call_site());
+
+                // Variants resulting from different states and events are
+                // rejected by `validate`.
+                if variants.contains(&variant) {
+                    continue;
+                }
+
+                variants.push(variant);
+                states.push(state);
+                events.push(imports.ty(&event));
+            }
+        }
+
+        // Variants of states that embed a submachine carry the variant of the
+        // submachine as a second field.
+        let mut fields = Vec::new();
+        let mut bindings = Vec::new();
+        let mut wildcards = Vec::new();
+        let mut entries = Vec::new();
+
+        for (variant, state) in variants.iter().zip(&states) {
+            match self.machine.submachine(state) {
+                Some(submachine) => {
+                    let module = submachine.module();
+                    let entry = &submachine.entry.name;
+                    let child_machine = &submachine.names.machine;
+                    let child_variant = &submachine.names.variant;
+
+                    fields.push(quote! { , #module::#child_variant });
+                    bindings.push(quote! { , child });
+                    wildcards.push(quote! { _, _ });
+                    entries.push(quote! {
+                        #variant_type::#variant(self, #module::#child_machine::new(#module::#entry).as_enum())
+                    });
+                }
+                None => {
+                    fields.push(quote! {});
+                    bindings.push(quote! {});
+                    wildcards.push(quote! { _ });
+                    entries.push(quote! { #variant_type::#variant(self) });
+                }
+            }
+        }
+
+        let mut arms = Vec::new();
+
+        for ((variant, state), bind) in variants.iter().zip(&states).zip(&bindings) {
+            let mut chosen: Vec<&Ident> = Vec::new();
+
+            for t in &self.machine.transitions.0 {
+                if &t.from.name != state || self.machine.completes(t) {
+                    continue;
+                }
+
+                let event = &t.event.name;
+
+                // A choice has a single arm, shared by all of its targets.
+                if t.choice {
+                    if chosen.contains(&event) {
+                        continue;
+                    }
+
+                    chosen.push(event);
+                }
+
+                if t.error.is_some() {
+                    arms.push(quote! {
+                        (#variant_type::#variant(m #bind), #events_type::#event(e)) => match m.transition(e) {
+                            ::core::result::Result::Ok(m) => ::core::result::Result::Ok(m.as_enum()),
+                            ::core::result::Result::Err((m, _)) => ::core::result::Result::Ok(m.as_enum()),
+                        },
+                    });
+                } else {
+                    arms.push(quote! {
+                        (#variant_type::#variant(m #bind), #events_type::#event(e)) => ::core::result::Result::Ok(m.transition(e).as_enum()),
+                    });
+                }
+            }
+
+            if let Some(submachine) = self.machine.submachine(state) {
+                let name = submachine.name();
+
+                arms.push(quote! {
+                    (#variant_type::#variant(m, child), #events_type::#name(e)) => match #krate::Dispatch::dispatch(child, e) {
+                        ::core::result::Result::Ok(child) => {
+                            if child.is_final() {
+                                ::core::result::Result::Ok(m.completed().as_enum())
+                            } else {
+                                ::core::result::Result::Ok(#variant_type::#variant(m, child))
+                            }
+                        }
+                        ::core::result::Result::Err((child, e)) => ::core::result::Result::Err((#variant_type::#variant(m, child), #events_type::#name(e))),
+                    },
+                });
+            }
+        }
+
+        let mut defer_variants = Vec::new();
+        let mut defer_wildcards = Vec::new();
+        let mut defer_events = Vec::new();
+
+        for ((variant, state), wildcard) in variants.iter().zip(&states).zip(&wildcards) {
+            for d in &self.machine.deferred.0 {
+                if &d.state.name == state {
+                    defer_variants.push(variant.clone());
+                    defer_wildcards.push(wildcard.clone());
+                    defer_events.push(d.event.name.clone());
+                }
+            }
+        }
+
+        let defers = if defer_variants.is_empty() {
+            quote! {}
+        } else {
+            let defer_types = vec![variant_type; defer_variants.len()];
+            let defer_events_types = vec![events_type; defer_variants.len()];
+
+            quote! {
+                fn defers(&self, event: &#events_ty) -> bool {
+                    match (self, event) {
+                        #((#defer_types::#defer_variants(#defer_wildcards), #defer_events_types::#defer_events(_)) => true,)*
+                        _ => false,
+                    }
+                }
+            }
+        };
+
+        let mut timeout_variants = Vec::new();
+        let mut timeout_wildcards = Vec::new();
+        let mut timeout_millis = Vec::new();
+
+        for ((variant, state), wildcard) in variants.iter().zip(&states).zip(&wildcards) {
+            for t in &self.machine.timeouts.0 {
+                if &t.state.name == state {
+                    timeout_variants.push(variant.clone());
+                    timeout_wildcards.push(wildcard.clone());
+                    timeout_millis.push(t.millis);
+                }
+            }
+        }
+
+        let timeout = if timeout_variants.is_empty() {
+            quote! {}
+        } else {
+            let timeout_types = vec![variant_type; timeout_variants.len()];
+            let timeout_events_types = vec![events_type; timeout_variants.len()];
+
+            quote! {
+                fn timeout(&self) -> ::core::option::Option<(::core::time::Duration, #events_ty)> {
+                    match self {
+                        #(#timeout_types::#timeout_variants(#timeout_wildcards) => ::core::option::Option::Some((
+                            ::core::time::Duration::from_millis(#timeout_millis),
+                            #timeout_events_types::Timeout(Timeout),
+                        )),)*
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            }
+        };
+
+        let mut tick_variants = Vec::new();
+        let mut tick_bindings = Vec::new();
+
+        for ((variant, state), bind) in variants.iter().zip(&states).zip(&bindings) {
+            if self
+                .machine
+                .tick_timeouts
+                .0
+                .iter()
+                .any(|t| &t.state.name == state)
+            {
+                tick_variants.push(variant.clone());
+                tick_bindings.push(bind.clone());
+            }
+        }
+
+        let tick = if self.machine.tick_timeouts.0.is_empty() {
+            quote! {}
+        } else {
+            let tick_variants_once = &tick_variants;
+            let tick_variants_again = &tick_variants;
+            let tick_bindings_once = &tick_bindings;
+            let tick_bindings_again = &tick_bindings;
+            let tick_types = &vec![variant_type; tick_variants.len()];
+            let tick_types_again = tick_types;
+
+            quote! {
+                impl #generics #variant_ty {
+                    #[allow(unreachable_patterns)]
+                    pub fn tick(self) -> Self {
+                        match self {
+                            #(#tick_types::#tick_variants_once(mut m #tick_bindings_once) => {
+                                m.2 -= 1;
+
+                                if m.2 == 0 {
+                                    m.transition(TickTimeout).as_enum()
+                                } else {
+                                    #tick_types_again::#tick_variants_again(m #tick_bindings_again)
+                                }
+                            })*
+                            variant => variant,
+                        }
+                    }
+                }
+            }
+        };
+
+        let mut choices = TokenStream::new();
+        let mut choice_names: Vec<Ident> = Vec::new();
+
+        for t in &self.machine.transitions.0 {
+            let name = Ident::new(
+                &format!("{}From{}", t.event.name, t.from.name),
+                Span::call_site(),
+            );
+
+            if !t.choice || choice_names.contains(&name) {
+                continue;
+            }
+
+            let (targets, events): (Vec<_>, Vec<_>) = self
+                .machine
+                .transitions
+                .0
+                .iter()
+                .filter(|c| c.choice && c.event.name == t.event.name && c.from.name == t.from.name)
+                .map(|c| (&c.to.name, imports.ty(&c.event.name)))
+                .unzip();
+            let types = targets.iter().map(|t| imports.ty(t)).collect::<Vec<_>>();
+            let (choice_variants, arms, branches) = (&targets, &targets, &targets);
+            let branch = Ident::new(&format!("{}Branch", name), Span::call_site());
+            let enums = vec![&name; targets.len()];
+            let machine_types = vec![machine_type; targets.len()];
+            let argss = vec![args; targets.len()];
+            let visibility = visibility::of(&self.machine.event_visibilities, &t.event.name);
+
+            choices.extend(quote! {
+                #[derive(Debug, Clone)]
+                #visibility enum #name #generics {
+                    #(#choice_variants(#machine_types<#argss #types, #events>)),*
+                }
+
+                // The branches are only constructed by the `Choose`
+                // implementation of the event, which may never select some.
+                #[allow(dead_code)]
+                #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+                #visibility enum #branch {
+                    #(#branches),*
+                }
+
+                impl #generics #krate::AsEnum for #name #apply {
+                    type Enum = #variant_ty;
+
+                    fn as_enum(self) -> Self::Enum {
+                        match self {
+                            #(#enums::#arms(m) => m.as_enum(),)*
+                        }
+                    }
+                }
+
+                impl #generics #name #apply {
+                    pub fn as_enum(self) -> #variant_ty {
+                        #krate::AsEnum::as_enum(self)
+                    }
+                }
+            });
+
+            choice_names.push(name);
+        }
+
+        let mut unique_states: Vec<&Ident> = Vec::new();
+        for state in &states {
+            if !unique_states.contains(&state) {
+                unique_states.push(state);
+            }
+        }
+
+        // The handler has a method for each variant that can be transitioned
+        // further, while the variants of final states are returned by `run`.
+        let mut handler = TokenStream::new();
+
+        if self.machine.handler {
+            let mut handler_variants = Vec::new();
+            let mut handler_methods = Vec::new();
+            let mut handler_params = Vec::new();
+            let mut handler_bindings = Vec::new();
+
+            for (((variant, state), event), bind) in
+                variants.iter().zip(&states).zip(&events).zip(&bindings)
+            {
+                if !self
+                    .machine
+                    .transitions
+                    .0
+                    .iter()
+                    .any(|t| &t.from.name == state)
+                {
+                    continue;
+                }
+
+                let child = match self.machine.submachine(state) {
+                    Some(submachine) => {
+                        let module = submachine.module();
+                        let child_variant = &submachine.names.variant;
+                        quote! { , child: #module::#child_variant }
+                    }
+                    None => quote! {},
+                };
+
+                handler_variants.push(variant.clone());
+                handler_methods.push(snake_case("on_", variant));
+                let state = imports.ty(state);
+                handler_params.push(quote! { m: #machine_type<#args #state, #event> #child });
+                handler_bindings.push(bind.clone());
+            }
+
+            let name = Ident::new(&format!("{}Handler", self.machine.name), Span::call_site());
+            let handler_methods_again = &handler_methods;
+            let handler_methods = &handler_methods;
+            let handler_bindings_again = &handler_bindings;
+            let handler_bindings = &handler_bindings;
+            let handler_types = &vec![variant_ty; handler_variants.len()];
+            let handler_types_again = &vec![variant_type; handler_variants.len()];
+
+            handler = quote! {
+                pub trait #name #generics {
+                    #(fn #handler_methods(&mut self, #handler_params) -> #handler_types;)*
+                }
+
+                impl #generics #variant_ty {
+                    #[allow(unreachable_patterns)]
+                    pub fn run<H: #name #apply>(self, handler: &mut H) -> #variant_ty {
+                        let mut variant = self;
+
+                        loop {
+                            variant = match variant {
+                                #(#handler_types_again::#handler_variants(m #handler_bindings) => {
+                                    handler.#handler_methods_again(m #handler_bindings_again)
+                                })*
+                                variant => return variant,
+                            };
+                        }
+                    }
+                }
+            };
+        }
+
+        let mut is_in = TokenStream::new();
+
+        if !self.machine.groups.0.is_empty() {
+            let mut is_in_variants = Vec::new();
+            let mut is_in_groups = Vec::new();
+
+            for (variant, state) in variants.iter().zip(&states) {
+                for group in &self.machine.groups.0 {
+                    if group.contains(state) {
+                        is_in_variants.push(variant.clone());
+                        is_in_groups.push(group.name.clone());
+                    }
+                }
+            }
+
+            let is_in_types = vec![variant_type; is_in_variants.len()];
+
+            is_in = quote! {
+                impl #generics #variant_ty {
+                    #[allow(unreachable_patterns)]
+                    pub fn is_in(&self, group: Group) -> bool {
+                        match (self, group) {
+                            #((#is_in_types::#is_in_variants(..), Group::#is_in_groups) => true,)*
+                            _ => false,
+                        }
+                    }
+                }
+            };
+        }
+
+        // A submachine is completed once its variant is in one of its final
+        // states.
+        let final_variants = variants
+            .iter()
+            .zip(&states)
+            .filter(|(_, state)| self.machine.final_states.contains(state))
+            .map(|(variant, _)| variant)
+            .collect::<Vec<_>>();
+        let final_types = vec![variant_type; final_variants.len()];
+
+        // States can be compared to any other state of the machine, including
+        // imported states, which do not implement `PartialEq` for each other.
+        let mut is_states = Vec::new();
+        let mut is_others = Vec::new();
+        let mut is_generics = Vec::new();
+        for state in &unique_states {
+            for other in &unique_states {
+                if state != other {
+                    let (state, other) = (imports.ty(state), imports.ty(other));
+
+                    is_generics.push(generics.used_by(&[&state, &other]));
+                    is_states.push(state);
+                    is_others.push(other);
+                }
+            }
+        }
+        let unique_states = unique_states
+            .iter()
+            .map(|s| imports.ty(s))
+            .collect::<Vec<_>>();
+        let is_others_again = &is_others;
+        let is_others = &is_others;
+
+        let variants = &variants;
+        let states = &states.iter().map(|s| imports.ty(s)).collect::<Vec<_>>();
+        let events = &events;
+        let is_variants = variants;
+        let machine_types = &vec![machine_type; variants.len()];
+        let machine_types_again = machine_types;
+        let argss = &vec![args; variants.len()];
+        let argss_again = argss;
+        let impl_generics = &vec![generics; variants.len()];
+        let variant_tys = &vec![variant_ty; variants.len()];
+        let variant_types_again = &vec![variant_type; variants.len()];
+        let krates = &vec![krate; variants.len()];
+
+        tokens.extend(quote! {
+            #[derive(Debug, Clone)]
+            pub enum #variant_type #generics {
+                #(#variants(#machine_types<#argss #states, #events> #fields)),*
+            }
+
+            #(
+                impl #impl_generics #krates::AsEnum for #machine_types_again<#argss_again #states, #events> {
+                    type Enum = #variant_tys;
+
+                    fn as_enum(self) -> Self::Enum {
+                        #entries
+                    }
+                }
+            )*
+
+            pub trait IsState<S> {
+                fn is_state(&self, state: &S) -> bool;
+            }
+
+            impl<S: ::core::cmp::PartialEq> IsState<S> for S {
+                fn is_state(&self, state: &S) -> bool {
+                    self == state
+                }
+            }
+
+            #(
+                impl #is_generics IsState<#is_others> for #is_states {
+                    fn is_state(&self, _: &#is_others_again) -> bool {
+                        false
+                    }
+                }
+            )*
+
+            impl #generics #variant_ty {
+                pub fn is<S: #krate::State>(&self, state: &S) -> bool
+                where
+                    #(#unique_states: IsState<S>),*
+                {
+                    match self {
+                        #(#variant_types_again::#is_variants(m, ..) => m.0.is_state(state),)*
+                    }
+                }
+
+                #[allow(unreachable_patterns)]
+                pub fn is_final(&self) -> bool {
+                    match self {
+                        #(#final_types::#final_variants(..) => true,)*
+                        _ => false,
+                    }
+                }
+            }
+
+            impl #generics #krate::Dispatch for #variant_ty {
+                type Event = #events_ty;
+
+                #[allow(unreachable_patterns)]
+                fn dispatch(self, event: #events_ty) -> ::core::result::Result<Self, (Self, #events_ty)> {
+                    match (self, event) {
+                        #(#arms)*
+                        (m, e) => ::core::result::Result::Err((m, e)),
+                    }
+                }
+
+                #defers
+                #timeout
+            }
+
+            #tick
+            #choices
+            #handler
+            #is_in
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sm::{defer::Defer, initial_state::InitialState, transition::Transition};
+    use proc_macro2::TokenStream;
+    use syn::{self, parse_quote};
+
+    #[test]
+    fn test_machine_parse() {
+        let left: Machine = syn::parse2(quote! {
+           TurnStile {
+               InitialStates { Locked, Unlocked }
+
+               Coin { Locked => Unlocked }
+               Push { Unlocked => Locked }
+           }
+        })
+        .unwrap();
+
+        let right = Machine {
+            attributes: Attributes::default(),
+            visibility: Visibility::Inherited,
+            name: parse_quote! { TurnStile },
+            generics: Generics::default(),
+            imports: Imports(vec![]),
+            initial_states: InitialStates(vec![
+                InitialState {
+                    name: parse_quote! { Locked },
+                },
+                InitialState {
+                    name: parse_quote! { Unlocked },
+                },
+            ]),
+            final_states: FinalStates(vec![]),
+            transitions: Transitions(vec![
+                Transition {
+                    event: Event {
+                        name: parse_quote! { Coin },
+                    },
+                    from: State {
+                        name: parse_quote! { Locked },
+                    },
+                    to: State {
+                        name: parse_quote! { Unlocked },
+                    },
+                    choice: false,
+                    error: None,
+                },
+                Transition {
+                    event: Event {
+                        name: parse_quote! { Push },
+                    },
+                    from: State {
+                        name: parse_quote! { Unlocked },
+                    },
+                    to: State {
+                        name: parse_quote! { Locked },
+                    },
+                    choice: false,
+                    error: None,
+                },
+            ]),
+            deferred: Deferred(vec![]),
+            timeouts: Timeouts(vec![]),
+            tick_timeouts: TickTimeouts(vec![]),
+            submachines: Submachines(vec![]),
+            groups: Groups(vec![]),
+            names: TypeNames::default(),
+            context: None,
+            krate: CratePath::default(),
+            event_visibilities: vec![],
+            handler: false,
+        };
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_machine_parse_defer() {
+        let left: Machine = syn::parse2(quote! {
+           Game {
+               InitialStates { Loading }
+
+               Loaded { Loading => Playing }
+               Defer { Loading: Fire }
+               Fire { Playing => Playing }
+           }
+        })
+        .unwrap();
+
+        let right = Machine {
+            attributes: Attributes::default(),
+            visibility: Visibility::Inherited,
+            name: parse_quote! { Game },
+            generics: Generics::default(),
+            imports: Imports(vec![]),
+            initial_states: InitialStates(vec![InitialState {
+                name: parse_quote! { Loading },
+            }]),
+            final_states: FinalStates(vec![]),
+            transitions: Transitions(vec![
+                Transition {
+                    event: Event {
+                        name: parse_quote! { Loaded },
+                    },
+                    from: State {
+                        name: parse_quote! { Loading },
+                    },
+                    to: State {
+                        name: parse_quote! { Playing },
+                    },
+                    choice: false,
+                    error: None,
+                },
+                Transition {
+                    event: Event {
+                        name: parse_quote! { Fire },
+                    },
+                    from: State {
+                        name: parse_quote! { Playing },
+                    },
+                    to: State {
+                        name: parse_quote! { Playing },
+                    },
+                    choice: false,
+                    error: None,
+                },
+            ]),
+            deferred: Deferred(vec![Defer {
+                state: State {
+                    name: parse_quote! { Loading },
+                },
+                event: Event {
+                    name: parse_quote! { Fire },
+                },
+            }]),
+            timeouts: Timeouts(vec![]),
+            tick_timeouts: TickTimeouts(vec![]),
+            submachines: Submachines(vec![]),
+            groups: Groups(vec![]),
+            names: TypeNames::default(),
+            context: None,
+            krate: CratePath::default(),
+            event_visibilities: vec![],
+            handler: false,
+        };
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_machine_parse_defer_handled_event() {
+        let result: Result<Machine> = syn::parse2(quote! {
+           Game {
+               InitialStates { Loading }
+
+               Fire { Loading => Playing }
+               Defer { Loading: Fire }
+           }
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_machine_parse_unreachable_state() {
+        let result: Result<Machine> = syn::parse2(quote! {
+           Lock {
+               InitialStates { Locked }
+
+               TurnKey { Locked => Unlocked  Unlocked => Locked }
+               Break { Locked, Brokne => Broken }
+           }
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_machine_dead_ends() {
+        let machine: Machine = syn::parse2(quote! {
+           Lock {
+               InitialStates { Locked }
+               FinalStates { Broken }
+
+               TurnKey { Locked => Unlocked }
+               Break { Locked, Unlocked => Broken }
+               Repair { Broken => Repaired }
+           }
+        })
+        .unwrap();
+
+        let dead_ends = machine.dead_ends();
+
+        assert_eq!(dead_ends.len(), 1);
+        assert_eq!(dead_ends[0].name, "Repaired");
+    }
+
+    #[test]
+    fn test_machine_parse_nondeterministic() {
+        let mut errors = Errors::default();
+        let parser = |input: ParseStream<'_>| Machines::parse_with(input, &mut errors);
+        let machines = parser
+            .parse2(quote! {
+               Lock {
+                   InitialStates { Locked }
+
+                   TurnKey { Locked => Unlocked  Unlocked => Locked }
+                   TurnKey { Locked => Broken }
+                   TurnKey { Locked => Open }
+               }
+            })
+            .unwrap();
+
+        assert_eq!(machines.0.len(), 1);
+        // The first transition is reported once, and each of the conflicting
+        // transitions once as well.
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors.finish(Ok(())).unwrap_err().to_string(),
+            "nondeterministic transition, event `TurnKey` in state `Locked` leads to both `Unlocked` and `Broken`"
+        );
+    }
+
+    #[test]
+    fn test_machine_parse_duplicate_transition() {
+        let machine: Machine = syn::parse2(quote! {
+           Lock {
+               InitialStates { Locked }
+
+               TurnKey { Locked => Unlocked  Unlocked => Locked }
+               TurnKey { Locked => Unlocked }
+           }
+        })
+        .unwrap();
+
+        assert_eq!(machine.transitions.0.len(), 2);
+    }
+
+    #[test]
+    fn test_machine_parse_name_collisions() {
+        let cases = vec![
+            (
+                quote! {
+                    Lock {
+                        InitialStates { Locked }
+                        TurnKey { Locked => Machine  Machine => Locked }
+                    }
+                },
+                "state `Machine` has the same name as the generated `Machine` type, which can be renamed using `Names { Machine as ... }`",
+            ),
+            (
+                quote! {
+                    Lock {
+                        InitialStates { Locked }
+                        Locked { Locked => Unlocked  Unlocked => Locked }
+                    }
+                },
+                "state `Locked` has the same name as event `Locked`",
+            ),
+            (
+                quote! {
+                    Lock {
+                        InitialStates { Locked }
+                        TurnKey { Locked => CanTurnKey  CanTurnKey => Locked }
+                    }
+                },
+                "state `CanTurnKey` has the same name as the `CanTurnKey` trait generated for event `TurnKey`",
+            ),
+            (
+                quote! {
+                    Lock {
+                        InitialStates { Locked }
+                        TurnKey { Locked => Unlocked  Unlocked => Locked }
+                        Names { Machine as Variant }
+                    }
+                },
+                "`Machine` is renamed to `Variant`, which is already the name of the generated `Variant` type",
+            ),
+            (
+                quote! {
+                    Lock {
+                        InitialStates { Locked }
+                        TurnKey { Locked => Lock  Lock => Locked }
+                        Names { Machine as Lock }
+                    }
+                },
+                "state `Lock` has the same name as the generated `Machine` type, renamed to `Lock`",
+            ),
+            (
+                quote! {
+                    Gun {
+                        InitialStates { Idle }
+                        Reload { Idle => Reloading }
+                        Submachine(Reload: Ejecting) { Reloading => Idle }
+                    }
+                },
+                "event `Reload` and submachine `Reload` both result in the `Events::Reload` variant, rename one of them",
+            ),
+            (
+                quote! {
+                    Lock {
+                        InitialStates { A }
+                        C { A => AByB }
+                        BByC { A => A }
+                        C { AByB => A }
+                    }
+                },
+                "state `AByB` entered by event `C` and state `A` entered by event `BByC` both result in the `Variant::AByBByC` variant, rename one of them",
+            ),
+        ];
+
+        for (tokens, expected) in cases {
+            let err = syn::parse2::<Machine>(tokens).unwrap_err();
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_machine_parse_names() {
+        let machine: Machine = syn::parse2(quote! {
+           Lock {
+               InitialStates { Locked }
+
+               TurnKey { Locked => Machine  Machine => Locked }
+               Names { Machine as Lock, Variant as LockState }
+           }
+        })
+        .unwrap();
+
+        assert_eq!(machine.names.machine, "Lock");
+        assert_eq!(machine.names.variant, "LockState");
+        assert_eq!(machine.names.events, "Events");
+    }
+
+    #[test]
+    fn test_machine_parse_visibility() {
+        let machine: Machine = syn::parse2(quote! {
+           pub(crate) Lock {
+               InitialStates { Locked }
+
+               TurnKey { Locked => Unlocked  Unlocked => Locked }
+               pub(self) Break { Locked, Unlocked => Broken }
+               Break { Broken => Broken }
+               pub(self) Break { Unlocked => Broken }
+           }
+        })
+        .unwrap();
+
+        let crate_only = Visibility::Restricted(vec![Ident::new("crate", Span::call_site())]);
+        let private = Visibility::Restricted(vec![Ident::new("self", Span::call_site())]);
+
+        assert_eq!(machine.visibility, crate_only);
+        assert_eq!(
+            machine.event_visibilities,
+            vec![(parse_quote! { Break }, private)]
+        );
+    }
+
+    #[test]
+    fn test_machine_parse_visibility_errors() {
+        let cases = vec![
+            (
+                quote! {
+                    Lock {
+                        pub InitialStates { Locked }
+                        TurnKey { Locked => Unlocked  Unlocked => Locked }
+                    }
+                },
+                "`InitialStates` cannot have a visibility, only machines and events can",
+            ),
+            (
+                quote! {
+                    Lock {
+                        InitialStates { Locked }
+                        pub(crate) TurnKey { Locked => Unlocked }
+                        pub TurnKey { Unlocked => Locked }
+                    }
+                },
+                "event `TurnKey` is declared with a different visibility before",
+            ),
+            (
+                quote! {
+                    Lock {
+                        use crate::TurnKey;
+
+                        InitialStates { Locked }
+                        pub(crate) TurnKey { Locked => Unlocked  Unlocked => Locked }
+                    }
+                },
+                "imported event `TurnKey` cannot have a visibility, as it is declared elsewhere",
+            ),
+        ];
+
+        for (tokens, expected) in cases {
+            let err = syn::parse2::<Machine>(tokens).unwrap_err();
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_machines_parse_submachine_names() {
+        let machines: Machines = syn::parse2(quote! {
+            Gun {
+                InitialStates { Idle }
+
+                Trigger { Idle => Reloading }
+                Submachine(Reload: Ejecting) { Reloading => Idle }
+            }
+
+            Reload {
+                InitialStates { Ejecting }
+
+                Insert { Ejecting => Loaded }
+                FinalStates { Loaded }
+                Names { Machine as ReloadMachine }
+            }
+        })
+        .unwrap();
+
+        let submachine = &machines.0[0].submachines.0[0];
+        assert_eq!(submachine.names, machines.0[1].names);
+        assert_eq!(submachine.names.machine, "ReloadMachine");
+    }
+
+    #[test]
+    fn test_machine_parse_generics() {
+        let machine: Machine = syn::parse2(quote! {
+            Slot<T: Item> {
+                use crate::Item;
+                use crate::Holding<T>;
+
+                InitialStates { Empty }
+
+                Put { Empty => Holding }
+            }
+        })
+        .unwrap();
+
+        let generics: Generics = syn::parse2(quote! { <T: Item> }).unwrap();
+        assert_eq!(machine.generics, generics);
+        assert_eq!(
+            format!("{}", machine.imports.ty(&parse_quote! { Holding })),
+            format!("{}", quote! { Holding<T> })
+        );
+
+        let err = syn::parse2::<Machines>(quote! {
+            Gun {
+                InitialStates { Idle }
+
+                Trigger { Idle => Reloading }
+                Submachine(Reload: Ejecting) { Reloading => Idle }
+            }
+
+            Reload<T: Ammo> {
+                InitialStates { Ejecting }
+                FinalStates { Loaded }
+
+                Insert { Ejecting => Loaded }
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "generic machine `Reload` cannot be embedded in another machine"
+        );
+    }
+
+    #[test]
+    fn test_machine_parse_context() {
+        let machine: Machine = syn::parse2(quote! {
+            Lock {
+                InitialStates { Locked }
+                Context { crate::Stats }
+
+                TurnKey { Locked => Unlocked  Unlocked => Locked }
+            }
+        })
+        .unwrap();
+
+        let context: Context = syn::parse2(quote! { Context { crate::Stats } }).unwrap();
+        assert_eq!(machine.context, Some(context));
+
+        let err = syn::parse2::<Machine>(quote! {
+            Lock {
+                InitialStates { Locked }
+                Context { crate::Stats }
+                Context { crate::Config }
+
+                TurnKey { Locked => Unlocked  Unlocked => Locked }
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "`Context { ... }` is declared more than once"
+        );
+
+        let err = syn::parse2::<Machines>(quote! {
+            Gun {
+                InitialStates { Idle }
+
+                Trigger { Idle => Reloading }
+                Submachine(Reload: Ejecting) { Reloading => Idle }
+            }
+
+            Reload {
+                InitialStates { Ejecting }
+                FinalStates { Loaded }
+                Context { crate::Magazine }
+
+                Insert { Ejecting => Loaded }
+            }
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "machine `Reload` has a context, and cannot be embedded in another machine"
+        );
+    }
+
+    #[test]
+    fn test_machines_parse_crate_path() {
+        let machines: Machines = syn::parse2(quote! {
+            crate = ::engine::sm;
+
+            TurnStile {
+                InitialStates { Locked }
+
+                Coin { Locked => Unlocked }
+                Push { Unlocked => Locked }
+            }
+
+            Lock {
+                InitialStates { Locked }
+
+                TurnKey { Locked => Unlocked  Unlocked => Locked }
+            }
+        })
+        .unwrap();
+
+        let krate = CratePath {
+            global: true,
+            path: vec![parse_quote! { engine }, parse_quote! { sm }],
+        };
+
+        assert_eq!(machines.0.len(), 2);
+        assert!(machines.0.iter().all(|m| m.krate == krate));
+
+        let result: Result<Machines> = syn::parse2(quote! {
+            crate = ::engine::sm
+            Lock {}
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_machine_parse_misplaced_initial_states() {
+        let result: Result<Machine> = syn::parse2(quote! {
+           Lock {
+               TurnKey { Locked => Unlocked  Unlocked => Locked }
+               InitialStates { Locked }
+           }
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_machine_parse_misnamed_initial_states() {
+        let mut errors = Errors::default();
+        let parser = |input: ParseStream<'_>| Machine::parse_with(input, &mut errors);
+        let result = parser.parse2(quote! {
+           Lock {
+               Initial { Locked, Unlocked }
+
+               TurnKey { Locked => Unlocked  Unlocked => Locked }
+           }
         });
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::sm::{initial_state::InitialState, transition::Transition};
-    use proc_macro2::TokenStream;
-    use syn::{self, parse_quote};
+        assert!(result.is_ok());
+        assert_eq!(errors.len(), 1);
+    }
 
     #[test]
-    fn test_machine_parse() {
-        let left: Machine = syn::parse2(quote! {
-           TurnStile {
-               InitialStates { Locked, Unlocked }
+    fn test_machines_parse_with_errors() {
+        let mut errors = Errors::default();
+        let parser = |input: ParseStream<'_>| Machines::parse_with(input, &mut errors);
+        let machines = parser
+            .parse2(quote! {
+                Lock {
+                    InitialStates { Locked }
+
+                    TurnKey { Locked, => Unlocked }
+                    Break { Locked Broken }
+                    Repair { Broken => }
+                }
 
-               Coin { Locked => Unlocked }
-               Push { Unlocked => Locked }
-           }
-        })
-        .unwrap();
+                TurnStile {
+                    Push { Locked => Unlocked }
+                }
 
-        let right = Machine {
-            name: parse_quote! { TurnStile },
-            initial_states: InitialStates(vec![
-                InitialState {
-                    name: parse_quote! { Locked },
-                },
-                InitialState {
-                    name: parse_quote! { Unlocked },
-                },
-            ]),
-            transitions: Transitions(vec![
-                Transition {
-                    event: Event {
-                        name: parse_quote! { Coin },
-                    },
-                    from: State {
-                        name: parse_quote! { Locked },
-                    },
-                    to: State {
-                        name: parse_quote! { Unlocked },
-                    },
-                },
-                Transition {
-                    event: Event {
-                        name: parse_quote! { Push },
-                    },
-                    from: State {
-                        name: parse_quote! { Unlocked },
-                    },
-                    to: State {
-                        name: parse_quote! { Locked },
-                    },
-                },
-            ]),
-        };
+                Door {
+                    InitialStates { Closed }
 
-        assert_eq!(left, right);
+                    Open { Closed => Opened }
+                    Close { Opened => Closed }
+                }
+            })
+            .unwrap();
+
+        // The stray comma, missing `=>`, missing target state and missing
+        // initial states are all reported, without skipping any machine.
+        assert_eq!(errors.len(), 4);
+        assert_eq!(machines.0.len(), 3);
+        assert_eq!(machines.0[2].name, "Door");
     }
 
     #[test]
     fn test_machine_to_tokens() {
         let machine = Machine {
+            attributes: Attributes::default(),
+            visibility: Visibility::Inherited,
             name: parse_quote! { TurnStile },
+            generics: Generics::default(),
+            imports: Imports(vec![]),
             initial_states: InitialStates(vec![
                 InitialState {
                     name: parse_quote! { Unlocked },
@@ -310,6 +3002,9 @@ mod tests {
                     name: parse_quote! { Locked },
                 },
             ]),
+            final_states: FinalStates(vec![State {
+                name: parse_quote! { Locked },
+            }]),
             transitions: Transitions(vec![Transition {
                 event: Event {
                     name: parse_quote! { Push },
@@ -320,18 +3015,28 @@ mod tests {
                 to: State {
                     name: parse_quote! { Locked },
                 },
+                choice: false,
+                error: None,
             }]),
+            deferred: Deferred(vec![]),
+            timeouts: Timeouts(vec![]),
+            tick_timeouts: TickTimeouts(vec![]),
+            submachines: Submachines(vec![]),
+            groups: Groups(vec![]),
+            names: TypeNames::default(),
+            context: None,
+            krate: CratePath::default(),
+            event_visibilities: vec![],
+            handler: false,
         };
 
         let left = quote! {
             #[allow(non_snake_case)]
             mod TurnStile {
-                use sm::{AsEnum, Event, InitialState, Initializer, Machine as M, NoneEvent, State, Transition};
-
                 #[derive(Debug, Eq, PartialEq, Clone)]
-                pub struct Machine<S: State, E: Event>(S, Option<E>);
+                pub struct Machine<S: ::sm::State, E: ::sm::Event>(S, ::core::option::Option<E>);
 
-                impl<S: State, E: Event> M for Machine<S, E> {
+                impl<S: ::sm::State, E: ::sm::Event> ::sm::Machine for Machine<S, E> {
                     type State = S;
                     type Event = E;
 
@@ -339,30 +3044,53 @@ mod tests {
                         self.0.clone()
                     }
 
-                    fn trigger(&self) -> Option<Self::Event> {
+                    fn trigger(&self) -> ::core::option::Option<Self::Event> {
                         self.1.clone()
                     }
                 }
 
-                impl<S: InitialState> Initializer<S> for Machine<S, NoneEvent> {
-                    type Machine = Machine<S, NoneEvent>;
+                impl<S: ::sm::InitialState> ::sm::Initializer<S> for Machine<S, ::sm::NoneEvent> {
+                    type Machine = Machine<S, ::sm::NoneEvent>;
 
                     fn new(state: S) -> Self::Machine {
-                        Machine(state, Option::None)
+                        Machine(state, ::core::option::Option::None)
+                    }
+                }
+
+                impl<S: ::sm::State + diagnostics::Initial> Machine<S, ::sm::NoneEvent> {
+                    pub fn new(state: S) -> Self {
+                        Machine(state, ::core::option::Option::None)
+                    }
+                }
+
+                impl<S: ::sm::State, E: ::sm::Event> Machine<S, E> {
+                    pub fn state(&self) -> S {
+                        ::sm::Machine::state(self)
+                    }
+
+                    pub fn trigger(&self) -> ::core::option::Option<E> {
+                        ::sm::Machine::trigger(self)
+                    }
+
+                    pub fn as_enum(self) -> <Self as ::sm::AsEnum>::Enum
+                    where
+                        Self: ::sm::AsEnum,
+                    {
+                        ::sm::AsEnum::as_enum(self)
                     }
                 }
 
                 #[derive(Clone, Copy, Debug, Eq)]
                 pub struct Unlocked;
-                impl State for Unlocked {}
+                impl ::sm::State for Unlocked {}
 
-                impl PartialEq<Unlocked> for Unlocked {
+                impl ::core::cmp::PartialEq<Unlocked> for Unlocked {
                     fn eq(&self, _: & Unlocked) -> bool {
                         true
                     }
                 }
 
-                impl PartialEq<Locked> for Unlocked {
+                impl ::core::cmp::PartialEq<Locked> for Unlocked {
                     fn eq(&self, _: & Locked) -> bool {
                         false
                     }
@@ -370,41 +3098,109 @@ mod tests {
 
                 #[derive(Clone, Copy, Debug, Eq)]
                 pub struct Locked;
-                impl State for Locked {}
+                impl ::sm::State for Locked {}
 
-                impl PartialEq<Unlocked> for Locked {
+                impl ::core::cmp::PartialEq<Unlocked> for Locked {
                     fn eq(&self, _: &Unlocked) -> bool {
                         false
                     }
                 }
 
-                impl PartialEq<Locked> for Locked {
+                impl ::core::cmp::PartialEq<Locked> for Locked {
                     fn eq(&self, _: &Locked) -> bool {
                         true
                     }
                 }
 
-                impl InitialState for Unlocked {}
-                impl InitialState for Locked {}
+                impl ::sm::InitialState for Unlocked {}
+                impl ::sm::InitialState for Locked {}
+
+                #[doc(hidden)]
+                pub mod diagnostics {
+                    #[diagnostic::on_unimplemented(
+                        message = "`{Self}` is not an initial state of `TurnStile`",
+                        label = "cannot initialise `TurnStile` in this state",
+                        note = "the initial states of `TurnStile` are: `Unlocked`, `Locked`"
+                    )]
+                    pub trait Initial {}
+
+                    pub mod transition {
+                        #[diagnostic::on_unimplemented(
+                            message = "`Unlocked` has no transition for event `{Self}`; valid events are: `Push`",
+                            label = "invalid transition for `TurnStile` in state `Unlocked`",
+                            note = "a machine can only be transitioned using the events declared for its current state"
+                        )]
+                        pub trait Unlocked<M> {
+                            type Machine;
+
+                            fn accept(self, machine: M) -> Self::Machine;
+                        }
+
+                        #[diagnostic::on_unimplemented(
+                            message = "`Locked` has no transition for event `{Self}`, or any other event",
+                            label = "invalid transition for `TurnStile` in state `Locked`",
+                            note = "a machine can only be transitioned using the events declared for its current state"
+                        )]
+                        pub trait Locked<M> {
+                            type Machine;
+
+                            fn accept(self, machine: M) -> Self::Machine;
+                        }
+                    }
+                }
+
+                impl diagnostics::Initial for Unlocked {}
+                impl diagnostics::Initial for Locked {}
+
+                impl<E: ::sm::Event> Machine<Unlocked, E> {
+                    pub fn transition<V: diagnostics::transition::Unlocked<Self>>(self, event: V) -> V::Machine {
+                        diagnostics::transition::Unlocked::accept(event, self)
+                    }
+                }
+
+                impl<E: ::sm::Event> diagnostics::transition::Unlocked<Machine<Unlocked, E>> for Push {
+                    type Machine = <Push as ::sm::Accept<Machine<Unlocked, E>>>::Machine;
+
+                    fn accept(self, machine: Machine<Unlocked, E>) -> Self::Machine {
+                        ::sm::Accept::accept(self, machine)
+                    }
+                }
+
+                impl<E: ::sm::Event> Machine<Locked, E> {
+                    pub fn transition<V: diagnostics::transition::Locked<Self>>(self, event: V) -> V::Machine {
+                        diagnostics::transition::Locked::accept(event, self)
+                    }
+                }
 
                 #[derive(Clone, Copy, Debug, Eq)]
                 pub struct Push;
-                impl Event for Push {}
+                impl ::sm::Event for Push {}
 
-                impl PartialEq<Push> for Push {
+                impl ::core::cmp::PartialEq<Push> for Push {
                     fn eq(&self, _: &Push) -> bool {
                         true
                     }
                 }
 
+                #[derive(Debug, Eq, PartialEq, Clone)]
+                pub enum Events {
+                    Push(Push)
+                }
+
+                impl ::core::convert::From<Push> for Events {
+                    fn from(event: Push) -> Self {
+                        Events::Push(event)
+                    }
+                }
+
                 #[derive(Debug, Clone)]
                 pub enum Variant {
-                    InitialUnlocked(Machine<Unlocked, NoneEvent>),
-                    InitialLocked(Machine<Locked, NoneEvent>),
+                    InitialUnlocked(Machine<Unlocked, ::sm::NoneEvent>),
+                    InitialLocked(Machine<Locked, ::sm::NoneEvent>),
                     LockedByPush(Machine<Locked, Push>)
                 }
 
-                impl AsEnum for Machine<Unlocked, NoneEvent> {
+                impl ::sm::AsEnum for Machine<Unlocked, ::sm::NoneEvent> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
@@ -412,7 +3208,7 @@ mod tests {
                     }
                 }
 
-                impl AsEnum for Machine<Locked, NoneEvent> {
+                impl ::sm::AsEnum for Machine<Locked, ::sm::NoneEvent> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
@@ -420,7 +3216,7 @@ mod tests {
                     }
                 }
 
-                impl AsEnum for Machine<Locked, Push> {
+                impl ::sm::AsEnum for Machine<Locked, Push> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
@@ -428,13 +3224,84 @@ mod tests {
                     }
                 }
 
-                impl<E: Event> Transition<Push> for Machine<Unlocked, E> {
+                pub trait IsState<S> {
+                    fn is_state(&self, state: &S) -> bool;
+                }
+
+                impl<S: ::core::cmp::PartialEq> IsState<S> for S {
+                    fn is_state(&self, state: &S) -> bool {
+                        self == state
+                    }
+                }
+
+                impl IsState<Locked> for Unlocked {
+                    fn is_state(&self, _: &Locked) -> bool {
+                        false
+                    }
+                }
+
+                impl IsState<Unlocked> for Locked {
+                    fn is_state(&self, _: &Unlocked) -> bool {
+                        false
+                    }
+                }
+
+                impl Variant {
+                    pub fn is<S: ::sm::State>(&self, state: &S) -> bool
+                    where
+                        Unlocked: IsState<S>,
+                        Locked: IsState<S>
+                    {
+                        match self {
+                            Variant::InitialUnlocked(m, ..) => m.0.is_state(state),
+                            Variant::InitialLocked(m, ..) => m.0.is_state(state),
+                            Variant::LockedByPush(m, ..) => m.0.is_state(state),
+                        }
+                    }
+
+                    #[allow(unreachable_patterns)]
+                    pub fn is_final(&self) -> bool {
+                        match self {
+                            Variant::InitialLocked(..) => true,
+                            Variant::LockedByPush(..) => true,
+                            _ => false,
+                        }
+                    }
+                }
+
+                impl ::sm::Dispatch for Variant {
+                    type Event = Events;
+
+                    #[allow(unreachable_patterns)]
+                    fn dispatch(self, event: Events) -> ::core::result::Result<Self, (Self, Events)> {
+                        match (self, event) {
+                            (Variant::InitialUnlocked(m), Events::Push(e)) => ::core::result::Result::Ok(m.transition(e).as_enum()),
+                            (m, e) => ::core::result::Result::Err((m, e)),
+                        }
+                    }
+                }
+
+                impl<E: ::sm::Event> ::sm::Transition<Push> for Machine<Unlocked, E> {
                     type Machine = Machine<Locked, Push>;
 
                     fn transition(self, event: Push) -> Self::Machine {
-                        Machine(Locked, Some(event))
+                        Machine(Locked, ::core::option::Option::Some(event))
+                    }
+                }
+
+                impl<E: ::sm::Event> Machine<Unlocked, E> {
+                    pub fn push(self) -> <Self as ::sm::Transition<Push>>::Machine {
+                        self.transition(Push)
                     }
                 }
+
+                pub trait CanPush: ::sm::Transition<Push, Machine = <Self as CanPush>::Next> {
+                    type Next;
+                }
+
+                impl<M: ::sm::Transition<Push>> CanPush for M {
+                    type Next = M::Machine;
+                }
             }
         };
 
@@ -467,7 +3334,11 @@ mod tests {
 
         let right = Machines(vec![
             Machine {
+                attributes: Attributes::default(),
+                visibility: Visibility::Inherited,
                 name: parse_quote! { TurnStile },
+                generics: Generics::default(),
+                imports: Imports(vec![]),
                 initial_states: InitialStates(vec![
                     InitialState {
                         name: parse_quote! { Locked },
@@ -476,6 +3347,7 @@ mod tests {
                         name: parse_quote! { Unlocked },
                     },
                 ]),
+                final_states: FinalStates(vec![]),
                 transitions: Transitions(vec![
                     Transition {
                         event: Event {
@@ -487,6 +3359,8 @@ mod tests {
                         to: State {
                             name: parse_quote! { Unlocked },
                         },
+                        choice: false,
+                        error: None,
                     },
                     Transition {
                         event: Event {
@@ -498,11 +3372,27 @@ mod tests {
                         to: State {
                             name: parse_quote! { Locked },
                         },
+                        choice: false,
+                        error: None,
                     },
                 ]),
+                deferred: Deferred(vec![]),
+                timeouts: Timeouts(vec![]),
+                tick_timeouts: TickTimeouts(vec![]),
+                submachines: Submachines(vec![]),
+                groups: Groups(vec![]),
+                names: TypeNames::default(),
+                context: None,
+                krate: CratePath::default(),
+                event_visibilities: vec![],
+                handler: false,
             },
             Machine {
+                attributes: Attributes::default(),
+                visibility: Visibility::Inherited,
                 name: parse_quote! { Lock },
+                generics: Generics::default(),
+                imports: Imports(vec![]),
                 initial_states: InitialStates(vec![
                     InitialState {
                         name: parse_quote! { Locked },
@@ -511,6 +3401,7 @@ mod tests {
                         name: parse_quote! { Unlocked },
                     },
                 ]),
+                final_states: FinalStates(vec![]),
                 transitions: Transitions(vec![
                     Transition {
                         event: Event {
@@ -522,6 +3413,8 @@ mod tests {
                         to: State {
                             name: parse_quote! { Unlocked },
                         },
+                        choice: false,
+                        error: None,
                     },
                     Transition {
                         event: Event {
@@ -533,8 +3426,20 @@ mod tests {
                         to: State {
                             name: parse_quote! { Locked },
                         },
+                        choice: false,
+                        error: None,
                     },
                 ]),
+                deferred: Deferred(vec![]),
+                timeouts: Timeouts(vec![]),
+                tick_timeouts: TickTimeouts(vec![]),
+                submachines: Submachines(vec![]),
+                groups: Groups(vec![]),
+                names: TypeNames::default(),
+                context: None,
+                krate: CratePath::default(),
+                event_visibilities: vec![],
+                handler: false,
             },
         ]);
 
@@ -545,7 +3450,11 @@ mod tests {
     fn test_machines_to_tokens() {
         let machines = Machines(vec![
             Machine {
+                attributes: Attributes::default(),
+                visibility: Visibility::Inherited,
                 name: parse_quote! { TurnStile },
+                generics: Generics::default(),
+                imports: Imports(vec![]),
                 initial_states: InitialStates(vec![
                     InitialState {
                         name: parse_quote! { Locked },
@@ -554,6 +3463,7 @@ mod tests {
                         name: parse_quote! { Unlocked },
                     },
                 ]),
+                final_states: FinalStates(vec![]),
                 transitions: Transitions(vec![
                     Transition {
                         event: Event {
@@ -565,6 +3475,8 @@ mod tests {
                         to: State {
                             name: parse_quote! { Unlocked },
                         },
+                        choice: false,
+                        error: None,
                     },
                     Transition {
                         event: Event {
@@ -576,11 +3488,27 @@ mod tests {
                         to: State {
                             name: parse_quote! { Locked },
                         },
+                        choice: false,
+                        error: None,
                     },
                 ]),
+                deferred: Deferred(vec![]),
+                timeouts: Timeouts(vec![]),
+                tick_timeouts: TickTimeouts(vec![]),
+                submachines: Submachines(vec![]),
+                groups: Groups(vec![]),
+                names: TypeNames::default(),
+                context: None,
+                krate: CratePath::default(),
+                event_visibilities: vec![],
+                handler: false,
             },
             Machine {
+                attributes: Attributes::default(),
+                visibility: Visibility::Inherited,
                 name: parse_quote! { Lock },
+                generics: Generics::default(),
+                imports: Imports(vec![]),
                 initial_states: InitialStates(vec![
                     InitialState {
                         name: parse_quote! { Locked },
@@ -589,6 +3517,7 @@ mod tests {
                         name: parse_quote! { Unlocked },
                     },
                 ]),
+                final_states: FinalStates(vec![]),
                 transitions: Transitions(vec![
                     Transition {
                         event: Event {
@@ -600,6 +3529,8 @@ mod tests {
                         to: State {
                             name: parse_quote! { Unlocked },
                         },
+                        choice: false,
+                        error: None,
                     },
                     Transition {
                         event: Event {
@@ -611,22 +3542,30 @@ mod tests {
                         to: State {
                             name: parse_quote! { Locked },
                         },
+                        choice: false,
+                        error: None,
                     },
                 ]),
+                deferred: Deferred(vec![]),
+                timeouts: Timeouts(vec![]),
+                tick_timeouts: TickTimeouts(vec![]),
+                submachines: Submachines(vec![]),
+                groups: Groups(vec![]),
+                names: TypeNames::default(),
+                context: None,
+                krate: CratePath::default(),
+                event_visibilities: vec![],
+                handler: false,
             },
         ]);
 
         let left = quote! {
-            use sm::{AsEnum, Initializer, Machine as M, Transition};
-
             #[allow(non_snake_case)]
             mod TurnStile {
-                use sm::{AsEnum, Event, InitialState, Initializer, Machine as M, NoneEvent, State, Transition};
-
                 #[derive(Debug, Eq, PartialEq, Clone)]
-                pub struct Machine<S: State, E: Event>(S, Option<E>);
+                pub struct Machine<S: ::sm::State, E: ::sm::Event>(S, ::core::option::Option<E>);
 
-                impl<S: State, E: Event> M for Machine<S, E> {
+                impl<S: ::sm::State, E: ::sm::Event> ::sm::Machine for Machine<S, E> {
                     type State = S;
                     type Event = E;
 
@@ -634,30 +3573,53 @@ mod tests {
                         self.0.clone()
                     }
 
-                    fn trigger(&self) -> Option<Self::Event> {
+                    fn trigger(&self) -> ::core::option::Option<Self::Event> {
                         self.1.clone()
                     }
                 }
 
-                impl<S: InitialState> Initializer<S> for Machine<S, NoneEvent> {
-                    type Machine = Machine<S, NoneEvent>;
+                impl<S: ::sm::InitialState> ::sm::Initializer<S> for Machine<S, ::sm::NoneEvent> {
+                    type Machine = Machine<S, ::sm::NoneEvent>;
 
                     fn new(state: S) -> Self::Machine {
-                        Machine(state, Option::None)
+                        Machine(state, ::core::option::Option::None)
+                    }
+                }
+
+                impl<S: ::sm::State + diagnostics::Initial> Machine<S, ::sm::NoneEvent> {
+                    pub fn new(state: S) -> Self {
+                        Machine(state, ::core::option::Option::None)
+                    }
+                }
+
+                impl<S: ::sm::State, E: ::sm::Event> Machine<S, E> {
+                    pub fn state(&self) -> S {
+                        ::sm::Machine::state(self)
+                    }
+
+                    pub fn trigger(&self) -> ::core::option::Option<E> {
+                        ::sm::Machine::trigger(self)
+                    }
+
+                    pub fn as_enum(self) -> <Self as ::sm::AsEnum>::Enum
+                    where
+                        Self: ::sm::AsEnum,
+                    {
+                        ::sm::AsEnum::as_enum(self)
                     }
                 }
 
                 #[derive(Clone, Copy, Debug, Eq)]
                 pub struct Locked;
-                impl State for Locked {}
+                impl ::sm::State for Locked {}
 
-                impl PartialEq<Locked> for Locked {
+                impl ::core::cmp::PartialEq<Locked> for Locked {
                     fn eq(&self, _: &Locked) -> bool {
                         true
                     }
                 }
 
-                impl PartialEq<Unlocked> for Locked {
+                impl ::core::cmp::PartialEq<Unlocked> for Locked {
                     fn eq(&self, _: &Unlocked) -> bool {
                         false
                     }
@@ -665,34 +3627,99 @@ mod tests {
 
                 #[derive(Clone, Copy, Debug, Eq)]
                 pub struct Unlocked;
-                impl State for Unlocked {}
+                impl ::sm::State for Unlocked {}
 
-                impl PartialEq<Locked> for Unlocked {
+                impl ::core::cmp::PartialEq<Locked> for Unlocked {
                     fn eq(&self, _: & Locked) -> bool {
                         false
                     }
                 }
 
-                impl PartialEq<Unlocked> for Unlocked {
+                impl ::core::cmp::PartialEq<Unlocked> for Unlocked {
                     fn eq(&self, _: & Unlocked) -> bool {
                         true
                     }
                 }
 
-                impl InitialState for Locked {}
-                impl InitialState for Unlocked {}
+                impl ::sm::InitialState for Locked {}
+                impl ::sm::InitialState for Unlocked {}
+
+                #[doc(hidden)]
+                pub mod diagnostics {
+                    #[diagnostic::on_unimplemented(
+                        message = "`{Self}` is not an initial state of `TurnStile`",
+                        label = "cannot initialise `TurnStile` in this state",
+                        note = "the initial states of `TurnStile` are: `Locked`, `Unlocked`"
+                    )]
+                    pub trait Initial {}
+
+                    pub mod transition {
+                        #[diagnostic::on_unimplemented(
+                            message = "`Locked` has no transition for event `{Self}`; valid events are: `Coin`",
+                            label = "invalid transition for `TurnStile` in state `Locked`",
+                            note = "a machine can only be transitioned using the events declared for its current state"
+                        )]
+                        pub trait Locked<M> {
+                            type Machine;
+
+                            fn accept(self, machine: M) -> Self::Machine;
+                        }
+
+                        #[diagnostic::on_unimplemented(
+                            message = "`Unlocked` has no transition for event `{Self}`; valid events are: `Push`",
+                            label = "invalid transition for `TurnStile` in state `Unlocked`",
+                            note = "a machine can only be transitioned using the events declared for its current state"
+                        )]
+                        pub trait Unlocked<M> {
+                            type Machine;
+
+                            fn accept(self, machine: M) -> Self::Machine;
+                        }
+                    }
+                }
+
+                impl diagnostics::Initial for Locked {}
+                impl diagnostics::Initial for Unlocked {}
+
+                impl<E: ::sm::Event> Machine<Locked, E> {
+                    pub fn transition<V: diagnostics::transition::Locked<Self>>(self, event: V) -> V::Machine {
+                        diagnostics::transition::Locked::accept(event, self)
+                    }
+                }
+
+                impl<E: ::sm::Event> diagnostics::transition::Locked<Machine<Locked, E>> for Coin {
+                    type Machine = <Coin as ::sm::Accept<Machine<Locked, E>>>::Machine;
+
+                    fn accept(self, machine: Machine<Locked, E>) -> Self::Machine {
+                        ::sm::Accept::accept(self, machine)
+                    }
+                }
+
+                impl<E: ::sm::Event> Machine<Unlocked, E> {
+                    pub fn transition<V: diagnostics::transition::Unlocked<Self>>(self, event: V) -> V::Machine {
+                        diagnostics::transition::Unlocked::accept(event, self)
+                    }
+                }
+
+                impl<E: ::sm::Event> diagnostics::transition::Unlocked<Machine<Unlocked, E>> for Push {
+                    type Machine = <Push as ::sm::Accept<Machine<Unlocked, E>>>::Machine;
+
+                    fn accept(self, machine: Machine<Unlocked, E>) -> Self::Machine {
+                        ::sm::Accept::accept(self, machine)
+                    }
+                }
 
                 #[derive(Clone, Copy, Debug, Eq)]
                 pub struct Coin;
-                impl Event for Coin {}
+                impl ::sm::Event for Coin {}
 
-                impl PartialEq<Coin> for Coin {
+                impl ::core::cmp::PartialEq<Coin> for Coin {
                     fn eq(&self, _: &Coin) -> bool {
                         true
                     }
                 }
 
-                impl PartialEq<Push> for Coin {
+                impl ::core::cmp::PartialEq<Push> for Coin {
                     fn eq(&self, _: &Push) -> bool {
                         false
                     }
@@ -700,29 +3727,47 @@ mod tests {
 
                 #[derive(Clone, Copy, Debug, Eq)]
                 pub struct Push;
-                impl Event for Push {}
+                impl ::sm::Event for Push {}
 
-                impl PartialEq<Coin> for Push {
+                impl ::core::cmp::PartialEq<Coin> for Push {
                     fn eq(&self, _: &Coin) -> bool {
                         false
                     }
                 }
 
-                impl PartialEq<Push> for Push {
+                impl ::core::cmp::PartialEq<Push> for Push {
                     fn eq(&self, _: &Push) -> bool {
                         true
                     }
                 }
 
+                #[derive(Debug, Eq, PartialEq, Clone)]
+                pub enum Events {
+                    Coin(Coin),
+                    Push(Push)
+                }
+
+                impl ::core::convert::From<Coin> for Events {
+                    fn from(event: Coin) -> Self {
+                        Events::Coin(event)
+                    }
+                }
+
+                impl ::core::convert::From<Push> for Events {
+                    fn from(event: Push) -> Self {
+                        Events::Push(event)
+                    }
+                }
+
                 #[derive(Debug, Clone)]
                 pub enum Variant {
-                    InitialLocked(Machine<Locked, NoneEvent>),
-                    InitialUnlocked(Machine<Unlocked, NoneEvent>),
+                    InitialLocked(Machine<Locked, ::sm::NoneEvent>),
+                    InitialUnlocked(Machine<Unlocked, ::sm::NoneEvent>),
                     UnlockedByCoin(Machine<Unlocked, Coin>),
                     LockedByPush(Machine<Locked, Push>)
                 }
 
-                impl AsEnum for Machine<Locked, NoneEvent> {
+                impl ::sm::AsEnum for Machine<Locked, ::sm::NoneEvent> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
@@ -730,7 +3775,7 @@ mod tests {
                     }
                 }
 
-                impl AsEnum for Machine<Unlocked, NoneEvent> {
+                impl ::sm::AsEnum for Machine<Unlocked, ::sm::NoneEvent> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
@@ -738,7 +3783,7 @@ mod tests {
                     }
                 }
 
-                impl AsEnum for Machine<Unlocked, Coin> {
+                impl ::sm::AsEnum for Machine<Unlocked, Coin> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
@@ -746,7 +3791,7 @@ mod tests {
                     }
                 }
 
-                impl AsEnum for Machine<Locked, Push> {
+                impl ::sm::AsEnum for Machine<Locked, Push> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
@@ -754,31 +3799,115 @@ mod tests {
                     }
                 }
 
-                impl<E: Event> Transition<Coin> for Machine<Locked, E> {
+                pub trait IsState<S> {
+                    fn is_state(&self, state: &S) -> bool;
+                }
+
+                impl<S: ::core::cmp::PartialEq> IsState<S> for S {
+                    fn is_state(&self, state: &S) -> bool {
+                        self == state
+                    }
+                }
+
+                impl IsState<Unlocked> for Locked {
+                    fn is_state(&self, _: &Unlocked) -> bool {
+                        false
+                    }
+                }
+
+                impl IsState<Locked> for Unlocked {
+                    fn is_state(&self, _: &Locked) -> bool {
+                        false
+                    }
+                }
+
+                impl Variant {
+                    pub fn is<S: ::sm::State>(&self, state: &S) -> bool
+                    where
+                        Locked: IsState<S>,
+                        Unlocked: IsState<S>
+                    {
+                        match self {
+                            Variant::InitialLocked(m, ..) => m.0.is_state(state),
+                            Variant::InitialUnlocked(m, ..) => m.0.is_state(state),
+                            Variant::UnlockedByCoin(m, ..) => m.0.is_state(state),
+                            Variant::LockedByPush(m, ..) => m.0.is_state(state),
+                        }
+                    }
+
+                    #[allow(unreachable_patterns)]
+                    pub fn is_final(&self) -> bool {
+                        match self {
+                            _ => false,
+                        }
+                    }
+                }
+
+                impl ::sm::Dispatch for Variant {
+                    type Event = Events;
+
+                    #[allow(unreachable_patterns)]
+                    fn dispatch(self, event: Events) -> ::core::result::Result<Self, (Self, Events)> {
+                        match (self, event) {
+                            (Variant::InitialLocked(m), Events::Coin(e)) => ::core::result::Result::Ok(m.transition(e).as_enum()),
+                            (Variant::InitialUnlocked(m), Events::Push(e)) => ::core::result::Result::Ok(m.transition(e).as_enum()),
+                            (Variant::UnlockedByCoin(m), Events::Push(e)) => ::core::result::Result::Ok(m.transition(e).as_enum()),
+                            (Variant::LockedByPush(m), Events::Coin(e)) => ::core::result::Result::Ok(m.transition(e).as_enum()),
+                            (m, e) => ::core::result::Result::Err((m, e)),
+                        }
+                    }
+                }
+
+                impl<E: ::sm::Event> ::sm::Transition<Coin> for Machine<Locked, E> {
                     type Machine = Machine<Unlocked, Coin>;
 
                     fn transition(self, event: Coin) -> Self::Machine {
-                        Machine(Unlocked, Some(event))
+                        Machine(Unlocked, ::core::option::Option::Some(event))
                     }
                 }
 
-                impl<E: Event> Transition<Push> for Machine<Unlocked, E> {
+                impl<E: ::sm::Event> ::sm::Transition<Push> for Machine<Unlocked, E> {
                     type Machine = Machine<Locked, Push>;
 
                     fn transition(self, event: Push) -> Self::Machine {
-                        Machine(Locked, Some(event))
+                        Machine(Locked, ::core::option::Option::Some(event))
+                    }
+                }
+
+                impl<E: ::sm::Event> Machine<Locked, E> {
+                    pub fn coin(self) -> <Self as ::sm::Transition<Coin>>::Machine {
+                        self.transition(Coin)
                     }
                 }
+
+                impl<E: ::sm::Event> Machine<Unlocked, E> {
+                    pub fn push(self) -> <Self as ::sm::Transition<Push>>::Machine {
+                        self.transition(Push)
+                    }
+                }
+
+                pub trait CanCoin: ::sm::Transition<Coin, Machine = <Self as CanCoin>::Next> {
+                    type Next;
+                }
+
+                impl<M: ::sm::Transition<Coin>> CanCoin for M {
+                    type Next = M::Machine;
+                }
+                pub trait CanPush: ::sm::Transition<Push, Machine = <Self as CanPush>::Next> {
+                    type Next;
+                }
+
+                impl<M: ::sm::Transition<Push>> CanPush for M {
+                    type Next = M::Machine;
+                }
             }
 
             #[allow(non_snake_case)]
             mod Lock {
-                use sm::{AsEnum, Event, InitialState, Initializer, Machine as M, NoneEvent, State, Transition};
-
                 #[derive(Debug, Eq, PartialEq, Clone)]
-                pub struct Machine<S: State, E: Event>(S, Option<E>);
+                pub struct Machine<S: ::sm::State, E: ::sm::Event>(S, ::core::option::Option<E>);
 
-                impl<S: State, E: Event> M for Machine<S, E> {
+                impl<S: ::sm::State, E: ::sm::Event> ::sm::Machine for Machine<S, E> {
                     type State = S;
                     type Event = E;
 
@@ -786,30 +3915,53 @@ mod tests {
                         self.0.clone()
                     }
 
-                    fn trigger(&self) -> Option<Self::Event> {
+                    fn trigger(&self) -> ::core::option::Option<Self::Event> {
                         self.1.clone()
                     }
                 }
 
-                impl<S: InitialState> Initializer<S> for Machine<S, NoneEvent> {
-                    type Machine = Machine<S, NoneEvent>;
+                impl<S: ::sm::InitialState> ::sm::Initializer<S> for Machine<S, ::sm::NoneEvent> {
+                    type Machine = Machine<S, ::sm::NoneEvent>;
 
                     fn new(state: S) -> Self::Machine {
-                        Machine(state, Option::None)
+                        Machine(state, ::core::option::Option::None)
+                    }
+                }
+
+                impl<S: ::sm::State + diagnostics::Initial> Machine<S, ::sm::NoneEvent> {
+                    pub fn new(state: S) -> Self {
+                        Machine(state, ::core::option::Option::None)
+                    }
+                }
+
+                impl<S: ::sm::State, E: ::sm::Event> Machine<S, E> {
+                    pub fn state(&self) -> S {
+                        ::sm::Machine::state(self)
+                    }
+
+                    pub fn trigger(&self) -> ::core::option::Option<E> {
+                        ::sm::Machine::trigger(self)
+                    }
+
+                    pub fn as_enum(self) -> <Self as ::sm::AsEnum>::Enum
+                    where
+                        Self: ::sm::AsEnum,
+                    {
+                        ::sm::AsEnum::as_enum(self)
                     }
                 }
 
                 #[derive(Clone, Copy, Debug, Eq)]
                 pub struct Locked;
-                impl State for Locked {}
+                impl ::sm::State for Locked {}
 
-                impl PartialEq<Locked> for Locked {
+                impl ::core::cmp::PartialEq<Locked> for Locked {
                     fn eq(&self, _: &Locked) -> bool {
                         true
                     }
                 }
 
-                impl PartialEq<Unlocked> for Locked {
+                impl ::core::cmp::PartialEq<Unlocked> for Locked {
                     fn eq(&self, _: &Unlocked) -> bool {
                         false
                     }
@@ -817,42 +3969,118 @@ mod tests {
 
                 #[derive(Clone, Copy, Debug, Eq)]
                 pub struct Unlocked;
-                impl State for Unlocked {}
+                impl ::sm::State for Unlocked {}
 
-                impl PartialEq<Locked> for Unlocked {
+                impl ::core::cmp::PartialEq<Locked> for Unlocked {
                     fn eq(&self, _: & Locked) -> bool {
                         false
                     }
                 }
 
-                impl PartialEq<Unlocked> for Unlocked {
+                impl ::core::cmp::PartialEq<Unlocked> for Unlocked {
                     fn eq(&self, _: & Unlocked) -> bool {
                         true
                     }
                 }
 
-                impl InitialState for Locked {}
-                impl InitialState for Unlocked {}
+                impl ::sm::InitialState for Locked {}
+                impl ::sm::InitialState for Unlocked {}
+
+                #[doc(hidden)]
+                pub mod diagnostics {
+                    #[diagnostic::on_unimplemented(
+                        message = "`{Self}` is not an initial state of `Lock`",
+                        label = "cannot initialise `Lock` in this state",
+                        note = "the initial states of `Lock` are: `Locked`, `Unlocked`"
+                    )]
+                    pub trait Initial {}
+
+                    pub mod transition {
+                        #[diagnostic::on_unimplemented(
+                            message = "`Locked` has no transition for event `{Self}`; valid events are: `TurnKey`",
+                            label = "invalid transition for `Lock` in state `Locked`",
+                            note = "a machine can only be transitioned using the events declared for its current state"
+                        )]
+                        pub trait Locked<M> {
+                            type Machine;
+
+                            fn accept(self, machine: M) -> Self::Machine;
+                        }
+
+                        #[diagnostic::on_unimplemented(
+                            message = "`Unlocked` has no transition for event `{Self}`; valid events are: `TurnKey`",
+                            label = "invalid transition for `Lock` in state `Unlocked`",
+                            note = "a machine can only be transitioned using the events declared for its current state"
+                        )]
+                        pub trait Unlocked<M> {
+                            type Machine;
+
+                            fn accept(self, machine: M) -> Self::Machine;
+                        }
+                    }
+                }
+
+                impl diagnostics::Initial for Locked {}
+                impl diagnostics::Initial for Unlocked {}
+
+                impl<E: ::sm::Event> Machine<Locked, E> {
+                    pub fn transition<V: diagnostics::transition::Locked<Self>>(self, event: V) -> V::Machine {
+                        diagnostics::transition::Locked::accept(event, self)
+                    }
+                }
+
+                impl<E: ::sm::Event> diagnostics::transition::Locked<Machine<Locked, E>> for TurnKey {
+                    type Machine = <TurnKey as ::sm::Accept<Machine<Locked, E>>>::Machine;
+
+                    fn accept(self, machine: Machine<Locked, E>) -> Self::Machine {
+                        ::sm::Accept::accept(self, machine)
+                    }
+                }
+
+                impl<E: ::sm::Event> Machine<Unlocked, E> {
+                    pub fn transition<V: diagnostics::transition::Unlocked<Self>>(self, event: V) -> V::Machine {
+                        diagnostics::transition::Unlocked::accept(event, self)
+                    }
+                }
+
+                impl<E: ::sm::Event> diagnostics::transition::Unlocked<Machine<Unlocked, E>> for TurnKey {
+                    type Machine = <TurnKey as ::sm::Accept<Machine<Unlocked, E>>>::Machine;
+
+                    fn accept(self, machine: Machine<Unlocked, E>) -> Self::Machine {
+                        ::sm::Accept::accept(self, machine)
+                    }
+                }
 
                 #[derive(Clone, Copy, Debug, Eq)]
                 pub struct TurnKey;
-                impl Event for TurnKey {}
+                impl ::sm::Event for TurnKey {}
 
-                impl PartialEq<TurnKey> for TurnKey {
+                impl ::core::cmp::PartialEq<TurnKey> for TurnKey {
                     fn eq(&self, _: &TurnKey) -> bool {
                         true
                     }
                 }
 
+                #[derive(Debug, Eq, PartialEq, Clone)]
+                pub enum Events {
+                    TurnKey(TurnKey)
+                }
+
+                impl ::core::convert::From<TurnKey> for Events {
+                    fn from(event: TurnKey) -> Self {
+                        Events::TurnKey(event)
+                    }
+                }
+
                 #[derive(Debug, Clone)]
                 pub enum Variant {
-                    InitialLocked(Machine<Locked, NoneEvent>),
-                    InitialUnlocked(Machine<Unlocked, NoneEvent>),
+                    InitialLocked(Machine<Locked, ::sm::NoneEvent>),
+                    InitialUnlocked(Machine<Unlocked, ::sm::NoneEvent>),
                     UnlockedByTurnKey(Machine<Unlocked, TurnKey>),
                     LockedByTurnKey(Machine<Locked, TurnKey>)
                 }
 
-                impl AsEnum for Machine<Locked, NoneEvent> {
+                impl ::sm::AsEnum for Machine<Locked, ::sm::NoneEvent> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
@@ -860,7 +4088,7 @@ mod tests {
                     }
                 }
 
-                impl AsEnum for Machine<Unlocked, NoneEvent> {
+                impl ::sm::AsEnum for Machine<Unlocked, ::sm::NoneEvent> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
@@ -868,7 +4096,7 @@ mod tests {
                     }
                 }
 
-                impl AsEnum for Machine<Unlocked, TurnKey> {
+                impl ::sm::AsEnum for Machine<Unlocked, TurnKey> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
@@ -876,28 +4104,108 @@ mod tests {
                     }
                 }
 
-                impl AsEnum for Machine<Locked, TurnKey> {
+                impl ::sm::AsEnum for Machine<Locked, TurnKey> {
                     type Enum = Variant;
 
                     fn as_enum(self) -> Self::Enum {
                         Variant::LockedByTurnKey(self)
                     }
                 }
-                impl<E: Event> Transition<TurnKey> for Machine<Locked, E> {
+
+                pub trait IsState<S> {
+                    fn is_state(&self, state: &S) -> bool;
+                }
+
+                impl<S: ::core::cmp::PartialEq> IsState<S> for S {
+                    fn is_state(&self, state: &S) -> bool {
+                        self == state
+                    }
+                }
+
+                impl IsState<Unlocked> for Locked {
+                    fn is_state(&self, _: &Unlocked) -> bool {
+                        false
+                    }
+                }
+
+                impl IsState<Locked> for Unlocked {
+                    fn is_state(&self, _: &Locked) -> bool {
+                        false
+                    }
+                }
+
+                impl Variant {
+                    pub fn is<S: ::sm::State>(&self, state: &S) -> bool
+                    where
+                        Locked: IsState<S>,
+                        Unlocked: IsState<S>
+                    {
+                        match self {
+                            Variant::InitialLocked(m, ..) => m.0.is_state(state),
+                            Variant::InitialUnlocked(m, ..) => m.0.is_state(state),
+                            Variant::UnlockedByTurnKey(m, ..) => m.0.is_state(state),
+                            Variant::LockedByTurnKey(m, ..) => m.0.is_state(state),
+                        }
+                    }
+
+                    #[allow(unreachable_patterns)]
+                    pub fn is_final(&self) -> bool {
+                        match self {
+                            _ => false,
+                        }
+                    }
+                }
+
+                impl ::sm::Dispatch for Variant {
+                    type Event = Events;
+
+                    #[allow(unreachable_patterns)]
+                    fn dispatch(self, event: Events) -> ::core::result::Result<Self, (Self, Events)> {
+                        match (self, event) {
+                            (Variant::InitialLocked(m), Events::TurnKey(e)) => ::core::result::Result::Ok(m.transition(e).as_enum()),
+                            (Variant::InitialUnlocked(m), Events::TurnKey(e)) => ::core::result::Result::Ok(m.transition(e).as_enum()),
+                            (Variant::UnlockedByTurnKey(m), Events::TurnKey(e)) => ::core::result::Result::Ok(m.transition(e).as_enum()),
+                            (Variant::LockedByTurnKey(m), Events::TurnKey(e)) => ::core::result::Result::Ok(m.transition(e).as_enum()),
+                            (m, e) => ::core::result::Result::Err((m, e)),
+                        }
+                    }
+                }
+
+                impl<E: ::sm::Event> ::sm::Transition<TurnKey> for Machine<Locked, E> {
                     type Machine = Machine<Unlocked, TurnKey>;
 
                     fn transition(self, event: TurnKey) -> Self::Machine {
-                        Machine(Unlocked, Some(event))
+                        Machine(Unlocked, ::core::option::Option::Some(event))
                     }
                 }
 
-                impl<E: Event> Transition<TurnKey> for Machine<Unlocked, E> {
+                impl<E: ::sm::Event> ::sm::Transition<TurnKey> for Machine<Unlocked, E> {
                     type Machine = Machine<Locked, TurnKey>;
 
                     fn transition(self, event: TurnKey) -> Self::Machine {
-                        Machine(Locked, Some(event))
+                        Machine(Locked, ::core::option::Option::Some(event))
+                    }
+                }
+
+                impl<E: ::sm::Event> Machine<Locked, E> {
+                    pub fn turn_key(self) -> <Self as ::sm::Transition<TurnKey>>::Machine {
+                        self.transition(TurnKey)
+                    }
+                }
+
+                impl<E: ::sm::Event> Machine<Unlocked, E> {
+                    pub fn turn_key(self) -> <Self as ::sm::Transition<TurnKey>>::Machine {
+                        self.transition(TurnKey)
                     }
                 }
+
+                pub trait CanTurnKey: ::sm::Transition<TurnKey, Machine = <Self as CanTurnKey>::Next> {
+                    type Next;
+                }
+
+                impl<M: ::sm::Transition<TurnKey>> CanTurnKey for M {
+                    type Next = M::Machine;
+                }
             }
         };
 
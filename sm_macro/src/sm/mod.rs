@@ -1,5 +1,19 @@
+pub mod attribute;
+pub mod context;
+pub mod defer;
+pub mod derive;
+pub mod error;
 pub mod event;
+pub mod final_state;
+pub mod generics;
+pub mod group;
+pub mod import;
 pub mod initial_state;
 pub mod machine;
+pub mod names;
 pub mod state;
+pub mod submachine;
+pub mod ticks;
+pub mod timeout;
 pub mod transition;
+pub mod visibility;
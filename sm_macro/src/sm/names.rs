@@ -0,0 +1,292 @@
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use syn::{
+    braced,
+    parse::{Parse, ParseStream, Result},
+    punctuated::Punctuated,
+    Error, Ident, Token,
+};
+
+/// Methods already available on a generated machine, which cannot be used as
+/// the name of a transition method.
+const MACHINE_METHODS: &[&str] = &[
+    "as_enum",
+    "choose",
+    "clone",
+    "clone_from",
+    "context",
+    "context_mut",
+    "eq",
+    "fmt",
+    "ne",
+    "new",
+    "remaining_ticks",
+    "state",
+    "transition",
+    "transition_async",
+    "trigger",
+];
+
+/// Keywords which can only be used as an identifier in their raw form.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+/// Names of the blocks of a machine, with the syntax of the block. These can
+/// never be used as the name of an event, and are reserved for states as well,
+/// as a state with the name of a block is easily mistaken for one.
+const BLOCKS: &[(&str, &str)] = &[
+    ("After", "After(...) { ... }"),
+    ("AfterTicks", "AfterTicks(...) { ... }"),
+    ("Context", "Context { ... }"),
+    ("Defer", "Defer { ... }"),
+    ("FinalStates", "FinalStates { ... }"),
+    ("Groups", "Groups { ... }"),
+    ("InitialStates", "InitialStates { ... }"),
+    ("Names", "Names { ... }"),
+    ("Submachine", "Submachine(...) { ... }"),
+];
+
+/// block returns the syntax of the block the passed in name is reserved for,
+/// e.g. `After(...) { ... }` for `After`.
+pub(crate) fn block(name: &Ident) -> Option<&'static str> {
+    BLOCKS
+        .iter()
+        .find(|(block, _)| name == block)
+        .map(|(_, syntax)| *syntax)
+}
+
+/// TypeNames are the names of the types generated for a machine. They default
+/// to `Machine`, `Variant` and `Events`, and can be changed using a `Names`
+/// block, in case one of them is needed for a state or event.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TypeNames {
+    pub machine: Ident,
+    pub variant: Ident,
+    pub events: Ident,
+}
+
+impl Default for TypeNames {
+    fn default() -> Self {
+        TypeNames {
+            machine: Ident::new("Machine", Span::call_site()),
+            variant: Ident::new("Variant", Span::call_site()),
+            events: Ident::new("Events", Span::call_site()),
+        }
+    }
+}
+
+impl TypeNames {
+    /// all returns each of the names, along with its default.
+    pub(crate) fn all(&self) -> [(&'static str, &Ident); 3] {
+        [
+            ("Machine", &self.machine),
+            ("Variant", &self.variant),
+            ("Events", &self.events),
+        ]
+    }
+}
+
+impl Parse for TypeNames {
+    /// example names tokens:
+    ///
+    /// ```text
+    /// Names { Machine as Lock, Variant as LockState }
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut names = TypeNames::default();
+        let mut renamed: Vec<Ident> = Vec::new();
+
+        // `Names { ... }`
+        //  ^^^^^
+        let block_name: Ident = input.parse()?;
+
+        if block_name != "Names" {
+            return Err(input.error("expected `Names { ... }` block"));
+        }
+
+        // `Names { ... }`
+        //          ^^^
+        let block_names;
+        braced!(block_names in input);
+
+        // `Names { Machine as Lock, Variant as LockState }`
+        //          ^^^^^^^^^^^^^^^  ^^^^^^^^^^^^^^^^^^^^
+        let punctuated_names: Punctuated<(Ident, Ident), Token![,]> = block_names
+            .parse_terminated(|input| {
+                let name: Ident = input.parse()?;
+                let _: Token![as] = input.parse()?;
+                let rename: Ident = input.parse()?;
+
+                Ok((name, rename))
+            })?;
+
+        for (name, rename) in punctuated_names {
+            if renamed.contains(&name) {
+                return Err(Error::new(
+                    name.span(),
+                    format!("`{}` is renamed more than once", name),
+                ));
+            }
+
+            if name == "Machine" {
+                names.machine = rename;
+            } else if name == "Variant" {
+                names.variant = rename;
+            } else if name == "Events" {
+                names.events = rename;
+            } else {
+                return Err(Error::new(
+                    name.span(),
+                    format!(
+                        "unknown generated type `{}`, expected `Machine`, `Variant` or `Events`",
+                        name
+                    ),
+                ));
+            }
+
+            renamed.push(name);
+        }
+
+        Ok(names)
+    }
+}
+
+/// snake_case returns the snake case version of the passed in camel case
+/// identifier, e.g. `on_` and `UpdatingByDrainAccumulatedTime` result in
+/// `on_updating_by_drain_accumulated_time`.
+pub(crate) fn snake_case(prefix: &str, ident: &Ident) -> Ident {
+    let name = ident.to_string();
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake = String::from(prefix);
+
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lower) {
+                snake.push('_');
+            }
+        }
+
+        snake.extend(c.to_lowercase());
+    }
+
+    Ident::new(&snake, Span::call_site())
+}
+
+/// method returns the name of the transition method for the passed in event,
+/// e.g. `drain_accumulated_time` for `DrainAccumulatedTime`. Keywords result
+/// in a raw identifier, while `None` is returned for names which are already
+/// taken by the machine, or cannot be used as an identifier at all.
+pub(crate) fn method(event: &Ident) -> Option<TokenStream> {
+    let name = snake_case("", event).to_string();
+
+    if MACHINE_METHODS.contains(&name.as_str())
+        || ["crate", "self", "super"].contains(&name.as_str())
+    {
+        None
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name).parse().ok()
+    } else {
+        Some(Ident::new(&name, event.span()).into_token_stream())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_snake_case() {
+        let cases: Vec<(Ident, &str)> = vec![
+            (parse_quote! { Idle }, "idle"),
+            (parse_quote! { InitialIdle }, "initial_idle"),
+            (
+                parse_quote! { UpdatingByDrainAccumulatedTime },
+                "updating_by_drain_accumulated_time",
+            ),
+            (
+                parse_quote! { LoadedByHTTPRequest },
+                "loaded_by_http_request",
+            ),
+            (parse_quote! { Level2Loaded }, "level2_loaded"),
+        ];
+
+        for (ident, expected) in cases {
+            assert_eq!(snake_case("", &ident), expected);
+        }
+
+        assert_eq!(
+            snake_case("on_", &parse_quote! { InitialIdle }),
+            "on_initial_idle"
+        );
+    }
+
+    #[test]
+    fn test_block() {
+        assert_eq!(block(&parse_quote! { After }), Some("After(...) { ... }"));
+        assert_eq!(block(&parse_quote! { Groups }), Some("Groups { ... }"));
+        assert_eq!(block(&parse_quote! { Handler }), None);
+        assert_eq!(block(&parse_quote! { Locked }), None);
+    }
+
+    #[test]
+    fn test_type_names_parse() {
+        let left: TypeNames = syn::parse2(quote! {
+            Names { Machine as Lock, Variant as LockState }
+        })
+        .unwrap();
+
+        let right = TypeNames {
+            machine: parse_quote! { Lock },
+            variant: parse_quote! { LockState },
+            events: parse_quote! { Events },
+        };
+
+        assert_eq!(left, right);
+
+        let cases = vec![
+            (
+                quote! { Names { Machine as Lock, Machine as Door } },
+                "`Machine` is renamed more than once",
+            ),
+            (
+                quote! { Names { Group as LockGroup } },
+                "unknown generated type `Group`, expected `Machine`, `Variant` or `Events`",
+            ),
+        ];
+
+        for (tokens, expected) in cases {
+            let err = syn::parse2::<TypeNames>(tokens).unwrap_err();
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_method() {
+        let cases: Vec<(Ident, Option<&str>)> = vec![
+            (
+                parse_quote! { DrainAccumulatedTime },
+                Some("drain_accumulated_time"),
+            ),
+            (parse_quote! { Break }, Some("r#break")),
+            (parse_quote! { Trigger }, None),
+            (parse_quote! { Super }, None),
+        ];
+
+        for (ident, expected) in cases {
+            assert_eq!(
+                method(&ident).map(|m| m.to_string()),
+                expected.map(String::from)
+            );
+        }
+    }
+}
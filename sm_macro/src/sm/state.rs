@@ -6,13 +6,23 @@ use syn::{
     Ident,
 };
 
+use crate::sm::import::CratePath;
+
 #[derive(Debug, PartialEq)]
 pub(crate) struct States(pub Vec<State>);
 
 impl ToTokens for States {
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.to_tokens_with(tokens, &CratePath::default());
+    }
+}
+
+impl States {
+    /// to_tokens_with generates the states, implementing the traits of the
+    /// crate at the passed in path.
+    pub(crate) fn to_tokens_with(&self, tokens: &mut TokenStream, krate: &CratePath) {
         for state in &self.0 {
-            state.to_tokens(tokens);
+            state.to_tokens_with(tokens, krate);
 
             let name = &state.name;
             for other in &self.0 {
@@ -20,7 +30,7 @@ impl ToTokens for States {
                 let eq = name == other;
 
                 tokens.extend(quote! {
-                    impl PartialEq<#other> for #name {
+                    impl ::core::cmp::PartialEq<#other> for #name {
                         fn eq(&self, _: & #other) -> bool {
                             #eq
                         }
@@ -61,12 +71,20 @@ impl Parse for State {
 
 impl ToTokens for State {
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.to_tokens_with(tokens, &CratePath::default());
+    }
+}
+
+impl State {
+    /// to_tokens_with generates the state, implementing the `State` trait of
+    /// the crate at the passed in path.
+    pub(crate) fn to_tokens_with(&self, tokens: &mut TokenStream, krate: &CratePath) {
         let name = &self.name;
 
         tokens.extend(quote! {
             #[derive(Clone, Copy, Debug, Eq)]
             pub struct #name;
-            impl State for #name {}
+            impl #krate::State for #name {}
         });
     }
 }
@@ -96,7 +114,7 @@ mod tests {
         let left = quote! {
             #[derive(Clone, Copy, Debug, Eq)]
             pub struct Unlocked;
-            impl State for Unlocked {}
+            impl ::sm::State for Unlocked {}
         };
 
         let mut right = TokenStream::new();
@@ -119,15 +137,15 @@ mod tests {
         let left = quote! {
             #[derive(Clone, Copy, Debug, Eq)]
             pub struct Locked;
-            impl State for Locked {}
+            impl ::sm::State for Locked {}
 
-            impl PartialEq<Locked> for Locked {
+            impl ::core::cmp::PartialEq<Locked> for Locked {
                 fn eq(&self, _: &Locked) -> bool {
                     true
                 }
             }
 
-            impl PartialEq<Unlocked> for Locked {
+            impl ::core::cmp::PartialEq<Unlocked> for Locked {
                 fn eq(&self, _: &Unlocked) -> bool {
                     false
                 }
@@ -135,15 +153,15 @@ mod tests {
 
             #[derive(Clone, Copy, Debug, Eq)]
             pub struct Unlocked;
-            impl State for Unlocked {}
+            impl ::sm::State for Unlocked {}
 
-            impl PartialEq<Locked> for Unlocked {
+            impl ::core::cmp::PartialEq<Locked> for Unlocked {
                 fn eq(&self, _: & Locked) -> bool {
                     false
                 }
             }
 
-            impl PartialEq<Unlocked> for Unlocked {
+            impl ::core::cmp::PartialEq<Unlocked> for Unlocked {
                 fn eq(&self, _: & Unlocked) -> bool {
                     true
                 }
@@ -0,0 +1,193 @@
+use proc_macro2::TokenStream;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream, Result},
+    Error, Ident, Path, Token,
+};
+
+use crate::sm::{
+    error::Errors, event::Event, import::resolve, names::TypeNames, state::State,
+    transition::Transitions,
+};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Submachines(pub Vec<Submachine>);
+
+impl Submachines {
+    /// example submachines block tokens:
+    ///
+    /// ```text
+    /// Submachine(Reload: Ejecting) { Reloading => Idle }
+    /// ```
+    ///
+    /// Besides the submachines, this also returns the transitions triggered by
+    /// the generated `Completed` event, once the submachine reaches any of its
+    /// final states.
+    pub(crate) fn parse_block(
+        input: ParseStream<'_>,
+        errors: &mut Errors,
+    ) -> Result<(Self, Transitions)> {
+        let mut submachines: Vec<Submachine> = Vec::new();
+
+        // `Submachine(...) { ... }`
+        //  ^^^^^^^^^^
+        let block_name: Ident = input.parse()?;
+
+        if block_name != "Submachine" {
+            return Err(Error::new(
+                block_name.span(),
+                "expected `Submachine(...) { ... }` block",
+            ));
+        }
+
+        // `Submachine(Reload: Ejecting) { ... }`
+        //             ^^^^^^
+        let block_submachine;
+        parenthesized!(block_submachine in input);
+        let path = Path::parse_mod_style(&block_submachine)?;
+        let global = path.leading_colon.is_some();
+        let path: Vec<Ident> = path.segments.into_iter().map(|s| s.ident).collect();
+
+        // `Submachine(Reload: Ejecting) { ... }`
+        //                   ^ ^^^^^^^^
+        let _: Token![:] = block_submachine.parse()?;
+        let entry = State::parse(&block_submachine)?;
+
+        if block_submachine.peek(Token![=>]) {
+            let _: Token![=>] = block_submachine.parse()?;
+            let exit = State::parse(&block_submachine)?;
+
+            return Err(Error::new(
+                exit.name.span(),
+                format!(
+                    "a submachine completes in any of its final states, declare `FinalStates {{ {} }}` in the submachine instead",
+                    exit.name
+                ),
+            ));
+        }
+
+        // `Submachine(Reload: Ejecting) { Reloading => Idle }`
+        //                               ^^^^^^^^^^^^^^^^^^^^^
+        let event = Event {
+            name: Ident::new("Completed", block_name.span()),
+        };
+        let transitions = Transitions::parse_body(input, &event, errors)?;
+
+        for t in &transitions.0 {
+            submachines.push(Submachine {
+                state: t.from.clone(),
+                global,
+                path: path.clone(),
+                entry: entry.clone(),
+                names: TypeNames::default(),
+            });
+        }
+
+        Ok((Submachines(submachines), transitions))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Submachine {
+    pub state: State,
+    pub global: bool,
+    pub path: Vec<Ident>,
+    pub entry: State,
+    pub names: TypeNames,
+}
+
+impl Submachine {
+    /// name returns the name of the submachine, which is the last segment of
+    /// its path.
+    pub(crate) fn name(&self) -> &Ident {
+        self.path.last().expect("path has at least one segment")
+    }
+
+    /// module returns the path to the module of the submachine, as seen from
+    /// within the module of the parent machine.
+    pub(crate) fn module(&self) -> TokenStream {
+        resolve(self.global, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+    use syn::{parse::Parser, parse_quote};
+
+    #[test]
+    fn test_submachines_parse() {
+        let mut errors = Errors::default();
+        let parser = |input: ParseStream<'_>| Submachines::parse_block(input, &mut errors);
+        let (left, transitions) = parser
+            .parse2(quote! {
+                Submachine(Reload: Ejecting) { Reloading => Idle }
+            })
+            .unwrap();
+
+        let right = Submachines(vec![Submachine {
+            state: State {
+                name: parse_quote! { Reloading },
+            },
+            global: false,
+            path: vec![parse_quote! { Reload }],
+            entry: State {
+                name: parse_quote! { Ejecting },
+            },
+            names: TypeNames::default(),
+        }]);
+
+        assert_eq!(left, right);
+        assert_eq!(transitions.0.len(), 1);
+        assert_eq!(transitions.0[0].event.name, "Completed");
+        assert!(errors.is_empty());
+
+        let mut errors = Errors::default();
+        let parser = |input: ParseStream<'_>| Submachines::parse_block(input, &mut errors);
+        let result = parser.parse2(quote! {
+            Submachine(Reload: Ejecting => Loaded) { Reloading => Idle }
+        });
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "a submachine completes in any of its final states, declare `FinalStates { Loaded }` in the submachine instead"
+        );
+    }
+
+    #[test]
+    fn test_submachine_module() {
+        let submachine = |path: Path| Submachine {
+            state: State {
+                name: parse_quote! { Reloading },
+            },
+            global: path.leading_colon.is_some(),
+            path: path.segments.into_iter().map(|s| s.ident).collect(),
+            entry: State {
+                name: parse_quote! { Ejecting },
+            },
+            names: TypeNames::default(),
+        };
+
+        let cases: Vec<(Path, TokenStream)> = vec![
+            (parse_quote! { Reload }, quote! { self::scope::Reload }),
+            (parse_quote! { self::Reload }, quote! { super::Reload }),
+            (
+                parse_quote! { super::Reload },
+                quote! { super::super::Reload },
+            ),
+            (parse_quote! { crate::Reload }, quote! { crate::Reload }),
+            (
+                parse_quote! { ::machines::Reload },
+                quote! { ::machines::Reload },
+            ),
+        ];
+
+        for (path, expected) in cases {
+            let left = submachine(path);
+
+            assert_eq!(left.name(), "Reload");
+            assert_eq!(format!("{}", left.module()), format!("{}", expected));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use syn::{
+    parenthesized,
+    parse::{ParseStream, Result},
+    Error, Ident, LitInt,
+};
+
+use crate::sm::{error::Errors, event::Event, state::State, transition::Transitions};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct TickTimeouts(pub Vec<TickTimeout>);
+
+impl TickTimeouts {
+    /// example tick timeouts block tokens:
+    ///
+    /// ```text
+    /// AfterTicks(30) { Invulnerable => Vulnerable }
+    /// ```
+    ///
+    /// Besides the tick timeouts, this also returns the transitions triggered
+    /// by the generated `TickTimeout` event.
+    pub(crate) fn parse_block(
+        input: ParseStream<'_>,
+        errors: &mut Errors,
+    ) -> Result<(Self, Transitions)> {
+        let mut tick_timeouts: Vec<TickTimeout> = Vec::new();
+
+        // `AfterTicks(30) { ... }`
+        //  ^^^^^^^^^^
+        let block_name: Ident = input.parse()?;
+
+        if block_name != "AfterTicks" {
+            return Err(Error::new(
+                block_name.span(),
+                "expected `AfterTicks(...) { ... }` block",
+            ));
+        }
+
+        // `AfterTicks(30) { ... }`
+        //            ^^^^
+        let block_ticks;
+        parenthesized!(block_ticks in input);
+        let lit: LitInt = block_ticks.parse()?;
+
+        if lit.value() == 0 || lit.value() > u64::from(u32::MAX) {
+            return Err(Error::new(
+                lit.span(),
+                "expected a number of ticks between 1 and 4294967295",
+            ));
+        }
+
+        let ticks = lit.value() as u32;
+
+        // `AfterTicks(30) { Invulnerable => Vulnerable }`
+        //                 ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+        let event = Event {
+            name: Ident::new("TickTimeout", block_name.span()),
+        };
+        let transitions = Transitions::parse_body(input, &event, errors)?;
+
+        for t in &transitions.0 {
+            tick_timeouts.push(TickTimeout {
+                state: t.from.clone(),
+                ticks,
+            });
+        }
+
+        Ok((TickTimeouts(tick_timeouts), transitions))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TickTimeout {
+    pub state: State,
+    pub ticks: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sm::transition::Transition;
+    use quote::quote;
+    use syn::{parse::Parser, parse_quote};
+
+    #[test]
+    fn test_tick_timeouts_parse() {
+        let mut errors = Errors::default();
+        let parser = |input: ParseStream<'_>| TickTimeouts::parse_block(input, &mut errors);
+        let (left, transitions) = parser
+            .parse2(quote! {
+                AfterTicks(30) { Invulnerable, Spawning => Vulnerable }
+            })
+            .unwrap();
+
+        let right = TickTimeouts(vec![
+            TickTimeout {
+                state: State {
+                    name: parse_quote! { Invulnerable },
+                },
+                ticks: 30,
+            },
+            TickTimeout {
+                state: State {
+                    name: parse_quote! { Spawning },
+                },
+                ticks: 30,
+            },
+        ]);
+
+        assert_eq!(left, right);
+        assert_eq!(
+            transitions,
+            Transitions(vec![
+                Transition {
+                    event: Event {
+                        name: parse_quote! { TickTimeout },
+                    },
+                    from: State {
+                        name: parse_quote! { Invulnerable },
+                    },
+                    to: State {
+                        name: parse_quote! { Vulnerable },
+                    },
+                    choice: false,
+                    error: None,
+                },
+                Transition {
+                    event: Event {
+                        name: parse_quote! { TickTimeout },
+                    },
+                    from: State {
+                        name: parse_quote! { Spawning },
+                    },
+                    to: State {
+                        name: parse_quote! { Vulnerable },
+                    },
+                    choice: false,
+                    error: None,
+                },
+            ])
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_tick_timeouts_parse_zero() {
+        let mut errors = Errors::default();
+        let parser = |input: ParseStream<'_>| TickTimeouts::parse_block(input, &mut errors);
+        let result = parser.parse2(quote! {
+            AfterTicks(0) { Invulnerable => Vulnerable }
+        });
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use quote::ToTokens;
+use syn::{
+    parenthesized,
+    parse::{ParseStream, Result},
+    Error, Ident, Lit,
+};
+
+use crate::sm::{error::Errors, event::Event, state::State, transition::Transitions};
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Timeouts(pub Vec<Timeout>);
+
+impl Timeouts {
+    /// example timeouts block tokens:
+    ///
+    /// ```text
+    /// After(3s) { Stunned, Dazed => Idle }
+    /// ```
+    ///
+    /// Besides the timeouts, this also returns the transitions triggered by
+    /// the generated `Timeout` event.
+    pub(crate) fn parse_block(
+        input: ParseStream<'_>,
+        errors: &mut Errors,
+    ) -> Result<(Self, Transitions)> {
+        let mut timeouts: Vec<Timeout> = Vec::new();
+
+        // `After(3s) { ... }`
+        //  ^^^^^
+        let block_name: Ident = input.fork().parse()?;
+
+        if block_name != "After" {
+            return Err(input.error("expected `After(...) { ... }` block"));
+        }
+
+        // `After(3s) { ... }`
+        //       ^^^^
+        let _: Ident = input.parse()?;
+        let block_duration;
+        parenthesized!(block_duration in input);
+        let duration: Lit = block_duration.parse()?;
+        let millis =
+            parse_duration(&duration.clone().into_token_stream().to_string()).ok_or_else(|| {
+                Error::new_spanned(
+                    duration,
+                    "expected duration with a unit of `ms`, `s`, `m` or `h`, e.g. `3s`",
+                )
+            })?;
+
+        // `After(3s) { Stunned, Dazed => Idle }`
+        //            ^^^^^^^^^^^^^^^^^^^^^^^^^^
+        let event = Event {
+            name: Ident::new("Timeout", block_name.span()),
+        };
+        let transitions = Transitions::parse_body(input, &event, errors)?;
+
+        for t in &transitions.0 {
+            timeouts.push(Timeout {
+                state: t.from.clone(),
+                millis,
+            });
+        }
+
+        Ok((Timeouts(timeouts), transitions))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Timeout {
+    pub state: State,
+    pub millis: u64,
+}
+
+/// parse_duration converts a duration literal such as `3s` or `250ms` into
+/// the number of milliseconds it represents.
+fn parse_duration(value: &str) -> Option<u64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '_')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+
+    let factor = match unit {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        _ => return None,
+    };
+
+    number
+        .replace('_', "")
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("250ms"), Some(250));
+        assert_eq!(parse_duration("3s"), Some(3_000));
+        assert_eq!(parse_duration("2m"), Some(120_000));
+        assert_eq!(parse_duration("1h"), Some(3_600_000));
+        assert_eq!(parse_duration("1_500ms"), Some(1_500));
+        assert_eq!(parse_duration("3"), None);
+        assert_eq!(parse_duration("3d"), None);
+        assert_eq!(parse_duration("s"), None);
+    }
+}
@@ -1,13 +1,22 @@
-use proc_macro2::TokenStream;
-use quote::{quote, ToTokens};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::{
     braced,
     parse::{Parse, ParseStream, Result},
     token::Comma,
-    Token,
+    Error, Ident, Token,
 };
 
-use crate::sm::{event::Event, state::State};
+use crate::sm::{
+    context::Context,
+    error::Errors,
+    event::Event,
+    generics::Generics,
+    import::{CratePath, Imports},
+    names::{self, TypeNames},
+    state::State,
+    visibility::{self, Visibility},
+};
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Transitions(pub Vec<Transition>);
@@ -20,94 +29,715 @@ impl Parse for Transitions {
     /// Coin { ... }
     /// ```
     fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let mut errors = Errors::default();
         let mut transitions: Vec<Transition> = Vec::new();
         while !input.is_empty() {
-            // `Coin { Locked, Unlocked => Unlocked }`
-            //  ^^^^
-            let event = Event::parse(input)?;
+            transitions.extend(Transitions::parse_block(input, &mut errors)?.0);
+        }
 
-            // `Coin { Locked, Unlocked => Unlocked }`
-            //         ^^^^^^^^^^^^^^^^^^^^^^^^^^^^
-            let block_transition;
-            braced!(block_transition in input);
+        errors.finish(Ok(Transitions(transitions)))
+    }
+}
+
+impl Transitions {
+    /// example transitions block tokens:
+    ///
+    /// ```text
+    /// Coin { Locked, Unlocked => Unlocked }
+    /// ```
+    pub(crate) fn parse_block(input: ParseStream<'_>, errors: &mut Errors) -> Result<Self> {
+        // `Coin { Locked, Unlocked => Unlocked }`
+        //  ^^^^
+        let event = Event::parse(input)?;
+
+        Transitions::parse_body(input, &event, errors)
+    }
 
-            while !block_transition.is_empty() {
-                let mut from_states: Vec<State> = Vec::new();
+    /// example transitions body tokens, for the given event:
+    ///
+    /// ```text
+    /// { Locked, Unlocked => Unlocked }
+    /// ```
+    ///
+    /// Multiple target states separated by a `|` declare a choice, which
+    /// results in one transition per target state. A target state followed by
+    /// a `?` and an error state declares a fallible transition.
+    ///
+    /// Problems that do not prevent parsing the rest of the body, such as a
+    /// stray `,`, are added to `errors`, while any other problem is returned as
+    /// an error.
+    pub(crate) fn parse_body(
+        input: ParseStream<'_>,
+        event: &Event,
+        errors: &mut Errors,
+    ) -> Result<Self> {
+        let mut transitions: Vec<Transition> = Vec::new();
+
+        // `Coin { Locked, Unlocked => Unlocked }`
+        //         ^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+        let block_transition;
+        braced!(block_transition in input);
+
+        while !block_transition.is_empty() {
+            let mut from_states: Vec<State> = Vec::new();
+
+            loop {
+                // `Coin { Locked, Unlocked }`
+                //                         ^
+                if block_transition.is_empty() {
+                    return Err(match &from_states[..] {
+                        [] => Error::new(event.name.span(), "expected a source state"),
+                        [.., from, to] => Error::new(
+                            to.name.span(),
+                            format!(
+                                "expected `=>` after `{}`, did you mean `{} => {}`?",
+                                to.name, from.name, to.name
+                            ),
+                        ),
+                        [from] => Error::new(
+                            from.name.span(),
+                            format!("expected `=>` and a target state after `{}`", from.name),
+                        ),
+                    });
+                }
 
-                // `Coin { Locked, Unlocked => Unlocked }`
-                //                          ^^
-                while !block_transition.peek(Token![=>]) {
+                let lookahead = block_transition.lookahead1();
+
+                if lookahead.peek(Token![=>]) {
+                    // `Coin { Locked, Unlocked => Unlocked }`
+                    //                          ^^
+                    break;
+                } else if lookahead.peek(Token![,]) {
                     // `Coin { Locked, Unlocked => Unlocked }`
                     //               ^
-                    if block_transition.peek(Token![,]) {
-                        let _: Comma = block_transition.parse()?;
-                        continue;
-                    }
+                    let comma: Comma = block_transition.parse()?;
 
+                    if from_states.is_empty() || !block_transition.peek(Ident) {
+                        errors.push(Error::new(
+                            comma.spans[0],
+                            "unexpected `,`, a `,` can only be used between two source states",
+                        ));
+                    }
+                } else if lookahead.peek(Ident) {
                     // `Coin { Locked, Unlocked => Unlocked }`
                     //         ^^^^^^  ^^^^^^^^
                     from_states.push(State::parse(&block_transition)?);
+                } else {
+                    return Err(lookahead.error());
                 }
+            }
 
-                // `Coin { Locked, Unlocked => Unlocked }`
-                //                          ^^
-                let _: Token![=>] = block_transition.parse()?;
+            // `Coin { Locked, Unlocked => Unlocked }`
+            //                          ^^
+            let arrow: Token![=>] = block_transition.parse()?;
 
-                // `Coin { Locked, Unlocked => Unlocked }`
-                //                             ^^^^^^^^
-                let to = State::parse(&block_transition)?;
+            if from_states.is_empty() {
+                errors.push(Error::new_spanned(
+                    arrow,
+                    "expected a source state before `=>`",
+                ));
+            }
+
+            // `Coin { Locked, Unlocked => Unlocked }`
+            //                             ^^^^^^^^
+            let mut to_states = vec![parse_state(&block_transition, &arrow, "a target state")?];
+
+            // `Attack { Aiming => Hit | Miss }`
+            //                         ^^^^^^
+            while block_transition.peek(Token![|]) {
+                let bar: Token![|] = block_transition.parse()?;
+                to_states.push(parse_state(&block_transition, &bar, "a target state")?);
+            }
 
-                for from in from_states {
+            let choice = to_states.len() > 1;
+
+            // `Connect { Idle => Connected ? Failed }`
+            //                              ^^^^^^^^
+            let mut error: Option<State> = None;
+            if block_transition.peek(Token![?]) {
+                let question: Token![?] = block_transition.parse()?;
+
+                if choice {
+                    errors.push(Error::new(
+                        question.spans[0],
+                        "choice transitions cannot be fallible",
+                    ));
+                }
+
+                error = Some(parse_state(&block_transition, &question, "an error state")?);
+            }
+
+            for (i, from) in from_states.iter().enumerate() {
+                if from_states[..i].iter().any(|f| f.name == from.name) {
+                    errors.push(Error::new(
+                        from.name.span(),
+                        format!(
+                            "state `{}` is listed more than once as a source state, is a `=>` missing?",
+                            from.name
+                        ),
+                    ));
+                }
+            }
+
+            for state in from_states.iter().chain(&to_states).chain(&error) {
+                if let Some(block) = names::block(&state.name) {
+                    errors.push(Error::new(
+                        state.name.span(),
+                        format!(
+                            "`{}` is reserved for the `{}` block, and cannot be used as a state",
+                            state.name, block
+                        ),
+                    ));
+                }
+            }
+
+            for from in from_states {
+                for to in &to_states {
                     let event = event.clone();
+                    let from = from.clone();
                     let to = to.clone();
+                    let error = error.clone();
 
-                    transitions.push(Transition { event, from, to })
+                    transitions.push(Transition {
+                        event,
+                        from,
+                        to,
+                        choice,
+                        error,
+                    })
                 }
             }
+
+            // `Coin { Locked => Unlocked, Unlocked => Locked }`
+            //                           ^
+            while block_transition.peek(Token![,]) {
+                let comma: Comma = block_transition.parse()?;
+
+                errors.push(Error::new(
+                    comma.spans[0],
+                    "unexpected `,`, a `,` can only be used between two source states",
+                ));
+            }
         }
 
         Ok(Transitions(transitions))
     }
 }
 
+/// parse_state parses the state following the passed in token, e.g. the target
+/// state after `=>`, reporting a missing state at the token.
+fn parse_state<T: ToTokens>(input: ParseStream<'_>, token: &T, expected: &str) -> Result<State> {
+    if !input.peek(Ident) {
+        return Err(Error::new_spanned(
+            token,
+            format!(
+                "expected {} after `{}`",
+                expected,
+                token.into_token_stream()
+            ),
+        ));
+    }
+
+    State::parse(input)
+}
+
 impl ToTokens for Transitions {
     fn to_tokens(&self, tokens: &mut TokenStream) {
+        let machine = TypeNames::default().machine;
+
+        self.to_tokens_with(
+            tokens,
+            &Imports(Vec::new()),
+            &machine,
+            &CratePath::default(),
+            &Generics::default(),
+            Fields::default(),
+        );
+    }
+}
+
+impl Transitions {
+    /// to_tokens_with generates the transitions for the passed in machine
+    /// type, passing the extra `fields` of the machine on to each newly
+    /// constructed machine. Imported states are constructed using `Enter`, or
+    /// `EnterWith` if the machine has a context.
+    pub(crate) fn to_tokens_with(
+        &self,
+        tokens: &mut TokenStream,
+        imports: &Imports,
+        machine: &Ident,
+        krate: &CratePath,
+        generics: &Generics,
+        fields: Fields<'_>,
+    ) {
+        let mut choices: Vec<&Transition> = Vec::new();
+
+        for transition in &self.0 {
+            if !transition.choice {
+                transition.to_tokens_with(tokens, imports, machine, krate, generics, fields);
+                continue;
+            }
+
+            // Each choice is generated once, for all of its targets.
+            if choices.iter().any(|c| c.chooses(transition)) {
+                continue;
+            }
+
+            choices.push(transition);
+            let targets = self
+                .0
+                .iter()
+                .filter(|t| transition.chooses(t))
+                .collect::<Vec<_>>();
+
+            transition
+                .choice_to_tokens_with(tokens, &targets, imports, machine, krate, generics, fields);
+        }
+    }
+
+    /// completed_to_tokens_with generates the transitions triggered by the
+    /// `Completed` event of the submachines of the passed in machine type. See
+    /// `Transition::completed_to_tokens_with`.
+    pub(crate) fn completed_to_tokens_with(
+        &self,
+        tokens: &mut TokenStream,
+        imports: &Imports,
+        machine: &Ident,
+        krate: &CratePath,
+        generics: &Generics,
+        fields: Fields<'_>,
+    ) {
         for transition in &self.0 {
-            transition.to_tokens(tokens);
+            transition.completed_to_tokens_with(tokens, imports, machine, krate, generics, fields);
         }
     }
+
+    /// methods generates a named method for each event accepted by a state,
+    /// e.g. `m.coin()` as a shorthand for `m.transition(Coin)`. The methods
+    /// are only implemented for the machines in the states that accept the
+    /// event. Imported events are passed to the method, while local events
+    /// are constructed by the method itself. Each method has the visibility of
+    /// its event.
+    pub(crate) fn methods(
+        &self,
+        imports: &Imports,
+        machine: &Ident,
+        krate: &CratePath,
+        generics: &Generics,
+        visibilities: &[(Ident, Visibility)],
+    ) -> TokenStream {
+        let (params, args) = (generics.params(), generics.args());
+        let mut tokens = TokenStream::new();
+        let mut froms: Vec<&Ident> = Vec::new();
+
+        for transition in &self.0 {
+            if !froms.contains(&&transition.from.name) {
+                froms.push(&transition.from.name);
+            }
+        }
+
+        for from in froms {
+            let mut events: Vec<&Ident> = Vec::new();
+            let mut methods = TokenStream::new();
+
+            for transition in self.0.iter().filter(|t| &t.from.name == from) {
+                if events.contains(&&transition.event.name) {
+                    continue;
+                }
+
+                let visibility = visibility::of(visibilities, &transition.event.name);
+
+                events.push(&transition.event.name);
+                methods.extend(transition.method(imports, krate, &visibility));
+            }
+
+            if !methods.is_empty() {
+                let from = imports.ty(from);
+
+                tokens.extend(quote! {
+                    impl<#params E: #krate::Event> #machine<#args #from, E> {
+                        #methods
+                    }
+                });
+            }
+        }
+
+        tokens
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct Transition {
     pub event: Event,
     pub from: State,
     pub to: State,
+    pub choice: bool,
+    pub error: Option<State>,
 }
 
 impl ToTokens for Transition {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let event = &self.event.name;
-        let from = &self.from.name;
-        let to = &self.to.name;
+        let machine = TypeNames::default().machine;
+
+        self.to_tokens_with(
+            tokens,
+            &Imports(Vec::new()),
+            &machine,
+            &CratePath::default(),
+            &Generics::default(),
+            Fields::default(),
+        );
+    }
+}
+
+impl Transition {
+    /// enter returns the expression constructing the passed in state, when it
+    /// is entered using this transition.
+    fn enter(
+        &self,
+        state: &State,
+        imports: &Imports,
+        krate: &CratePath,
+        context: Option<&Context>,
+    ) -> TokenStream {
+        let name = &state.name;
+
+        if !imports.contains(name) {
+            return quote! { #name };
+        }
+
+        let name = imports.ty(name);
+        let event = imports.ty(&self.event.name);
+        let from = imports.ty(&self.from.name);
+
+        match context {
+            Some(context) => quote! {
+                <#name as #krate::EnterWith<#from, #event, #context>>::enter_with(&self.0, &event, &mut context)
+            },
+            None => quote! { <#name as #krate::Enter<#from, #event>>::enter(&self.0, &event) },
+        }
+    }
+
+    /// target returns a description of the target of this transition, as
+    /// used in error messages.
+    pub(crate) fn target(&self) -> String {
+        match &self.error {
+            Some(error) => format!("`{} ? {}`", self.to.name, error.name),
+            None if self.choice => format!("`{}` (choice)", self.to.name),
+            None => format!("`{}`", self.to.name),
+        }
+    }
+
+    /// method returns the named method triggering this transition, if the
+    /// name of the event can be used as a method name.
+    fn method(
+        &self,
+        imports: &Imports,
+        krate: &CratePath,
+        visibility: &Visibility,
+    ) -> Option<TokenStream> {
+        let method = names::method(&self.event.name)?;
+        let event = imports.ty(&self.event.name);
+
+        let (param, arg) = if imports.contains(&self.event.name) {
+            (quote! { , event: #event }, quote! { event })
+        } else {
+            (TokenStream::new(), quote! { #event })
+        };
+
+        if self.choice {
+            return Some(quote! {
+                #visibility fn #method(self #param) -> <Self as #krate::Choice<#event>>::Machine
+                where
+                    Self: #krate::Choice<#event>,
+                {
+                    #krate::Choice::choose(self, #arg)
+                }
+            });
+        }
+
+        if self.error.is_some() {
+            let fallible = quote! { <Self as #krate::TryTransition<#event>> };
+
+            return Some(quote! {
+                #visibility fn #method(self #param) -> ::core::result::Result<#fallible::Machine, (#fallible::Failed, #fallible::Error)> {
+                    #krate::TryTransition::try_transition(self, #arg)
+                }
+            });
+        }
+
+        Some(quote! {
+            #visibility fn #method(self #param) -> <Self as #krate::Transition<#event>>::Machine {
+                self.transition(#arg)
+            }
+        })
+    }
+
+    fn to_tokens_with(
+        &self,
+        tokens: &mut TokenStream,
+        imports: &Imports,
+        machine: &Ident,
+        krate: &CratePath,
+        generics: &Generics,
+        fields: Fields<'_>,
+    ) {
+        if self.choice {
+            return self.choice_to_tokens_with(
+                tokens,
+                &[self],
+                imports,
+                machine,
+                krate,
+                generics,
+                fields,
+            );
+        }
+
+        let event = imports.ty(&self.event.name);
+        let from = imports.ty(&self.from.name);
+        let to = imports.ty(&self.to.name);
+        let to_fields = fields.values(&to);
+        let to_state = self.enter(&self.to, imports, krate, fields.context);
+        let (params, args) = (generics.params(), generics.args());
+        let phantom = generics.phantom_new();
+
+        // The context is moved out of the machine before the new state is
+        // entered, as entering an imported state can change the context.
+        let entered =
+            imports.contains(&self.to.name) || self.error.iter().any(|e| imports.contains(&e.name));
+        let carry = fields.carry(entered);
+
+        if let Some(error) = &self.error {
+            let error_fields = fields.values(&imports.ty(&error.name));
+            let error_state = self.enter(error, imports, krate, fields.context);
+            let error = imports.ty(&error.name);
+            let span = self.event.name.span();
+            let (error_type, guard) = match fields.context {
+                Some(context) => (
+                    quote! { <#event as #krate::FallibleWith<#from, #context>>::Error },
+                    quote! {
+                        <#event as #krate::FallibleWith<#from, #context>>::try_transition_with(&event, &self.0, &context)
+                    },
+                ),
+                None => (
+                    quote! { <#event as #krate::Fallible<#from>>::Error },
+                    quote! { <#event as #krate::Fallible<#from>>::try_transition(&event, &self.0) },
+                ),
+            };
+
+            // The impls are spanned to the event, so a missing `Fallible` impl
+            // is reported at the transition. It is reported at the macro
+            // invocation as well, for the tokens that keep their own span.
+            tokens.extend(quote_spanned! { span=>
+                impl<#params E: #krate::Event> #krate::TryTransition<#event> for #machine<#args #from, E> {
+                    type Machine = #machine<#args #to, #event>;
+                    type Failed = #machine<#args #error, #event>;
+                    type Error = #error_type;
+
+                    fn try_transition(self, event: #event) -> ::core::result::Result<Self::Machine, (Self::Failed, Self::Error)> {
+                        #carry
+                        match #guard {
+                            ::core::result::Result::Ok(()) => ::core::result::Result::Ok(#machine(#to_state, ::core::option::Option::Some(event) #to_fields #phantom)),
+                            ::core::result::Result::Err(err) => ::core::result::Result::Err((#machine(#error_state, ::core::option::Option::Some(event) #error_fields #phantom), err)),
+                        }
+                    }
+                }
+
+                impl<#params E: #krate::Event> #krate::Accept<#machine<#args #from, E>> for #event {
+                    type Machine = ::core::result::Result<
+                        #machine<#args #to, #event>,
+                        (#machine<#args #error, #event>, #error_type),
+                    >;
+
+                    fn accept(self, machine: #machine<#args #from, E>) -> Self::Machine {
+                        #krate::TryTransition::try_transition(machine, self)
+                    }
+                }
+            });
+
+            return;
+        }
 
         tokens.extend(quote! {
-            impl<E: Event> Transition<#event> for Machine<#from, E> {
-                type Machine = Machine<#to, #event>;
+            impl<#params E: #krate::Event> #krate::Transition<#event> for #machine<#args #from, E> {
+                type Machine = #machine<#args #to, #event>;
 
                 fn transition(self, event: #event) -> Self::Machine {
-                    Machine(#to, Some(event))
+                    #carry
+                    #machine(#to_state, ::core::option::Option::Some(event) #to_fields #phantom)
+                }
+            }
+        });
+    }
+
+    /// chooses returns whether the passed in transition is one of the targets
+    /// of the same choice as this transition.
+    fn chooses(&self, other: &Transition) -> bool {
+        self.choice
+            && other.choice
+            && self.event.name == other.event.name
+            && self.from.name == other.from.name
+    }
+
+    /// choice_to_tokens_with generates the choice of this transition, for all
+    /// of the passed in targets of the choice. The target is selected by the
+    /// `Choose` implementation of the event, or `ChooseWith` if the machine has
+    /// a context, which returns the generated branch enum of the choice.
+    #[allow(clippy::too_many_arguments)]
+    fn choice_to_tokens_with(
+        &self,
+        tokens: &mut TokenStream,
+        targets: &[&Transition],
+        imports: &Imports,
+        machine: &Ident,
+        krate: &CratePath,
+        generics: &Generics,
+        fields: Fields<'_>,
+    ) {
+        let event = imports.ty(&self.event.name);
+        let from = imports.ty(&self.from.name);
+        let (params, args) = (generics.params(), generics.args());
+        let phantom = generics.phantom_new();
+        let apply = generics.apply();
+        let name = format!("{}From{}", self.event.name, self.from.name);
+        let choice = Ident::new(&name, Span::call_site());
+        let branch = Ident::new(&format!("{}Branch", name), Span::call_site());
+        let span = self.event.name.span();
+
+        let entered = targets.iter().any(|t| imports.contains(&t.to.name));
+        let carry = fields.carry(entered);
+        let guard = match fields.context {
+            Some(context) => quote_spanned! { span=>
+                <#event as #krate::ChooseWith<#from, #context>>::choose_with(&event, &self.0, &context)
+            },
+            None => quote_spanned! { span=>
+                <#event as #krate::Choose<#from>>::choose(&event, &self.0)
+            },
+        };
+        let arms = targets.iter().map(|t| {
+            let variant = &t.to.name;
+            let to_fields = fields.values(&imports.ty(&t.to.name));
+            let to_state = t.enter(&t.to, imports, krate, fields.context);
+
+            quote! {
+                #branch::#variant => #choice::#variant(#machine(#to_state, ::core::option::Option::Some(event) #to_fields #phantom)),
+            }
+        });
+
+        tokens.extend(quote! {
+            impl<#params E: #krate::Event> #krate::Choice<#event> for #machine<#args #from, E> {
+                type Machine = #choice #apply;
+
+                fn choose(self, event: #event) -> Self::Machine {
+                    #carry
+                    match #guard {
+                        #(#arms)*
+                    }
+                }
+            }
+
+            impl<#params E: #krate::Event> #krate::Accept<#machine<#args #from, E>> for #event {
+                type Machine = #choice #apply;
+
+                fn accept(self, machine: #machine<#args #from, E>) -> Self::Machine {
+                    #krate::Choice::choose(machine, self)
                 }
             }
         });
     }
+
+    /// completed_to_tokens_with generates this transition, triggered by the
+    /// `Completed` event of a submachine, as a private `completed` method
+    /// instead of a `Transition` implementation. The method is only called by
+    /// the dispatch of the machine variant, once the submachine reaches one of
+    /// its final states, so that the submachine cannot be skipped.
+    fn completed_to_tokens_with(
+        &self,
+        tokens: &mut TokenStream,
+        imports: &Imports,
+        machine: &Ident,
+        krate: &CratePath,
+        generics: &Generics,
+        fields: Fields<'_>,
+    ) {
+        let event = imports.ty(&self.event.name);
+        let from = imports.ty(&self.from.name);
+        let to = imports.ty(&self.to.name);
+        let to_fields = fields.values(&to);
+        let to_state = self.enter(&self.to, imports, krate, fields.context);
+        let (params, args) = (generics.params(), generics.args());
+        let phantom = generics.phantom_new();
+        let carry = fields.carry(imports.contains(&self.to.name));
+
+        tokens.extend(quote! {
+            impl<#params E: #krate::Event> #machine<#args #from, E> {
+                fn completed(self) -> #machine<#args #to, #event> {
+                    let event = #event;
+                    #carry
+                    #machine(#to_state, ::core::option::Option::Some(event) #to_fields #phantom)
+                }
+            }
+        });
+    }
+}
+
+/// Fields are the fields of a generated machine that follow its state and
+/// event, which are passed on by each transition to the new machine.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Fields<'a> {
+    /// ticks is set if the machine counts the ticks left in its state, using
+    /// a field that is reset to the ticks of the new state.
+    pub ticks: bool,
+    /// context is the type of the context shared by the states of the
+    /// machine, if any, which is stored in a field after the ticks.
+    pub context: Option<&'a Context>,
+}
+
+impl Fields<'_> {
+    /// index returns the index of the context field.
+    pub(crate) fn index(self) -> syn::Index {
+        syn::Index::from(if self.ticks { 3 } else { 2 })
+    }
+
+    /// carry returns the statement moving the context out of the machine,
+    /// which is mutable if the context is passed to `EnterWith`.
+    fn carry(self, mutable: bool) -> TokenStream {
+        if self.context.is_none() {
+            return TokenStream::new();
+        }
+
+        let index = self.index();
+
+        if mutable {
+            quote! { let mut context = self.#index; }
+        } else {
+            quote! { let context = self.#index; }
+        }
+    }
+
+    /// values returns the values of the fields, each preceded by a comma, for
+    /// a new machine in the passed in state.
+    fn values(self, state: &TokenStream) -> TokenStream {
+        let mut tokens = TokenStream::new();
+
+        if self.ticks {
+            tokens.extend(quote! { , <#state as Ticks>::TICKS });
+        }
+
+        if self.context.is_some() {
+            tokens.extend(quote! { , context });
+        }
+
+        tokens
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use proc_macro2::TokenStream;
-    use syn::{self, parse_quote};
+    use syn::{self, parse::Parser, parse_quote};
 
     #[test]
     fn test_transition_to_tokens() {
@@ -121,14 +751,16 @@ mod tests {
             to: State {
                 name: parse_quote! { Unlocked },
             },
+            choice: false,
+            error: None,
         };
 
         let left = quote! {
-            impl<E: Event> Transition<Push> for Machine<Locked, E> {
+            impl<E: ::sm::Event> ::sm::Transition<Push> for Machine<Locked, E> {
                 type Machine = Machine<Unlocked, Push>;
 
                 fn transition(self, event: Push) -> Self::Machine {
-                    Machine(Unlocked, Some(event))
+                    Machine(Unlocked, ::core::option::Option::Some(event))
                 }
             }
         };
@@ -139,6 +771,295 @@ mod tests {
         assert_eq!(format!("{}", left), format!("{}", right))
     }
 
+    #[test]
+    fn test_transition_completed_to_tokens() {
+        let transition = Transition {
+            event: Event {
+                name: parse_quote! { Completed },
+            },
+            from: State {
+                name: parse_quote! { Reloading },
+            },
+            to: State {
+                name: parse_quote! { Idle },
+            },
+            choice: false,
+            error: None,
+        };
+
+        let left = quote! {
+            impl<E: ::sm::Event> Machine<Reloading, E> {
+                fn completed(self) -> Machine<Idle, Completed> {
+                    let event = Completed;
+                    Machine(Idle, ::core::option::Option::Some(event))
+                }
+            }
+        };
+
+        let mut right = TokenStream::new();
+        transition.completed_to_tokens_with(
+            &mut right,
+            &Imports(Vec::new()),
+            &TypeNames::default().machine,
+            &CratePath::default(),
+            &Generics::default(),
+            Fields::default(),
+        );
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+
+    #[test]
+    fn test_transition_context_to_tokens() {
+        let transition = Transition {
+            event: Event {
+                name: parse_quote! { Coin },
+            },
+            from: State {
+                name: parse_quote! { Locked },
+            },
+            to: State {
+                name: parse_quote! { Unlocked },
+            },
+            choice: false,
+            error: None,
+        };
+        let imports: Imports = syn::parse2(quote! { use crate::Unlocked; }).unwrap();
+        let context: Context = syn::parse2(quote! { Context { crate::Stats } }).unwrap();
+        let fields = Fields {
+            ticks: false,
+            context: Some(&context),
+        };
+
+        let left = quote! {
+            impl<E: ::sm::Event> ::sm::Transition<Coin> for Machine<Locked, E> {
+                type Machine = Machine<Unlocked, Coin>;
+
+                fn transition(self, event: Coin) -> Self::Machine {
+                    let mut context = self.2;
+                    Machine(
+                        <Unlocked as ::sm::EnterWith<Locked, Coin, crate::Stats>>::enter_with(&self.0, &event, &mut context),
+                        ::core::option::Option::Some(event),
+                        context
+                    )
+                }
+            }
+        };
+
+        let mut right = TokenStream::new();
+        transition.to_tokens_with(
+            &mut right,
+            &imports,
+            &parse_quote! { Machine },
+            &CratePath::default(),
+            &Generics::default(),
+            fields,
+        );
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+
+    #[test]
+    fn test_choice_to_tokens() {
+        let transitions: Transitions = syn::parse2(quote! {
+            Attack { Aiming => Hit | Miss }
+        })
+        .unwrap();
+
+        let left = quote! {
+            impl<E: ::sm::Event> ::sm::Choice<Attack> for Machine<Aiming, E> {
+                type Machine = AttackFromAiming;
+
+                fn choose(self, event: Attack) -> Self::Machine {
+                    match <Attack as ::sm::Choose<Aiming>>::choose(&event, &self.0) {
+                        AttackFromAimingBranch::Hit => AttackFromAiming::Hit(Machine(Hit, ::core::option::Option::Some(event))),
+                        AttackFromAimingBranch::Miss => AttackFromAiming::Miss(Machine(Miss, ::core::option::Option::Some(event))),
+                    }
+                }
+            }
+
+            impl<E: ::sm::Event> ::sm::Accept<Machine<Aiming, E>> for Attack {
+                type Machine = AttackFromAiming;
+
+                fn accept(self, machine: Machine<Aiming, E>) -> Self::Machine {
+                    ::sm::Choice::choose(machine, self)
+                }
+            }
+        };
+
+        let mut right = TokenStream::new();
+        transitions.to_tokens(&mut right);
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+
+    #[test]
+    fn test_transitions_parse_choice() {
+        let left: Transitions = syn::parse2(quote! {
+            Attack { Aiming => Hit | Miss }
+        })
+        .unwrap();
+
+        let right = Transitions(vec![
+            Transition {
+                event: Event {
+                    name: parse_quote! { Attack },
+                },
+                from: State {
+                    name: parse_quote! { Aiming },
+                },
+                to: State {
+                    name: parse_quote! { Hit },
+                },
+                choice: true,
+                error: None,
+            },
+            Transition {
+                event: Event {
+                    name: parse_quote! { Attack },
+                },
+                from: State {
+                    name: parse_quote! { Aiming },
+                },
+                to: State {
+                    name: parse_quote! { Miss },
+                },
+                choice: true,
+                error: None,
+            },
+        ]);
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_fallible_to_tokens() {
+        let transition = Transition {
+            event: Event {
+                name: parse_quote! { Connect },
+            },
+            from: State {
+                name: parse_quote! { Idle },
+            },
+            to: State {
+                name: parse_quote! { Connected },
+            },
+            choice: false,
+            error: Some(State {
+                name: parse_quote! { Failed },
+            }),
+        };
+
+        let left = quote! {
+            impl<E: ::sm::Event> ::sm::TryTransition<Connect> for Machine<Idle, E> {
+                type Machine = Machine<Connected, Connect>;
+                type Failed = Machine<Failed, Connect>;
+                type Error = <Connect as ::sm::Fallible<Idle>>::Error;
+
+                fn try_transition(self, event: Connect) -> ::core::result::Result<Self::Machine, (Self::Failed, Self::Error)> {
+                    match <Connect as ::sm::Fallible<Idle>>::try_transition(&event, &self.0) {
+                        ::core::result::Result::Ok(()) => ::core::result::Result::Ok(Machine(Connected, ::core::option::Option::Some(event))),
+                        ::core::result::Result::Err(err) => ::core::result::Result::Err((Machine(Failed, ::core::option::Option::Some(event)), err)),
+                    }
+                }
+            }
+
+            impl<E: ::sm::Event> ::sm::Accept<Machine<Idle, E>> for Connect {
+                type Machine = ::core::result::Result<
+                    Machine<Connected, Connect>,
+                    (Machine<Failed, Connect>, <Connect as ::sm::Fallible<Idle>>::Error),
+                >;
+
+                fn accept(self, machine: Machine<Idle, E>) -> Self::Machine {
+                    ::sm::TryTransition::try_transition(machine, self)
+                }
+            }
+        };
+
+        let mut right = TokenStream::new();
+        transition.to_tokens(&mut right);
+
+        assert_eq!(format!("{}", left), format!("{}", right))
+    }
+
+    #[test]
+    fn test_transitions_parse_fallible() {
+        let left: Transitions = syn::parse2(quote! {
+            Connect { Idle, Retrying => Connected ? Failed }
+        })
+        .unwrap();
+
+        let right = Transitions(vec![
+            Transition {
+                event: Event {
+                    name: parse_quote! { Connect },
+                },
+                from: State {
+                    name: parse_quote! { Idle },
+                },
+                to: State {
+                    name: parse_quote! { Connected },
+                },
+                choice: false,
+                error: Some(State {
+                    name: parse_quote! { Failed },
+                }),
+            },
+            Transition {
+                event: Event {
+                    name: parse_quote! { Connect },
+                },
+                from: State {
+                    name: parse_quote! { Retrying },
+                },
+                to: State {
+                    name: parse_quote! { Connected },
+                },
+                choice: false,
+                error: Some(State {
+                    name: parse_quote! { Failed },
+                }),
+            },
+        ]);
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn test_transitions_parse_fallible_choice() {
+        let result: Result<Transitions> = syn::parse2(quote! {
+            Attack { Aiming => Hit | Miss ? Jammed }
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_transitions_parse_stray_comma() {
+        let mut errors = Errors::default();
+        let event = Event {
+            name: parse_quote! { TurnKey },
+        };
+        let parser = |input: ParseStream<'_>| Transitions::parse_body(input, &event, &mut errors);
+        let transitions = parser
+            .parse2(quote! {
+                { Locked, => Unlocked,, Unlocked => Locked, }
+            })
+            .unwrap();
+
+        assert_eq!(transitions.0.len(), 2);
+        assert_eq!(errors.len(), 4);
+    }
+
+    #[test]
+    fn test_transitions_parse_reserved_state() {
+        let result: Result<Transitions> = syn::parse2(quote! {
+            TurnKey { Locked => Groups }
+        });
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_transitions_parse() {
         let left: Transitions = syn::parse2(quote! {
@@ -158,6 +1079,8 @@ mod tests {
                 to: State {
                     name: parse_quote! { Locked },
                 },
+                choice: false,
+                error: None,
             },
             Transition {
                 event: Event {
@@ -169,6 +1092,8 @@ mod tests {
                 to: State {
                     name: parse_quote! { Locked },
                 },
+                choice: false,
+                error: None,
             },
             Transition {
                 event: Event {
@@ -180,6 +1105,8 @@ mod tests {
                 to: State {
                     name: parse_quote! { Unlocked },
                 },
+                choice: false,
+                error: None,
             },
             Transition {
                 event: Event {
@@ -191,6 +1118,8 @@ mod tests {
                 to: State {
                     name: parse_quote! { Unlocked },
                 },
+                choice: false,
+                error: None,
             },
         ]);
 
@@ -210,6 +1139,8 @@ mod tests {
                 to: State {
                     name: parse_quote! { Locked },
                 },
+                choice: false,
+                error: None,
             },
             Transition {
                 event: Event {
@@ -221,6 +1152,8 @@ mod tests {
                 to: State {
                     name: parse_quote! { Locked },
                 },
+                choice: false,
+                error: None,
             },
             Transition {
                 event: Event {
@@ -232,6 +1165,8 @@ mod tests {
                 to: State {
                     name: parse_quote! { Unlocked },
                 },
+                choice: false,
+                error: None,
             },
             Transition {
                 event: Event {
@@ -243,39 +1178,41 @@ mod tests {
                 to: State {
                     name: parse_quote! { Unlocked },
                 },
+                choice: false,
+                error: None,
             },
         ]);
 
         let left = quote! {
-            impl<E: Event> Transition<Push> for Machine<Locked, E> {
+            impl<E: ::sm::Event> ::sm::Transition<Push> for Machine<Locked, E> {
                 type Machine = Machine<Locked, Push>;
 
                 fn transition(self, event: Push) -> Self::Machine {
-                    Machine(Locked, Some(event))
+                    Machine(Locked, ::core::option::Option::Some(event))
                 }
             }
 
-            impl<E: Event> Transition<Push> for Machine<Unlocked, E> {
+            impl<E: ::sm::Event> ::sm::Transition<Push> for Machine<Unlocked, E> {
                 type Machine = Machine<Locked, Push>;
 
                 fn transition(self, event: Push) -> Self::Machine {
-                    Machine(Locked, Some(event))
+                    Machine(Locked, ::core::option::Option::Some(event))
                 }
             }
 
-            impl<E: Event> Transition<Coin> for Machine<Locked, E> {
+            impl<E: ::sm::Event> ::sm::Transition<Coin> for Machine<Locked, E> {
                 type Machine = Machine<Unlocked, Coin>;
 
                 fn transition(self, event: Coin) -> Self::Machine {
-                    Machine(Unlocked, Some(event))
+                    Machine(Unlocked, ::core::option::Option::Some(event))
                 }
             }
 
-            impl<E: Event> Transition<Coin> for Machine<Unlocked, E> {
+            impl<E: ::sm::Event> ::sm::Transition<Coin> for Machine<Unlocked, E> {
                 type Machine = Machine<Unlocked, Coin>;
 
                 fn transition(self, event: Coin) -> Self::Machine {
-                    Machine(Unlocked, Some(event))
+                    Machine(Unlocked, ::core::option::Option::Some(event))
                 }
             }
         };
@@ -0,0 +1,135 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream, Result},
+    Ident,
+};
+
+/// Visibility is the visibility declared in front of a machine or an event,
+/// such as `pub(crate)`, relative to the module containing the machine.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Visibility {
+    Inherited,
+    Public,
+    Restricted(Vec<Ident>),
+}
+
+impl Parse for Visibility {
+    /// example visibility tokens:
+    ///
+    /// ```text
+    /// pub(in crate::engine)
+    /// ```
+    fn parse(input: ParseStream<'_>) -> Result<Self> {
+        let visibility = match input.parse()? {
+            syn::Visibility::Inherited => Visibility::Inherited,
+            syn::Visibility::Public(_) => Visibility::Public,
+            syn::Visibility::Crate(_) => {
+                Visibility::Restricted(vec![Ident::new("crate", Span::call_site())])
+            }
+            syn::Visibility::Restricted(restricted) => Visibility::Restricted(
+                restricted
+                    .path
+                    .segments
+                    .into_iter()
+                    .map(|s| s.ident)
+                    .collect(),
+            ),
+        };
+
+        Ok(visibility)
+    }
+}
+
+impl ToTokens for Visibility {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Visibility::Inherited => {}
+            Visibility::Public => tokens.extend(quote! { pub }),
+            Visibility::Restricted(path) if path.len() == 1 => {
+                let scope = &path[0];
+
+                tokens.extend(quote! { pub(#scope) });
+            }
+            Visibility::Restricted(path) => tokens.extend(quote! { pub(in #(#path)::*) }),
+        }
+    }
+}
+
+impl Visibility {
+    /// nested returns the visibility, as seen from within the module of a
+    /// generated machine. Items without a declared visibility are public, as
+    /// they are limited by the visibility of the module.
+    pub(crate) fn nested(&self) -> Visibility {
+        match self {
+            Visibility::Inherited | Visibility::Public => Visibility::Public,
+            Visibility::Restricted(path) if path[0] == "crate" => self.clone(),
+            Visibility::Restricted(path) => {
+                let mut nested = vec![Ident::new("super", Span::call_site())];
+                nested.extend(path.iter().skip_while(|s| *s == "self").cloned());
+
+                Visibility::Restricted(nested)
+            }
+        }
+    }
+}
+
+/// of returns the visibility of the named item, as seen from within the module
+/// of a generated machine, given the declared visibilities of the items.
+pub(crate) fn of(visibilities: &[(Ident, Visibility)], name: &Ident) -> Visibility {
+    visibilities
+        .iter()
+        .find(|(item, _)| item == name)
+        .map_or(Visibility::Public, |(_, visibility)| visibility.nested())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn;
+
+    #[test]
+    fn test_visibility_parse() {
+        let visibility = |tokens: TokenStream| syn::parse2::<Visibility>(tokens).unwrap();
+
+        assert_eq!(visibility(quote! {}), Visibility::Inherited);
+        assert_eq!(visibility(quote! { pub }), Visibility::Public);
+        assert_eq!(
+            visibility(quote! { pub(crate) }),
+            Visibility::Restricted(vec![Ident::new("crate", Span::call_site())])
+        );
+        assert_eq!(
+            visibility(quote! { pub(in crate::engine) }),
+            Visibility::Restricted(vec![
+                Ident::new("crate", Span::call_site()),
+                Ident::new("engine", Span::call_site()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_visibility_nested() {
+        let cases = vec![
+            (quote! {}, quote! { pub }),
+            (quote! { pub }, quote! { pub }),
+            (quote! { pub(crate) }, quote! { pub(crate) }),
+            (quote! { pub(self) }, quote! { pub(super) }),
+            (quote! { pub(super) }, quote! { pub(in super::super) }),
+            (
+                quote! { pub(in crate::engine) },
+                quote! { pub(in crate::engine) },
+            ),
+            (
+                quote! { pub(in super::engine) },
+                quote! { pub(in super::super::engine) },
+            ),
+        ];
+
+        for (declared, nested) in cases {
+            let visibility: Visibility = syn::parse2(declared).unwrap();
+            let left = visibility.nested().into_token_stream();
+
+            assert_eq!(format!("{}", left), format!("{}", nested));
+        }
+    }
+}
@@ -0,0 +1,16 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Enemy {
+        InitialStates { Idle }
+
+        Hit { Idle => Stunned }
+
+        After(3s) { Stunned => Idle }
+        After(5s) { Stunned => Idle }
+        //~^ ERROR state `Stunned` has more than one timeout
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Enemy {
+        InitialStates { Idle }
+
+        Hit { Idle => Stunned }
+
+        After(3) { Stunned => Idle }
+        //~^ ERROR expected duration with a unit of `ms`, `s`, `m` or `h`, e.g. `3s`
+    }
+}
+
+fn main() {}
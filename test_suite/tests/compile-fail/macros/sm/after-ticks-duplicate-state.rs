@@ -0,0 +1,16 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Player {
+        InitialStates { Vulnerable }
+
+        Hit { Vulnerable => Invulnerable }
+
+        AfterTicks(3) { Invulnerable => Vulnerable }
+        AfterTicks(5) { Invulnerable => Vulnerable }
+        //~^ ERROR state `Invulnerable` has more than one tick timeout
+    }
+}
+
+fn main() {}
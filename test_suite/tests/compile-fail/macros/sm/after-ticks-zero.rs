@@ -0,0 +1,15 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Player {
+        InitialStates { Vulnerable }
+
+        Hit { Vulnerable => Invulnerable }
+
+        AfterTicks(0) { Invulnerable => Vulnerable }
+        //~^ ERROR expected a number of ticks between 1 and 4294967295
+    }
+}
+
+fn main() {}
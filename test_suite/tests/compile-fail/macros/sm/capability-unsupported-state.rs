@@ -0,0 +1,21 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    TurnStile {
+        InitialStates { Locked, Unlocked }
+
+        Push { Unlocked => Locked }
+    }
+}
+
+use crate::TurnStile::*;
+
+fn push<M: CanPush>(m: M) -> M::Next {
+    m.transition(Push)
+}
+
+fn main() {
+    let _ = push(Machine::new(Locked)); //~ ERROR the trait bound
+    //~^ ERROR has no transition for event `TurnStile::Push`
+}
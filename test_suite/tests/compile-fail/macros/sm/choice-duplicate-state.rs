@@ -0,0 +1,13 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Player {
+        InitialStates { Aiming }
+
+        Attack { Aiming => Hit | Miss | Hit }
+        //~^ ERROR choice for event `Attack` in state `Aiming` lists state `Hit` more than once
+    }
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Player {
+        InitialStates { Aiming }
+        FinalStates { Hit, Miss }
+
+        Attack { Aiming => Hit | Miss }
+        //~^ ERROR Choose<Player::Aiming>` is not satisfied
+    }
+}
+
+fn main() {
+    use Player::*;
+
+    let _ = Machine::new(Aiming).transition(Attack);
+}
@@ -6,10 +6,10 @@ sm!{
         InitialStates { Locked, Unlocked }
 
         TurnKey {
-        //~^ ERROR unexpected end of input, expected identifier
             Locked => Unlocked,
-            // TODO: support both with and without comma separation?
+            //~^ ERROR unexpected `,`, a `,` can only be used between two source states
             Unlocked => Locked,
+            //~^ ERROR unexpected `,`, a `,` can only be used between two source states
         }
     }
 }
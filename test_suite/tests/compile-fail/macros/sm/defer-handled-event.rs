@@ -0,0 +1,16 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Game {
+        InitialStates { Loading }
+
+        Loaded { Loading => Playing }
+        Fire { Loading, Playing => Playing }
+
+        Defer { Loading: Fire }
+        //~^ ERROR cannot defer event `Fire` in state `Loading`, which has a transition for it
+    }
+}
+
+fn main() {}
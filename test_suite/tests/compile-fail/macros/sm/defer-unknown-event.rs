@@ -0,0 +1,15 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Game {
+        InitialStates { Loading }
+
+        Loaded { Loading => Playing }
+
+        Defer { Loading: Fire }
+        //~^ ERROR cannot defer unknown event `Fire`
+    }
+}
+
+fn main() {}
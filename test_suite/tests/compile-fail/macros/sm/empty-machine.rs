@@ -3,7 +3,7 @@ use sm::sm;
 
 sm!{
     TurnStile {}
-    //~^ ERROR unexpected end of input, expected identifier
+    //~^ ERROR expected `InitialStates { ... }` block
 }
 
 fn main() {}
@@ -0,0 +1,13 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Player {
+        InitialStates { Aiming }
+
+        Attack { Aiming => Hit | Miss ? Jammed }
+        //~^ ERROR choice transitions cannot be fallible
+    }
+}
+
+fn main() {}
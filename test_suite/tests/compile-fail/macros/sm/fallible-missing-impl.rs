@@ -0,0 +1,13 @@
+extern crate sm;
+use sm::sm;
+
+sm! { //~ ERROR the trait bound `Connect: Fallible<Session::Idle>` is not satisfied
+    Session {
+        InitialStates { Idle }
+
+        Connect { Idle => Connected ? Failed }
+        //~^ ERROR the trait bound `Connect: Fallible<Session::Idle>` is not satisfied
+    }
+}
+
+fn main() {}
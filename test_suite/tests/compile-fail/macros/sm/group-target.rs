@@ -0,0 +1,15 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Enemy {
+        InitialStates { Idle }
+
+        Groups { Combat: Attacking, Blocking }
+
+        Spot { Idle => Combat } //~ ERROR group `Combat` cannot be the target of a transition
+        Block { Attacking => Blocking }
+    }
+}
+
+fn main() {}
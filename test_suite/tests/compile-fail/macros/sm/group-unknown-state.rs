@@ -0,0 +1,14 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Enemy {
+        InitialStates { Idle }
+
+        Groups { Combat: Attacking, Parrying } //~ ERROR unknown state `Parrying` in group `Combat`
+
+        Spot { Idle => Attacking }
+    }
+}
+
+fn main() {}
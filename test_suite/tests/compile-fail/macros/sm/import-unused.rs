@@ -0,0 +1,18 @@
+extern crate sm;
+use sm::sm;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Jump;
+impl sm::Event for Jump {}
+
+sm! {
+    Player {
+        use Jump; //~ ERROR imported type `Jump` is not used as a state or event
+
+        InitialStates { Standing }
+
+        Crouch { Standing => Crouching }
+    }
+}
+
+fn main() {}
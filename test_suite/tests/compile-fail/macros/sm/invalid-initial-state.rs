@@ -13,6 +13,5 @@ fn main() {
     use Lock::*;
 
     let _sm = Machine::new(Unlocked);
-    //~^ ERROR the trait bound `Lock::Unlocked: sm::InitialState` is not satisfied
-    //~| ERROR the trait bound `Lock::Unlocked: sm::InitialState` is not satisfied
+    //~^ ERROR `Lock::Unlocked` is not an initial state of `Lock`
 }
@@ -3,16 +3,18 @@ use sm::sm;
 
 sm!{
     Lock {
-        InitialStates { Locked, Unlocked }
+        InitialStates { Locked, Broken }
 
         TurnKey { Locked => Unlocked }
+        Repair { Broken => Locked }
+        Replace { Broken => Unlocked }
     }
 }
 
 fn main() {
     use Lock::*;
 
-    let sm = Machine::new(Unlocked);
-    sm = sm.transition(TurnKey);
-    //~^ ERROR no method named `transition` found for type `Lock::Machine<Lock::Unlocked, sm::NoneEvent>` in the current scope
+    let sm = Machine::new(Broken);
+    sm.transition(TurnKey);
+    //~^ ERROR `Broken` has no transition for event `Lock::TurnKey`; valid events are: `Repair`, `Replace`
 }
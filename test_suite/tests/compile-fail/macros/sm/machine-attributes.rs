@@ -0,0 +1,23 @@
+extern crate sm;
+
+/// A lock that cannot be unlocked once locked.
+#[sm::machine]
+#[deny(deprecated)]
+mod Lock {
+    enum States {
+        #[initial]
+        Unlocked,
+        Locked,
+    }
+
+    enum Events {
+        TurnKey,
+    }
+
+    transitions! {
+        TurnKey { Unlocked => Locked }
+        //~^ ERROR state `Locked` has no outgoing transitions, add `FinalStates { Locked }` if this is intended
+    }
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+extern crate sm;
+
+#[sm::machine]
+mod Lock {
+    enum States {
+        Locked,
+        Unlocked,
+    }
+
+    enum Events {
+        TurnKey,
+    }
+
+    transitions! { //~ ERROR expected at least one state marked as `#[initial]`
+        TurnKey { Locked => Unlocked }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+extern crate sm;
+
+#[sm::machine]
+mod Lock {
+    enum States {
+        #[initial]
+        Locked,
+        Unlocked,
+    }
+
+    enum Events {
+        TurnKey,
+    }
+
+    transitions! {
+        TurnKey { Locked => Unlocked }
+        Push { Unlocked => Locked } //~ ERROR event `Push` is not declared in `enum Events`
+    }
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Lock {
+        TurnKey {
+            Locked => Unlocked
+            Unlocked => Locked
+        }
+
+        InitialStates { Locked }
+        //~^ ERROR `InitialStates { ... }` has to be the first block of the machine, after any `use` declarations
+    }
+}
+
+fn main() {}
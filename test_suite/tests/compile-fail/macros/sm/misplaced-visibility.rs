@@ -0,0 +1,13 @@
+extern crate sm;
+use sm::sm;
+
+sm!{
+    Lock {
+        pub(crate) InitialStates { Locked }
+        //~^ ERROR `InitialStates` cannot have a visibility, only machines and events can
+
+        TurnKey { Locked => Unlocked  Unlocked => Locked }
+    }
+}
+
+fn main() {}
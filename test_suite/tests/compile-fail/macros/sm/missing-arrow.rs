@@ -0,0 +1,15 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Lock {
+        InitialStates { Locked }
+
+        TurnKey {
+            Locked Unlocked
+            //~^ ERROR expected `=>` after `Unlocked`, did you mean `Locked => Unlocked`?
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Lock {
+        InitialStates { Locked }
+
+        TurnKey {
+            Locked => Unlocked,,
+            //~^ ERROR unexpected `,`, a `,` can only be used between two source states
+            //~| ERROR unexpected `,`, a `,` can only be used between two source states
+            Unlocked => Locked
+        }
+
+        Break { Locked, Unlocked }
+        //~^ ERROR expected `=>` after `Unlocked`, did you mean `Locked => Unlocked`?
+
+        Repair { Broken => }
+        //~^ ERROR expected a target state after `=>`
+    }
+
+    TurnStile {
+        Push { Locked => Unlocked }
+        //~^ ERROR expected `InitialStates { ... }` block
+
+        Coin { Unlocked => Locked ? }
+        //~^ ERROR expected an error state after `?`
+    }
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Lock {
+        InitialStates { Locked }
+
+        TurnKey { Locked => Machine }
+        //~^ ERROR state `Machine` has the same name as the generated `Machine` type, which can be renamed using `Names { Machine as ... }`
+        Close { Machine => Locked }
+    }
+
+    Door {
+        InitialStates { A }
+
+        C { A => AByB  AByB => A }
+        BByC { A => A }
+        //~^ ERROR state `AByB` entered by event `C` and state `A` entered by event `BByC` both result in the `Variant::AByBByC` variant, rename one of them
+    }
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Lock {
+        InitialStates { Locked }
+        FinalStates { Broken }
+
+        TurnKey { Locked => Unlocked  Unlocked => Locked }
+        Break { Locked => Broken }
+        //~^ ERROR nondeterministic transition, event `Break` in state `Locked` leads to both `Broken` and `Unlocked`
+        Break { Locked => Unlocked }
+        //~^ ERROR `Locked` already transitions to `Broken` on `Break`
+    }
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+extern crate sm;
+
+mod engine {
+    use sm::sm;
+
+    sm!{
+        pub Lock {
+            InitialStates { Locked }
+            FinalStates { Locked }
+
+            TurnKey { Locked => Unlocked }
+            pub(self) Reset { Unlocked => Locked }
+        }
+
+        Door {
+            InitialStates { Closed }
+            FinalStates { Opened }
+
+            Open { Closed => Opened }
+        }
+    }
+}
+
+use engine::Door::Machine as Door;
+//~^ ERROR module `Door` is private
+
+fn main() {
+    use engine::Lock::*;
+
+    let _sm = Machine::new(Locked).turn_key().transition(engine::Lock::Reset);
+    //~^ ERROR unit struct `Reset` is private
+}
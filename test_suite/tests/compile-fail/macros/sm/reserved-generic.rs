@@ -0,0 +1,13 @@
+extern crate sm;
+use sm::sm;
+
+sm!{
+    Slot<S> {
+        //~^ ERROR generic parameter `S` is used by the generated code
+        InitialStates { Empty }
+
+        Put { Empty => Holding }
+    }
+}
+
+fn main() {}
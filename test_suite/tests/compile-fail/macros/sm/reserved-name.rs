@@ -0,0 +1,16 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Lock {
+        InitialStates { Locked }
+
+        TurnKey { Locked => Groups }
+        //~^ ERROR `Groups` is reserved for the `Groups { ... }` block, and cannot be used as a state
+
+        After { Groups => Locked }
+        //~^ ERROR `After` is reserved for the `After(...) { ... }` block, and cannot be used as an event
+    }
+}
+
+fn main() {}
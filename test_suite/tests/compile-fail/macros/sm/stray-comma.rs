@@ -0,0 +1,17 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Lock {
+        InitialStates { Locked }
+
+        TurnKey {
+            Locked, => Unlocked
+            //~^ ERROR unexpected `,`, a `,` can only be used between two source states
+            Unlocked =>, Locked
+            //~^ ERROR expected a target state after `=>`
+        }
+    }
+}
+
+fn main() {}
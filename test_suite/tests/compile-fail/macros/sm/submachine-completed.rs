@@ -0,0 +1,26 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Reload {
+        InitialStates { Ejecting }
+        FinalStates { Loaded }
+
+        Insert { Ejecting => Loaded }
+    }
+
+    Weapon {
+        InitialStates { Idle }
+
+        Empty { Idle => Reloading }
+
+        Submachine(Reload: Ejecting) { Reloading => Idle }
+    }
+}
+
+fn main() {
+    use Weapon::*;
+
+    Machine::new(Idle).transition(Empty).transition(Completed);
+    //~^ ERROR `Reloading` has no transition for event `Weapon::Completed`, or any other event
+}
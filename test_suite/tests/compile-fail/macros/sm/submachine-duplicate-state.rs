@@ -0,0 +1,20 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Reload {
+        InitialStates { Ejecting }
+
+        Insert { Ejecting => Loaded }
+    }
+
+    Weapon {
+        InitialStates { Reloading }
+
+        Submachine(Reload: Ejecting) { Reloading => Idle }
+        Submachine(Reload: Ejecting) { Reloading => Idle }
+        //~^ ERROR state `Reloading` has more than one submachine
+    }
+}
+
+fn main() {}
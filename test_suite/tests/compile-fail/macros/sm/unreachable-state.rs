@@ -0,0 +1,14 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Lock {
+        InitialStates { Locked }
+        FinalStates { Broken }
+
+        TurnKey { Locked => Unlocked  Unlocked => Locked }
+        Break { Locked, Brokne => Broken } //~ ERROR state `Brokne` is not reachable from any of the initial states
+    }
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+extern crate sm;
+use sm::sm;
+
+sm!{
+    TurnStile {
+        InitialStates { Unlocked }
+        FinalStates { Locked }
+
+        Push { Unlocked => Locked }
+    }
+
+    Lock {
+        InitialStates { Locked }
+        FinalStates { Unlocked }
+
+        TurnKey { Locked => Unlocked }
+    }
+}
+
+fn main() {
+    let _sm = Lock::Machine::new(TurnStile::Unlocked);
+    //~^ ERROR `TurnStile::Unlocked` is not an initial state of `Lock`
+}
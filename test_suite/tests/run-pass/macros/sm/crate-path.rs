@@ -0,0 +1,16 @@
+mod engine {
+    pub extern crate sm;
+}
+
+engine::sm::sm!{
+    crate = crate::engine::sm;
+
+    TurnStile {
+        InitialStates { Locked, Unlocked }
+
+        Coin { Locked => Unlocked }
+        Push { Unlocked => Locked }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Lock {
+        InitialStates { Locked }
+
+        TurnKey { Locked => Unlocked  Unlocked => Locked }
+        TurnKey { Locked => Unlocked }
+    }
+}
+
+fn main() {
+    use Lock::*;
+
+    let sm = Machine::new(Locked).transition(TurnKey);
+    assert_eq!(sm.state(), Unlocked);
+}
@@ -0,0 +1,33 @@
+extern crate sm;
+use sm::sm;
+
+sm!{
+    TurnStile {
+        InitialStates { Locked, Unlocked }
+
+        Coin { Locked => Unlocked }
+        Push { Unlocked => Locked }
+    }
+}
+
+sm!{
+    Lock {
+        InitialStates { Locked, Unlocked }
+
+        TurnKey {
+            Locked => Unlocked
+            Unlocked => Locked
+        }
+    }
+}
+
+fn main() {
+    sm!{
+        Door {
+            InitialStates { Closed }
+
+            Open { Closed => Opened }
+            Close { Opened => Closed }
+        }
+    }
+}
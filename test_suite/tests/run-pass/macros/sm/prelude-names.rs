@@ -0,0 +1,23 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Lock {
+        InitialStates { Locked, None }
+        FinalStates { Ok, Err }
+
+        TurnKey { Locked => Ok }
+        Reset { None => Locked }
+        Some { Locked => Err }
+    }
+}
+
+fn main() {
+    use Lock::*;
+
+    let sm = Machine::new(None).transition(Reset).transition(TurnKey);
+    assert_eq!(sm.state(), Ok);
+
+    let sm = Machine::new(Locked).some();
+    assert!(sm.as_enum().is(&Err));
+}
@@ -0,0 +1,85 @@
+extern crate sm;
+use futures_executor::block_on;
+use futures_util::{stream, StreamExt};
+use sm::{driver::drive, sm, AsyncTransition};
+use std::{cell::RefCell, rc::Rc};
+
+sm! {
+    Session {
+        InitialStates { Idle }
+
+        Connect { Idle => Connected }
+        Send { Connected => Connected }
+        Disconnect { Connected => Idle }
+    }
+}
+
+use crate::Session::*;
+
+async fn log(log: Rc<RefCell<Vec<String>>>, entry: String) {
+    log.borrow_mut().push(entry);
+}
+
+#[test]
+fn test_async_transition() {
+    let entries = Rc::new(RefCell::new(vec![]));
+
+    let sm = block_on(Machine::new(Idle).transition_async(Connect, |m| {
+        log(entries.clone(), format!("{:?}", m.state()))
+    }));
+
+    assert_eq!(sm.state(), Connected);
+    assert_eq!(*entries.borrow(), vec!["Connected"]);
+}
+
+#[test]
+fn test_drive() {
+    let entries = Rc::new(RefCell::new(vec![]));
+    let events = stream::iter(vec![
+        Connect.into(),
+        Send.into(),
+        Send.into(),
+        Disconnect.into(),
+    ]);
+
+    let variants: Vec<_> = block_on(
+        drive(Machine::new(Idle).as_enum(), events, |variant| {
+            log(entries.clone(), format!("{:?}", variant))
+        })
+        .collect(),
+    );
+
+    assert_eq!(variants.len(), 4);
+    assert_eq!(entries.borrow().len(), 4);
+
+    match variants.last() {
+        Some(Ok(Variant::IdleByDisconnect(m))) => assert_eq!(m.state(), Idle),
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+}
+
+#[test]
+fn test_drive_unhandled_event() {
+    let entries = Rc::new(RefCell::new(vec![]));
+    let events = stream::iter(vec![Send.into(), Connect.into()]);
+
+    let mut variants = Box::pin(drive(Machine::new(Idle).as_enum(), events, |variant| {
+        log(entries.clone(), format!("{:?}", variant))
+    }));
+
+    block_on(async {
+        match variants.next().await {
+            Some(Err(Events::Send(_))) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        match variants.next().await {
+            Some(Ok(Variant::ConnectedByConnect(_))) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        assert!(variants.next().await.is_none());
+    });
+
+    assert_eq!(entries.borrow().len(), 1);
+}
@@ -0,0 +1,69 @@
+extern crate sm;
+use sm::Dispatch;
+
+/// A lock, declared as a module.
+#[sm::machine]
+mod Lock {
+    use crate::Break;
+
+    enum States {
+        #[initial]
+        Locked,
+        #[initial]
+        Unlocked,
+        Broken,
+    }
+
+    enum Events {
+        TurnKey,
+        Break,
+    }
+
+    transitions! {
+        TurnKey {
+            Locked => Unlocked
+            Unlocked => Locked
+        }
+
+        Break {
+            Locked, Unlocked => Broken
+        }
+
+        After(1s) {
+            Broken => Locked
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Break;
+impl sm::Event for Break {}
+
+#[test]
+fn test_attribute_transition() {
+    use crate::Lock::*;
+
+    let sm = Machine::new(Locked);
+    assert_eq!(sm.state(), Locked);
+
+    let sm = sm.transition(TurnKey);
+    assert_eq!(sm.state(), Unlocked);
+    assert_eq!(sm.trigger().unwrap(), TurnKey);
+
+    let sm = sm.transition(Break);
+    assert_eq!(sm.state(), Broken);
+}
+
+#[test]
+fn test_attribute_dispatch() {
+    use crate::Lock::*;
+
+    let sm = Machine::new(Unlocked).as_enum();
+    let sm = sm.dispatch(Break.into()).unwrap();
+    assert!(sm.is(&Broken));
+
+    match sm.timeout() {
+        Some((_, event)) => assert_eq!(event, Events::Timeout(Timeout)),
+        None => panic!("expected timeout"),
+    }
+}
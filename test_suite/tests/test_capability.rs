@@ -0,0 +1,42 @@
+extern crate sm;
+use sm::{sm, Transition};
+
+sm! {
+    TurnStile {
+        InitialStates { Locked, Unlocked }
+
+        Coin { Locked, Unlocked => Unlocked }
+        Push { Unlocked => Locked }
+    }
+}
+
+use crate::TurnStile::*;
+
+fn insert_coin<M: CanCoin>(m: M) -> M::Next {
+    m.transition(Coin)
+}
+
+fn push_twice<M>(m: M) -> <<M as CanPush>::Next as CanCoin>::Next
+where
+    M: CanPush,
+    M::Next: CanCoin,
+{
+    m.transition(Push).transition(Coin)
+}
+
+#[test]
+fn test_capability() {
+    let sm = insert_coin(Machine::new(Locked));
+    assert_eq!(sm.state(), Unlocked);
+
+    let sm = insert_coin(Machine::new(Unlocked));
+    assert_eq!(sm.state(), Unlocked);
+    assert_eq!(sm.trigger().unwrap(), Coin);
+}
+
+#[test]
+fn test_capability_chain() {
+    let sm = push_twice(Machine::new(Unlocked));
+    assert_eq!(sm.state(), Unlocked);
+    assert_eq!(sm.trigger().unwrap(), Coin);
+}
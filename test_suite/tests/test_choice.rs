@@ -0,0 +1,88 @@
+extern crate sm;
+use sm::{sm, Choose, Dispatch};
+use std::cell::Cell;
+
+sm! {
+    Player {
+        InitialStates { Idle, Aiming }
+
+        Aim { Idle => Aiming }
+        Attack { Aiming => Hit | Miss | Critical }
+        Retry { Miss => Aiming }
+    }
+}
+
+use crate::Player::*;
+
+thread_local! {
+    static ROLL: Cell<u8> = Cell::new(10);
+}
+
+impl Choose<Aiming> for Attack {
+    type Branch = AttackFromAimingBranch;
+
+    fn choose(&self, _: &Aiming) -> Self::Branch {
+        match ROLL.with(Cell::get) {
+            0..=4 => AttackFromAimingBranch::Miss,
+            5..=18 => AttackFromAimingBranch::Hit,
+            _ => AttackFromAimingBranch::Critical,
+        }
+    }
+}
+
+fn attack(roll: u8) -> AttackFromAiming {
+    ROLL.with(|r| r.set(roll));
+
+    Machine::new(Idle).transition(Aim).transition(Attack)
+}
+
+#[test]
+fn test_choice() {
+    match attack(10) {
+        AttackFromAiming::Hit(m) => {
+            assert_eq!(m.state(), Hit);
+            assert_eq!(m.trigger().unwrap(), Attack);
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    match attack(20) {
+        AttackFromAiming::Critical(m) => assert_eq!(m.state(), Critical),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_choice_continue() {
+    let sm = match attack(1) {
+        AttackFromAiming::Miss(m) => m.transition(Retry),
+        result => panic!("unexpected result: {:?}", result),
+    };
+
+    assert_eq!(sm.state(), Aiming);
+}
+
+#[test]
+fn test_choice_as_enum() {
+    match attack(10).as_enum() {
+        Variant::HitByAttack(m) => assert_eq!(m.state(), Hit),
+        _ => panic!("unexpected variant"),
+    }
+
+    match attack(1).as_enum() {
+        Variant::MissByAttack(m) => assert_eq!(m.state(), Miss),
+        _ => panic!("unexpected variant"),
+    }
+}
+
+#[test]
+fn test_choice_dispatch() {
+    ROLL.with(|r| r.set(1));
+
+    let variant = Machine::new(Aiming).as_enum();
+
+    match variant.dispatch(Attack.into()) {
+        Ok(Variant::MissByAttack(m)) => assert_eq!(m.state(), Miss),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
@@ -0,0 +1,217 @@
+extern crate sm;
+
+use sm::{sm, ChooseWith, Dispatch, EnterWith, Fallible, FallibleWith, State};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Stats {
+    id: u32,
+    coins: u32,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unlocked(u32);
+impl State for Unlocked {}
+
+impl EnterWith<TurnStile::Locked, TurnStile::Coin, Stats> for Unlocked {
+    fn enter_with(_: &TurnStile::Locked, _: &TurnStile::Coin, stats: &mut Stats) -> Self {
+        stats.coins += 1;
+        Unlocked(stats.coins)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Counting;
+impl State for Counting {}
+
+impl EnterWith<Meter::Idle, Meter::Start, Stats> for Counting {
+    fn enter_with(_: &Meter::Idle, _: &Meter::Start, stats: &mut Stats) -> Self {
+        stats.coins += 1;
+        Counting::default()
+    }
+}
+
+impl FallibleWith<Unlocked, Stats> for TurnStile::Push {
+    type Error = u32;
+
+    fn try_transition_with(&self, _: &Unlocked, stats: &Stats) -> Result<(), Self::Error> {
+        match stats.coins {
+            coins if coins > 2 => Err(coins),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ChooseWith<Timer::Running, Stats> for Timer::Roll {
+    type Branch = Timer::RollFromRunningBranch;
+
+    fn choose_with(&self, _: &Timer::Running, stats: &Stats) -> Self::Branch {
+        match stats.id % 2 {
+            0 => Timer::RollFromRunningBranch::Heads,
+            _ => Timer::RollFromRunningBranch::Tails,
+        }
+    }
+}
+
+impl Fallible<TurnStile::Locked> for TurnStile::Kick {
+    type Error = ();
+
+    fn try_transition(&self, _: &TurnStile::Locked) -> Result<(), Self::Error> {
+        Err(())
+    }
+}
+
+sm! {
+    TurnStile {
+        use crate::Unlocked;
+
+        InitialStates { Locked }
+        FinalStates { Jammed, Broken }
+        Context { crate::Stats }
+
+        Coin { Locked => Unlocked }
+        Push { Unlocked => Locked ? Jammed }
+        Kick { Locked => Locked ? Broken }
+        Handler
+    }
+
+    Timer {
+        InitialStates { Idle }
+        FinalStates { Done }
+        Context { crate::Stats }
+
+        Start { Idle => Running }
+        Roll { Running => Heads | Tails }
+        AfterTicks(2) { Heads, Tails => Done }
+    }
+
+    Meter {
+        use crate::Counting;
+
+        InitialStates { Idle }
+        Context { crate::Stats }
+
+        Start { Idle => Counting }
+        Stop { Counting => Idle }
+    }
+}
+
+#[test]
+fn test_context_transition() {
+    use TurnStile::*;
+
+    let sm = Machine::new(Locked, Stats { id: 7, coins: 0 });
+    assert_eq!(sm.context(), &Stats { id: 7, coins: 0 });
+
+    let sm = sm.coin();
+    assert_eq!(sm.state(), Unlocked(1));
+    assert_eq!(sm.context().coins, 1);
+
+    let mut sm = sm.push().unwrap();
+    sm.context_mut().coins = 2;
+
+    let sm = sm.coin();
+    assert_eq!(sm.state(), Unlocked(3));
+
+    let (sm, coins) = sm.push().unwrap_err();
+    assert_eq!(sm.state(), Jammed);
+    assert_eq!(coins, 3);
+    assert_eq!(sm.context(), &Stats { id: 7, coins: 3 });
+}
+
+#[test]
+fn test_context_fallible() {
+    use TurnStile::*;
+
+    let (sm, ()) = Machine::new(Locked, Stats { id: 1, coins: 0 })
+        .kick()
+        .unwrap_err();
+    assert_eq!(sm.state(), Broken);
+    assert_eq!(sm.context().id, 1);
+}
+
+#[test]
+fn test_context_dispatch() {
+    use TurnStile::*;
+
+    let sm = Machine::new(Locked, Stats { id: 2, coins: 0 }).as_enum();
+    let sm = sm.dispatch(Events::Coin(Coin)).unwrap();
+    let sm = sm.dispatch(Events::Push(Push)).unwrap();
+    let sm = sm.dispatch(Events::Coin(Coin)).unwrap();
+
+    match sm {
+        Variant::UnlockedByCoin(m) => assert_eq!(m.context(), &Stats { id: 2, coins: 2 }),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_context_handler() {
+    use TurnStile::*;
+
+    struct Counter(u32);
+
+    impl TurnStileHandler for Counter {
+        fn on_initial_locked(&mut self, m: Machine<Locked, sm::NoneEvent>) -> Variant {
+            m.coin().as_enum()
+        }
+
+        fn on_unlocked_by_coin(&mut self, m: Machine<Unlocked, Coin>) -> Variant {
+            self.0 = m.context().coins;
+
+            match m.push() {
+                Ok(m) => m.as_enum(),
+                Err((m, _)) => m.as_enum(),
+            }
+        }
+
+        fn on_locked_by_push(&mut self, m: Machine<Locked, Push>) -> Variant {
+            m.coin().as_enum()
+        }
+
+        fn on_locked_by_kick(&mut self, m: Machine<Locked, Kick>) -> Variant {
+            m.coin().as_enum()
+        }
+    }
+
+    let mut counter = Counter(0);
+    let sm = Machine::new(Locked, Stats { id: 3, coins: 0 })
+        .as_enum()
+        .run(&mut counter);
+
+    assert!(sm.is(&Jammed));
+    assert_eq!(counter.0, 3);
+}
+
+#[test]
+fn test_context_choice_ticks() {
+    use Timer::*;
+
+    let sm = Machine::new(Idle, Stats { id: 4, coins: 0 }).start();
+
+    match sm.roll() {
+        RollFromRunning::Heads(mut m) => {
+            assert_eq!(m.remaining_ticks(), Some(2));
+            m.context_mut().coins = 5;
+
+            match m.as_enum().tick().tick() {
+                Variant::DoneByTickTimeout(m) => {
+                    assert_eq!(m.context(), &Stats { id: 4, coins: 5 })
+                }
+                _ => unreachable!(),
+            }
+        }
+        RollFromRunning::Tails(_) => unreachable!(),
+    }
+}
+
+#[test]
+fn test_context_default_state() {
+    use Meter::*;
+
+    let sm = Machine::new(Idle, Stats { id: 6, coins: 0 }).start();
+    assert_eq!(sm.state(), Counting);
+    assert_eq!(sm.context().coins, 1);
+
+    let sm = sm.stop().start();
+    assert_eq!(sm.context(), &Stats { id: 6, coins: 2 });
+}
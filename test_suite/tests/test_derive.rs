@@ -0,0 +1,96 @@
+extern crate sm;
+use sm::{Enter, Event, InitialState, Initializer, Machine, NoneEvent, State, Transition};
+
+#[derive(Debug, Eq, PartialEq, Machine)]
+pub struct Lock<S: State, E: Event>(S, Option<E>);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, State, InitialState)]
+pub struct Locked;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, State)]
+pub struct Unlocked;
+
+#[derive(Clone, Debug, Eq, PartialEq, Event)]
+pub struct TurnKey(u8);
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, State, Enter)]
+pub struct Open;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, State)]
+pub struct Turns(u8);
+
+impl Enter<Locked, TurnKey> for Turns {
+    fn enter(_: &Locked, event: &TurnKey) -> Self {
+        Turns(event.0)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, State, InitialState)]
+pub struct Position<T: Clone + Eq + std::fmt::Debug>(T);
+
+impl<E: Event> Transition<TurnKey> for Lock<Locked, E> {
+    type Machine = Lock<Unlocked, TurnKey>;
+
+    fn transition(self, event: TurnKey) -> Self::Machine {
+        Lock(Unlocked, Some(event))
+    }
+}
+
+#[test]
+fn test_derive_machine() {
+    let sm = Lock::new(Locked);
+    assert_eq!(sm.state(), Locked);
+    assert_eq!(sm.trigger(), None::<NoneEvent>);
+
+    let sm = sm.transition(TurnKey(1));
+    assert_eq!(sm.state(), Unlocked);
+    assert_eq!(sm.trigger(), Some(TurnKey(1)));
+}
+
+#[test]
+fn test_derive_generic_state() {
+    let sm = Lock::new(Position(3));
+    assert_eq!(sm.state(), Position(3));
+}
+
+#[test]
+fn test_derive_enter() {
+    assert_eq!(Open::enter(&Locked, &TurnKey(1)), Open);
+    assert_eq!(Open::enter(&Unlocked, &TurnKey(2)), Open);
+    assert_eq!(Turns::enter(&Locked, &TurnKey(3)), Turns(3));
+}
+
+/// A facade crate re-exporting `sm`, as seen from the derives below.
+mod engine {
+    pub extern crate sm;
+}
+
+#[derive(Debug, Eq, PartialEq, engine::sm::Machine)]
+#[sm(crate = crate::engine::sm)]
+pub struct Door<S: State, E: Event>(S, Option<E>);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, engine::sm::State, engine::sm::InitialState)]
+#[sm(crate = crate::engine::sm)]
+pub struct Closed;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, engine::sm::Event)]
+#[sm(crate = crate::engine::sm)]
+pub struct Push;
+
+impl<E: Event> Transition<Push> for Door<Closed, E> {
+    type Machine = Door<Closed, Push>;
+
+    fn transition(self, event: Push) -> Self::Machine {
+        Door(Closed, Some(event))
+    }
+}
+
+#[test]
+fn test_derive_crate_path() {
+    let sm = Door::new(Closed);
+    assert_eq!(sm.trigger(), None::<NoneEvent>);
+
+    let sm = sm.transition(Push);
+    assert_eq!(sm.state(), Closed);
+    assert_eq!(sm.trigger(), Some(Push));
+}
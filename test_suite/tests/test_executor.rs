@@ -0,0 +1,168 @@
+extern crate sm;
+use sm::{
+    executor::{Executor, Queue},
+    sm,
+};
+use std::collections::VecDeque;
+
+sm! {
+    Door {
+        InitialStates { Closed }
+
+        Open { Closed => Opened }
+        Close { Opened => Closed }
+        Lock { Closed => Locked }
+    }
+
+    Game {
+        InitialStates { Loading }
+
+        Loaded { Loading => Playing }
+        Pause { Playing => Paused }
+        Resume { Paused => Playing }
+        Fire { Playing => Playing }
+        Jump { Playing => Playing }
+
+        Defer {
+            Loading: Fire, Jump
+            Paused: Fire
+        }
+    }
+}
+
+use crate::Door::*;
+
+#[test]
+fn test_executor_processes_queued_events() {
+    let mut executor: Executor<_, VecDeque<_>> = Executor::new(Machine::new(Closed).as_enum());
+
+    executor.push(Open).unwrap();
+    executor.push(Close).unwrap();
+    executor.push(Lock).unwrap();
+    executor.run(|_, _| {}).unwrap();
+
+    match executor.into_inner() {
+        Variant::LockedByLock(m) => assert_eq!(m.state(), Locked),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_executor_run_to_completion() {
+    let mut executor: Executor<_, VecDeque<_>> = Executor::new(Machine::new(Closed).as_enum());
+    let mut seen = Vec::new();
+
+    executor.push(Open).unwrap();
+    executor.push(Close).unwrap();
+    executor
+        .run(|machine, queue| {
+            seen.push(format!("{:?}", machine));
+
+            // Raised events are queued behind the already queued `Close`.
+            if let Variant::OpenedByOpen(_) = machine {
+                queue.push(Lock.into()).unwrap();
+            }
+        })
+        .unwrap();
+
+    assert_eq!(seen.len(), 3);
+    assert!(seen[0].starts_with("OpenedByOpen"));
+    assert!(seen[1].starts_with("ClosedByClose"));
+    assert!(seen[2].starts_with("LockedByLock"));
+}
+
+#[test]
+fn test_executor_reports_unhandled_events() {
+    let mut executor: Executor<_, VecDeque<_>> = Executor::new(Machine::new(Closed).as_enum());
+
+    executor.push(Close).unwrap();
+    executor.push(Open).unwrap();
+
+    assert_eq!(executor.run(|_, _| {}), Err(Events::Close(Close)));
+    match executor.machine() {
+        Variant::InitialClosed(_) => {}
+        _ => unreachable!(),
+    }
+
+    assert_eq!(executor.step(), Ok(true));
+    assert_eq!(executor.step(), Ok(false));
+    match executor.machine() {
+        Variant::OpenedByOpen(_) => {}
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_executor_heapless_queue() {
+    let mut executor: Executor<_, heapless::Deque<_, 2>> =
+        Executor::new(Machine::new(Closed).as_enum());
+
+    executor.push(Open).unwrap();
+    executor.push(Close).unwrap();
+    assert_eq!(executor.push(Lock), Err(Events::Lock(Lock)));
+
+    executor.run(|_, _| {}).unwrap();
+    match executor.machine() {
+        Variant::ClosedByClose(_) => {}
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_executor_replays_deferred_events() {
+    use crate::Game::*;
+
+    let mut executor: Executor<_, VecDeque<_>> = Executor::new(Machine::new(Loading).as_enum());
+    let mut seen = Vec::new();
+
+    executor.push(Fire).unwrap();
+    executor.push(Jump).unwrap();
+    executor.push(Loaded).unwrap();
+    executor.push(Pause).unwrap();
+    executor.push(Fire).unwrap();
+    executor.push(Resume).unwrap();
+
+    executor
+        .run(|machine, _| seen.push(format!("{:?}", machine)))
+        .unwrap();
+
+    let seen: Vec<_> = seen
+        .iter()
+        .map(|s| s.split('(').next().unwrap())
+        .collect();
+
+    assert_eq!(
+        seen,
+        vec![
+            "PlayingByLoaded",
+            "PlayingByFire",
+            "PlayingByJump",
+            "PausedByPause",
+            "PlayingByResume",
+            "PlayingByFire",
+        ]
+    );
+    assert_eq!(executor.deferred_len(), 0);
+}
+
+#[test]
+fn test_executor_keeps_deferred_events() {
+    use crate::Game::*;
+
+    let mut executor: Executor<_, VecDeque<_>> = Executor::new(Machine::new(Loading).as_enum());
+
+    executor.push(Fire).unwrap();
+    executor.push(Pause).unwrap();
+
+    assert_eq!(executor.run(|_, _| {}), Err(Game::Events::Pause(Pause)));
+    assert_eq!(executor.deferred_len(), 1);
+
+    executor.push(Loaded).unwrap();
+    executor.run(|_, _| {}).unwrap();
+
+    assert_eq!(executor.deferred_len(), 0);
+    match executor.machine() {
+        Game::Variant::PlayingByFire(_) => {}
+        _ => unreachable!(),
+    }
+}
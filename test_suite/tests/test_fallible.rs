@@ -0,0 +1,116 @@
+extern crate sm;
+use sm::{sm, Dispatch, Fallible, TryTransition};
+use std::cell::Cell;
+
+sm! {
+    Session {
+        InitialStates { Idle }
+
+        Connect {
+            Idle, Failed => Connected ? Failed
+        }
+
+        Disconnect {
+            Connected => Idle
+        }
+    }
+}
+
+use crate::Session::*;
+
+thread_local! {
+    static REFUSE: Cell<bool> = Cell::new(false);
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConnectionRefused;
+
+impl Fallible<Idle> for Connect {
+    type Error = ConnectionRefused;
+
+    fn try_transition(&self, _: &Idle) -> Result<(), Self::Error> {
+        if REFUSE.with(Cell::get) {
+            Err(ConnectionRefused)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Fallible<Failed> for Connect {
+    type Error = ConnectionRefused;
+
+    fn try_transition(&self, _: &Failed) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_fallible_transition_ok() {
+    REFUSE.with(|r| r.set(false));
+
+    let sm = Machine::new(Idle).transition(Connect).unwrap();
+
+    assert_eq!(sm.state(), Connected);
+    assert_eq!(sm.trigger().unwrap(), Connect);
+    assert_eq!(sm.transition(Disconnect).state(), Idle);
+}
+
+#[test]
+fn test_fallible_transition_err() {
+    REFUSE.with(|r| r.set(true));
+
+    let (sm, err) = Machine::new(Idle).transition(Connect).unwrap_err();
+
+    assert_eq!(sm.state(), Failed);
+    assert_eq!(sm.trigger().unwrap(), Connect);
+    assert_eq!(err, ConnectionRefused);
+
+    let sm = sm.transition(Connect).unwrap();
+    assert_eq!(sm.state(), Connected);
+}
+
+#[test]
+fn test_fallible_transition_as_enum() {
+    REFUSE.with(|r| r.set(true));
+
+    match Machine::new(Idle).transition(Connect) {
+        Ok(m) => panic!("unexpected machine: {:?}", m),
+        Err((m, _)) => match m.as_enum() {
+            Variant::FailedByConnect(m) => assert_eq!(m.state(), Failed),
+            variant => panic!("unexpected variant: {:?}", variant),
+        },
+    }
+}
+
+#[test]
+fn test_fallible_transition_dispatch() {
+    REFUSE.with(|r| r.set(true));
+
+    let variant = Machine::new(Idle).as_enum();
+    let variant = variant.dispatch(Connect.into()).unwrap();
+    match &variant {
+        Variant::FailedByConnect(_) => {}
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+
+    match variant.dispatch(Connect.into()).unwrap() {
+        Variant::ConnectedByConnect(_) => {}
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+}
+
+fn connect<M: TryTransition<Connect>>(m: M) -> Result<M::Machine, M::Failed> {
+    m.try_transition(Connect).map_err(|(m, _)| m)
+}
+
+#[test]
+fn test_fallible_try_transition() {
+    REFUSE.with(|r| r.set(true));
+
+    let sm = connect(Machine::new(Idle)).unwrap_err();
+    assert_eq!(sm.state(), Failed);
+
+    let sm = connect(sm).unwrap();
+    assert_eq!(sm.state(), Connected);
+}
@@ -0,0 +1,224 @@
+extern crate sm;
+
+use sm::{sm, Choose, Dispatch, Enter, Event, Fallible, InitialState, State};
+use std::fmt::Debug;
+
+pub trait Item: Debug + Clone + Eq {
+    fn weight(&self) -> u32;
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sword;
+impl Item for Sword {
+    fn weight(&self) -> u32 {
+        3
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Potion(u32);
+impl Item for Potion {
+    fn weight(&self) -> u32 {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Holding<T>(T);
+impl<T: Item> State for Holding<T> {}
+impl<T: Item> InitialState for Holding<T> {}
+impl<T: Item> Enter<Empty, Put<T>> for Holding<T> {
+    fn enter(_: &Empty, event: &Put<T>) -> Self {
+        Holding(event.0.clone())
+    }
+}
+
+impl<T: Item> Enter<Holding<T>, Chest::Open> for Holding<T> {
+    fn enter(state: &Holding<T>, _: &Chest::Open) -> Self {
+        state.clone()
+    }
+}
+
+impl<T: Item> Choose<Holding<T>> for Chest::Open {
+    type Branch = Chest::OpenFromHoldingBranch;
+
+    fn choose(&self, state: &Holding<T>) -> Self::Branch {
+        match state.0.weight() {
+            weight if weight > 3 => Chest::OpenFromHoldingBranch::Holding,
+            _ => Chest::OpenFromHoldingBranch::Opened,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Enter)]
+pub struct Empty;
+impl State for Empty {}
+impl InitialState for Empty {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Put<T>(T);
+impl<T: Item> Event for Put<T> {}
+
+impl<T: Item> Fallible<Empty> for Put<T> {
+    type Error = u32;
+
+    fn try_transition(&self, _: &Empty) -> Result<(), Self::Error> {
+        match self.0.weight() {
+            weight if weight > 5 => Err(weight),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Label<'a>(&'a str);
+impl<'a> State for Label<'a> {}
+impl<'a> InitialState for Label<'a> {}
+
+sm! {
+    Slot<T: Item> {
+        use crate::Item;
+        use crate::Empty;
+        use crate::Holding<T>;
+        use crate::Put<T>;
+
+        InitialStates { Empty, Holding }
+        FinalStates { Broken }
+
+        Put { Empty => Holding }
+        Take { Holding => Empty }
+        Smash { Holding => Broken }
+        Groups { Occupied: Holding }
+        Handler
+    }
+
+    Chest<T: Item> {
+        use crate::Item;
+        use crate::Empty;
+        use crate::Holding<T>;
+        use crate::Put<T>;
+
+        InitialStates { Empty }
+        FinalStates { Broken }
+
+        Put { Empty => Holding ? Broken }
+        Open { Holding => Opened | Holding }
+        AfterTicks(2) { Opened => Broken }
+    }
+
+    Tag<'a> {
+        use crate::Label<'a>;
+
+        InitialStates { Label }
+        FinalStates { Printed }
+
+        Print { Label => Printed }
+    }
+}
+
+fn weight<T: Item>(sm: &Slot::Variant<T>) -> u32 {
+    match sm {
+        Slot::Variant::InitialHolding(m) => m.state().0.weight(),
+        Slot::Variant::HoldingByPut(m) => m.state().0.weight(),
+        _ => 0,
+    }
+}
+
+#[test]
+fn test_generics_transition() {
+    use Slot::*;
+
+    let sm = Machine::new(Empty).put(Put(Sword));
+    assert_eq!(sm.state(), Holding(Sword));
+    assert_eq!(sm.trigger(), Some(Put(Sword)));
+
+    let sm = sm.take();
+    assert_eq!(sm.state(), Empty);
+
+    let sm = Machine::new(Holding(Potion(7)));
+    assert_eq!(weight(&sm.clone().as_enum()), 7);
+    assert_eq!(weight(&sm.take().as_enum()), 0);
+}
+
+#[test]
+fn test_generics_dispatch() {
+    use Slot::*;
+
+    let sm: Variant<Sword> = Machine::new(Empty).as_enum();
+    let sm = sm.dispatch(Put(Sword).into()).unwrap();
+    assert!(sm.is(&Holding(Sword)));
+    assert!(sm.is_in(Group::Occupied));
+
+    let sm = sm.dispatch(Events::Smash(Smash)).unwrap();
+    assert!(sm.is(&Broken));
+
+    let (sm, event) = sm.dispatch(Events::Take(Take)).unwrap_err();
+    assert!(sm.is(&Broken));
+    assert_eq!(event, Events::Take(Take));
+}
+
+#[test]
+fn test_generics_capability() {
+    fn fill<T: Item, M: Slot::CanPut<T>>(sm: M, item: T) -> M::Next {
+        sm.transition(Put(item))
+    }
+
+    let sm = fill(Slot::Machine::new(Slot::Empty), Potion(2));
+    assert_eq!(sm.state(), Holding(Potion(2)));
+}
+
+#[test]
+fn test_generics_handler() {
+    use Slot::*;
+
+    struct Discard;
+
+    impl<T: Item> SlotHandler<T> for Discard {
+        fn on_initial_empty(&mut self, m: Machine<T, Empty, sm::NoneEvent>) -> Variant<T> {
+            m.as_enum()
+        }
+
+        fn on_initial_holding(&mut self, m: Machine<T, Holding<T>, sm::NoneEvent>) -> Variant<T> {
+            m.smash().as_enum()
+        }
+
+        fn on_holding_by_put(&mut self, m: Machine<T, Holding<T>, Put<T>>) -> Variant<T> {
+            m.smash().as_enum()
+        }
+
+        fn on_empty_by_take(&mut self, m: Machine<T, Empty, Take>) -> Variant<T> {
+            m.as_enum()
+        }
+    }
+
+    let sm = Machine::new(Holding(Sword)).as_enum().run(&mut Discard);
+    assert!(sm.is(&Broken));
+}
+
+#[test]
+fn test_generics_fallible_choice_ticks() {
+    use Chest::*;
+
+    let sm = Machine::new(Empty).put(Put(Potion(9))).unwrap_err();
+    assert_eq!(sm.0.state(), Broken);
+    assert_eq!(sm.1, 9);
+
+    let sm = Machine::new(Empty).put(Put(Sword)).unwrap();
+    match sm.open() {
+        OpenFromHolding::Opened(m) => {
+            let sm = m.as_enum().tick().tick();
+            assert!(sm.is(&Broken));
+        }
+        OpenFromHolding::Holding(_) => unreachable!(),
+    }
+}
+
+#[test]
+fn test_generics_lifetime() {
+    use Tag::*;
+
+    let text = String::from("sword");
+    let sm = Machine::new(Label(&text)).print();
+    assert_eq!(sm.state(), Printed);
+    assert!(sm.as_enum().is(&Printed));
+}
@@ -0,0 +1,81 @@
+extern crate sm;
+use sm::{sm, Transition};
+
+sm! {
+    Enemy {
+        InitialStates { Idle }
+
+        Groups {
+            Combat: Attacking, Blocking, Dodging
+            Moving: Walking, Dodging
+        }
+
+        Spot { Idle, Walking => Attacking }
+        Block { Attacking => Blocking }
+        Dodge { Attacking, Blocking => Dodging }
+        Walk { Idle => Walking }
+        Retreat { Combat => Walking }
+        Stun { Combat, Moving => Stunned }
+    }
+}
+
+use crate::Enemy::*;
+
+fn in_combat<M: InCombat>(_: &M) -> bool {
+    true
+}
+
+fn retreat<M: InCombat + CanRetreat>(sm: M) -> <M as Transition<Retreat>>::Machine {
+    sm.transition(Retreat)
+}
+
+#[test]
+fn test_group_marker_trait() {
+    let sm = Machine::new(Idle).transition(Spot);
+    assert!(in_combat(&sm));
+
+    let sm = sm.transition(Block);
+    assert!(in_combat(&sm));
+}
+
+#[test]
+fn test_group_capability_trait() {
+    let sm = retreat(Machine::new(Idle).transition(Spot));
+    assert_eq!(sm.state(), Walking);
+
+    let sm = retreat(Machine::new(Idle).transition(Spot).transition(Dodge));
+    assert_eq!(sm.state(), Walking);
+}
+
+#[test]
+fn test_group_is_in() {
+    let sm = Machine::new(Idle).as_enum();
+    assert!(!sm.is_in(Group::Combat));
+    assert!(!sm.is_in(Group::Moving));
+
+    let sm = Machine::new(Idle)
+        .transition(Spot)
+        .transition(Dodge)
+        .as_enum();
+    assert!(sm.is_in(Group::Combat));
+    assert!(sm.is_in(Group::Moving));
+
+    let sm = Machine::new(Idle).transition(Walk).as_enum();
+    assert!(!sm.is_in(Group::Combat));
+    assert!(sm.is_in(Group::Moving));
+}
+
+#[test]
+fn test_group_source() {
+    let sm = Machine::new(Idle).transition(Spot).transition(Retreat);
+    assert_eq!(sm.state(), Walking);
+
+    let sm = Machine::new(Idle)
+        .transition(Spot)
+        .transition(Block)
+        .transition(Retreat);
+    assert_eq!(sm.state(), Walking);
+
+    let sm = sm.transition(Stun);
+    assert_eq!(sm.state(), Stunned);
+}
@@ -0,0 +1,102 @@
+extern crate sm;
+use sm::{sm, Dispatch};
+
+sm! {
+    Reload {
+        InitialStates { Ejecting }
+        FinalStates { Loaded }
+
+        Insert { Ejecting => Loaded }
+    }
+
+    GameTick {
+        InitialStates { Idle }
+
+        DrainAccumulatedTime { Idle, Updating => Updating }
+        Render { Updating => Rendering }
+        CompletedRendering { Rendering => Finished }
+        Empty { Rendering => Reloading }
+
+        Submachine(Reload: Ejecting) { Reloading => Finished }
+
+        Handler
+    }
+}
+
+use crate::GameTick::*;
+
+#[derive(Default)]
+struct Game {
+    updates: u32,
+    renders: u32,
+    reload: bool,
+}
+
+impl GameTickHandler for Game {
+    fn on_initial_idle(&mut self, m: Machine<Idle, sm::NoneEvent>) -> Variant {
+        m.transition(DrainAccumulatedTime).as_enum()
+    }
+
+    fn on_updating_by_drain_accumulated_time(
+        &mut self,
+        m: Machine<Updating, DrainAccumulatedTime>,
+    ) -> Variant {
+        self.updates += 1;
+
+        if self.updates < 3 {
+            m.transition(DrainAccumulatedTime).as_enum()
+        } else {
+            m.transition(Render).as_enum()
+        }
+    }
+
+    fn on_rendering_by_render(&mut self, m: Machine<Rendering, Render>) -> Variant {
+        self.renders += 1;
+
+        if self.reload {
+            m.transition(Empty).as_enum()
+        } else {
+            m.transition(CompletedRendering).as_enum()
+        }
+    }
+
+    fn on_reloading_by_empty(
+        &mut self,
+        m: Machine<Reloading, Empty>,
+        child: Reload::Variant,
+    ) -> Variant {
+        // The events of the submachine are dispatched through the machine,
+        // which completes the submachine once it reaches its final state.
+        Variant::ReloadingByEmpty(m, child)
+            .dispatch(Events::Reload(Reload::Insert.into()))
+            .unwrap()
+    }
+}
+
+#[test]
+fn test_handler_run() {
+    let mut game = Game::default();
+    let sm = Machine::new(Idle).as_enum().run(&mut game);
+
+    assert!(sm.is(&Finished));
+    assert_eq!(game.updates, 3);
+    assert_eq!(game.renders, 1);
+
+    match sm {
+        Variant::FinishedByCompletedRendering(_) => {}
+        _ => panic!("unexpected variant"),
+    }
+}
+
+#[test]
+fn test_handler_run_submachine() {
+    let mut game = Game {
+        reload: true,
+        ..Game::default()
+    };
+
+    match Machine::new(Idle).as_enum().run(&mut game) {
+        Variant::FinishedByCompleted(_) => {}
+        _ => panic!("unexpected variant"),
+    }
+}
@@ -0,0 +1,85 @@
+/// A facade crate re-exporting `sm`, as seen from the machines below.
+mod engine {
+    pub extern crate sm;
+}
+
+// Types named after the traits of `sm`, which the generated code must neither
+// import into nor depend on being in this scope.
+#[derive(Debug)]
+pub struct AsEnum;
+#[derive(Debug)]
+pub struct Initializer;
+#[derive(Debug)]
+pub struct M;
+#[derive(Debug)]
+pub struct Transition;
+
+engine::sm::sm! {
+    crate = crate::engine::sm;
+
+    Lock {
+        InitialStates { Locked }
+        FinalStates { State }
+
+        TurnKey { Locked => Unlocked  Unlocked => Locked }
+        Break { Locked, Unlocked => State }
+    }
+
+    Door {
+        InitialStates { Closed }
+
+        Event { Closed => Open }
+        Close { Open => Closed }
+    }
+}
+
+#[engine::sm::machine(crate = crate::engine::sm)]
+mod Gate {
+    enum States {
+        #[initial]
+        Shut,
+        #[final_state]
+        Dispatch,
+    }
+
+    enum Events {
+        Raise,
+    }
+
+    transitions! {
+        Raise {
+            Shut => Dispatch
+        }
+    }
+}
+
+#[test]
+fn test_hygiene_transition() {
+    use crate::Lock::*;
+
+    let sm = Machine::new(Locked).transition(TurnKey);
+    assert_eq!(sm.state(), Unlocked);
+    assert_eq!(sm.trigger().unwrap(), TurnKey);
+
+    let sm = sm.transition(Break);
+    assert_eq!(sm.state(), State);
+}
+
+#[test]
+fn test_hygiene_as_enum() {
+    use crate::Door::*;
+
+    match Machine::new(Closed).transition(Event).as_enum() {
+        Variant::OpenByEvent(m) => assert_eq!(m.state(), Open),
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+}
+
+#[test]
+fn test_hygiene_attribute() {
+    use crate::Gate::*;
+
+    let sm = Machine::new(Shut).raise();
+    assert_eq!(sm.state(), Dispatch);
+    assert!(sm.as_enum().is(&Dispatch));
+}
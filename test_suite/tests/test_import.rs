@@ -0,0 +1,142 @@
+extern crate self as game;
+extern crate sm;
+use sm::{sm, Dispatch, Enter, Event, InitialState, State};
+
+mod input {
+    use sm::Event;
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct Jump(pub u32);
+    impl Event for Jump {}
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Standing(pub u32);
+impl State for Standing {}
+impl InitialState for Standing {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Jumping(pub u32);
+impl State for Jumping {}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Enter)]
+pub struct Falling;
+impl State for Falling {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Land;
+impl Event for Land {}
+
+impl Enter<Standing, input::Jump> for Jumping {
+    fn enter(from: &Standing, event: &input::Jump) -> Self {
+        Jumping(from.0 + event.0)
+    }
+}
+
+impl Enter<Falling, Land> for Standing {
+    fn enter(_: &Falling, _: &Land) -> Self {
+        Standing(0)
+    }
+}
+
+impl Enter<Enemy::Chasing, Land> for Standing {
+    fn enter(_: &Enemy::Chasing, _: &Land) -> Self {
+        Standing(0)
+    }
+}
+
+sm! {
+    Player {
+        use input::Jump;
+        use self::Standing;
+        use crate::Jumping as Airborne;
+        use Falling;
+        use Land;
+
+        InitialStates { Standing }
+
+        Jump { Standing => Airborne }
+        Peak { Airborne => Falling }
+        Land { Falling => Standing }
+    }
+
+    Enemy {
+        use Standing;
+        use Land;
+
+        InitialStates { Standing }
+
+        Spot { Standing => Chasing }
+        Land { Chasing => Standing }
+    }
+}
+
+/// Machines importing their states through a crate name, rather than a path
+/// relative to the module they are declared in.
+mod levels {
+    sm::sm! {
+        pub(crate) Climber {
+            use game::Standing;
+            use game::input::Jump;
+
+            InitialStates { Standing }
+
+            Jump { Standing => Climbing }
+        }
+    }
+}
+
+#[test]
+fn test_import_transition() {
+    use crate::Player::*;
+
+    let sm = Machine::new(Standing(10));
+    assert_eq!(sm.state(), Standing(10));
+
+    let sm = sm.transition(input::Jump(5));
+    assert_eq!(sm.state(), Airborne(15));
+    assert_eq!(sm.trigger().unwrap(), input::Jump(5));
+
+    let sm = sm.transition(Peak);
+    assert_eq!(sm.state(), Falling);
+
+    let sm = sm.transition(Land);
+    assert_eq!(sm.state(), Standing(0));
+}
+
+#[test]
+fn test_import_dispatch() {
+    use crate::Player::*;
+
+    let sm = Machine::new(Standing(1)).as_enum();
+    let sm = sm.dispatch(input::Jump(2).into()).unwrap();
+
+    match &sm {
+        Variant::AirborneByJump(m) => assert_eq!(m.state(), Jumping(3)),
+        _ => panic!("unexpected variant"),
+    }
+
+    assert!(sm.is(&Jumping(3)));
+    assert!(!sm.is(&Jumping(4)));
+    assert!(!sm.is(&Falling));
+}
+
+#[test]
+fn test_import_shared() {
+    use crate::Enemy::*;
+
+    let sm = Machine::new(Standing(7)).transition(Spot);
+    assert_eq!(sm.state(), Chasing);
+
+    let sm = sm.transition(Land);
+    assert_eq!(sm.state(), Standing(0));
+}
+
+#[test]
+fn test_import_extern_crate() {
+    use crate::levels::Climber::*;
+
+    let sm = Machine::new(Standing(3)).transition(input::Jump(4));
+    assert_eq!(sm.state(), Climbing);
+    assert_eq!(sm.trigger(), Some(input::Jump(4)));
+}
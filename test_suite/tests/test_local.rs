@@ -0,0 +1,86 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    TurnStile {
+        InitialStates { Locked, Unlocked }
+
+        Coin { Locked => Unlocked }
+        Push { Unlocked => Locked }
+    }
+}
+
+sm! {
+    Lock {
+        InitialStates { Locked, Unlocked }
+
+        TurnKey { Locked => Unlocked  Unlocked => Locked }
+    }
+}
+
+#[test]
+fn test_local_multiple_invocations() {
+    let sm = TurnStile::Machine::new(TurnStile::Locked).transition(TurnStile::Coin);
+    assert_eq!(sm.state(), TurnStile::Unlocked);
+
+    let sm = Lock::Machine::new(Lock::Locked).transition(Lock::TurnKey);
+    assert_eq!(sm.state(), Lock::Unlocked);
+}
+
+#[test]
+fn test_local_function() {
+    sm! {
+        Door {
+            InitialStates { Closed }
+
+            Open { Closed => Opened }
+            Close { Opened => Closed }
+        }
+    }
+
+    use Door::*;
+
+    let sm = Machine::new(Closed).open();
+    assert_eq!(sm.state(), Opened);
+
+    match sm.as_enum() {
+        Variant::OpenedByOpen(m) => assert_eq!(m.close().state(), Closed),
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Knock;
+impl sm::Event for Knock {}
+
+#[test]
+fn test_local_import() {
+    sm! {
+        Door {
+            use crate::Knock;
+
+            InitialStates { Closed }
+
+            Knock { Closed => Opened }
+        }
+    }
+
+    let sm = Door::Machine::new(Door::Closed).transition(Knock);
+    assert_eq!(sm.state(), Door::Opened);
+    assert_eq!(sm.trigger().unwrap(), Knock);
+}
+
+#[test]
+fn test_local_shadowing() {
+    sm! {
+        Lock {
+            InitialStates { Unlocked }
+
+            Break { Unlocked => Broken }
+            Repair { Broken => Unlocked }
+        }
+    }
+
+    let sm = Lock::Machine::new(Lock::Unlocked).transition(Lock::Break);
+    assert_eq!(sm.state(), Lock::Broken);
+}
@@ -0,0 +1,53 @@
+extern crate sm;
+use sm::sm;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Insert(u8);
+impl sm::Event for Insert {}
+
+sm! {
+    VendingMachine {
+        use crate::Insert;
+
+        InitialStates { Idle }
+
+        Insert { Idle => Paid }
+        DrainAccumulatedTime { Paid => Idle }
+        Break { Idle, Paid => Broken }
+        Select { Paid => Dispensing | Idle }
+    }
+}
+
+use crate::VendingMachine::*;
+
+impl sm::Choose<Paid> for Select {
+    type Branch = SelectFromPaidBranch;
+
+    fn choose(&self, _: &Paid) -> Self::Branch {
+        SelectFromPaidBranch::Dispensing
+    }
+}
+
+#[test]
+fn test_method() {
+    let sm = Machine::new(Idle).insert(Insert(2));
+    assert_eq!(sm.state(), Paid);
+    assert_eq!(sm.trigger().unwrap(), Insert(2));
+
+    let sm = sm.drain_accumulated_time();
+    assert_eq!(sm.state(), Idle);
+    assert_eq!(sm.trigger().unwrap(), DrainAccumulatedTime);
+
+    let sm = sm.r#break();
+    assert_eq!(sm.state(), Broken);
+}
+
+#[test]
+fn test_method_choice() {
+    let sm = Machine::new(Idle).insert(Insert(1));
+
+    match sm.select() {
+        SelectFromPaid::Dispensing(m) => assert_eq!(m.state(), Dispensing),
+        SelectFromPaid::Idle(_) => panic!("expected Dispensing"),
+    }
+}
@@ -0,0 +1,66 @@
+extern crate sm;
+use sm::{sm, Dispatch};
+
+sm! {
+    Reload {
+        InitialStates { Ejecting }
+        FinalStates { Loaded }
+        Names { Machine as ReloadMachine, Variant as ReloadVariant, Events as ReloadEvents }
+
+        Eject { Ejecting => Loaded }
+    }
+
+    Factory {
+        InitialStates { Idle }
+        FinalStates { Machine }
+        Names { Machine as Plant, Variant as PlantState }
+
+        Build { Idle => Variant }
+        Restock { Variant => Idle }
+        Finish { Idle => Machine }
+
+        Submachine(Reload: Ejecting) {
+            Variant => Idle
+        }
+    }
+}
+
+use crate::Factory::*;
+
+#[test]
+fn test_names_states() {
+    let factory = Plant::new(Idle).transition(Build);
+
+    assert_eq!(factory.state(), Variant);
+    assert_eq!(
+        factory.transition(Restock).transition(Finish).state(),
+        Machine
+    );
+}
+
+#[test]
+fn test_names_variant() {
+    match Plant::new(Idle).as_enum() {
+        PlantState::InitialIdle(m) => assert_eq!(m.state(), Idle),
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+}
+
+#[test]
+fn test_names_submachine() {
+    let variant = Plant::new(Idle).transition(Build).as_enum();
+    let variant = variant
+        .dispatch(Events::Reload(Reload::Eject.into()))
+        .unwrap();
+
+    match variant {
+        PlantState::IdleByCompleted(m) => assert_eq!(m.state(), Idle),
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+
+    let child = Reload::ReloadMachine::new(Reload::Ejecting).as_enum();
+    match child.dispatch(Reload::ReloadEvents::Eject(Reload::Eject)) {
+        Ok(Reload::ReloadVariant::LoadedByEject(_)) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
@@ -0,0 +1,144 @@
+extern crate sm;
+use sm::{sm, Dispatch};
+
+sm! {
+    Reload {
+        InitialStates { Ejecting }
+        FinalStates { Loaded, Jammed }
+
+        Eject { Ejecting => Inserting }
+        Insert { Inserting => Loaded }
+        Jam { Inserting => Jammed }
+    }
+
+    Weapon {
+        InitialStates { Idle, Reloading }
+
+        Fire { Idle => Idle }
+        Empty { Idle => Reloading }
+        Drop { Idle, Reloading => Dropped }
+
+        Submachine(Reload: Ejecting) {
+            Reloading => Idle
+        }
+    }
+}
+
+use crate::Weapon::*;
+
+#[test]
+fn test_submachine_entry() {
+    let variant = Machine::new(Idle).transition(Empty).as_enum();
+
+    match variant {
+        Variant::ReloadingByEmpty(m, child) => {
+            assert_eq!(m.state(), Reloading);
+            assert!(child.is(&Reload::Ejecting));
+
+            match child {
+                Reload::Variant::InitialEjecting(_) => {}
+                child => panic!("unexpected child variant: {:?}", child),
+            }
+        }
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+}
+
+#[test]
+fn test_submachine_initial_state() {
+    match Machine::new(Reloading).as_enum() {
+        Variant::InitialReloading(_, child) => assert!(child.is(&Reload::Ejecting)),
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+}
+
+#[test]
+fn test_submachine_dispatch() {
+    let variant = Machine::new(Idle).as_enum();
+    let variant = variant.dispatch(Empty.into()).unwrap();
+    let variant = variant
+        .dispatch(Events::Reload(Reload::Eject.into()))
+        .unwrap();
+
+    match &variant {
+        Variant::ReloadingByEmpty(_, child) => assert!(child.is(&Reload::Inserting)),
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+
+    match variant.dispatch(Events::Reload(Reload::Insert.into())) {
+        Ok(Variant::IdleByCompleted(m)) => assert_eq!(m.trigger().unwrap(), Completed),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_submachine_final_states() {
+    let variant = Machine::new(Reloading).as_enum();
+    let variant = variant
+        .dispatch(Events::Reload(Reload::Eject.into()))
+        .unwrap();
+
+    assert!(!variant.is(&Idle));
+
+    match variant.dispatch(Events::Reload(Reload::Jam.into())) {
+        Ok(Variant::IdleByCompleted(m)) => assert_eq!(m.state(), Idle),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_submachine_completed_event() {
+    let variant = Machine::new(Reloading).as_enum();
+
+    // The `Completed` event is only used once the submachine completes, so
+    // dispatching it does not skip the submachine.
+    match variant.dispatch(Completed.into()) {
+        Err((Variant::InitialReloading(_, child), Events::Completed(_))) => {
+            assert!(child.is(&Reload::Ejecting))
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_variant_is_final() {
+    let child = Reload::Machine::new(Reload::Ejecting).as_enum();
+    assert!(!child.is_final());
+
+    let child = child.dispatch(Reload::Eject.into()).unwrap();
+    let child = child.dispatch(Reload::Insert.into()).unwrap();
+    assert!(child.is_final());
+}
+
+#[test]
+fn test_submachine_unhandled_event() {
+    let variant = Machine::new(Reloading).as_enum();
+
+    match variant.dispatch(Events::Reload(Reload::Insert.into())) {
+        Err((Variant::InitialReloading(_, child), Events::Reload(_))) => {
+            assert!(child.is(&Reload::Ejecting))
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_submachine_parent_event() {
+    let variant = Machine::new(Reloading).as_enum();
+    let variant = variant
+        .dispatch(Events::Reload(Reload::Eject.into()))
+        .unwrap();
+
+    match variant.dispatch(Drop.into()) {
+        Ok(Variant::DroppedByDrop(m)) => assert_eq!(m.state(), Dropped),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_variant_is() {
+    let variant = Machine::new(Idle).transition(Fire).as_enum();
+
+    assert!(variant.is(&Idle));
+    assert!(!variant.is(&Reloading));
+}
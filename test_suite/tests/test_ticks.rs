@@ -0,0 +1,86 @@
+extern crate sm;
+use sm::sm;
+
+sm! {
+    Player {
+        InitialStates { Spawning, Vulnerable }
+
+        Hit { Vulnerable => Invulnerable }
+        Heal { Invulnerable => Vulnerable }
+
+        AfterTicks(3) { Invulnerable => Vulnerable }
+        AfterTicks(2) { Spawning => Invulnerable }
+    }
+}
+
+use crate::Player::*;
+
+#[test]
+fn test_ticks_without_timeout() {
+    let mut variant = Machine::new(Vulnerable).as_enum();
+
+    for _ in 0..10 {
+        variant = variant.tick();
+    }
+
+    match variant {
+        Variant::InitialVulnerable(m) => assert_eq!(m.remaining_ticks(), None),
+        _ => panic!("unexpected variant: {:?}", variant),
+    }
+}
+
+#[test]
+fn test_ticks_initial_state() {
+    let machine = Machine::new(Spawning);
+    assert_eq!(machine.remaining_ticks(), Some(2));
+
+    let variant = machine.as_enum().tick();
+    match &variant {
+        Variant::InitialSpawning(m) => assert_eq!(m.remaining_ticks(), Some(1)),
+        _ => panic!("unexpected variant: {:?}", variant),
+    }
+
+    match variant.tick() {
+        Variant::InvulnerableByTickTimeout(m) => assert_eq!(m.remaining_ticks(), Some(3)),
+        variant => panic!("unexpected variant: {:?}", variant),
+    }
+}
+
+#[test]
+fn test_ticks_reset_on_transition() {
+    let machine = Machine::new(Vulnerable).transition(Hit);
+    assert_eq!(machine.remaining_ticks(), Some(3));
+
+    let mut variant = machine.as_enum().tick().tick();
+    match variant {
+        Variant::InvulnerableByHit(m) => {
+            assert_eq!(m.remaining_ticks(), Some(1));
+
+            let machine = m.transition(Heal).transition(Hit);
+            assert_eq!(machine.remaining_ticks(), Some(3));
+            variant = machine.as_enum();
+        }
+        _ => panic!("unexpected variant: {:?}", variant),
+    }
+
+    for _ in 0..3 {
+        variant = variant.tick();
+    }
+
+    match variant {
+        Variant::VulnerableByTickTimeout(m) => assert_eq!(m.trigger().unwrap(), TickTimeout),
+        _ => panic!("unexpected variant: {:?}", variant),
+    }
+}
+
+#[test]
+fn test_ticks_dispatch_timeout_event() {
+    use sm::Dispatch;
+
+    let variant = Machine::new(Vulnerable).transition(Hit).as_enum();
+
+    match variant.dispatch(TickTimeout.into()) {
+        Ok(Variant::VulnerableByTickTimeout(m)) => assert_eq!(m.remaining_ticks(), None),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
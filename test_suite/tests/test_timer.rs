@@ -0,0 +1,103 @@
+extern crate sm;
+use sm::{
+    sm,
+    timer::{Clock, ManualClock, Timer},
+};
+use std::time::Duration;
+
+sm! {
+    Enemy {
+        InitialStates { Idle }
+
+        Hit { Idle, Stunned => Stunned }
+        Heal { Stunned => Idle }
+
+        After(3s) { Stunned => Dazed }
+        After(500ms) { Dazed => Idle }
+    }
+}
+
+use crate::Enemy::*;
+
+#[test]
+fn test_timer_without_timeout() {
+    let clock = ManualClock::new();
+    let mut timer = Timer::new(Machine::new(Idle).as_enum(), &clock);
+
+    clock.advance(Duration::from_secs(60));
+
+    assert_eq!(timer.remaining(), None);
+    assert!(!timer.update());
+}
+
+#[test]
+fn test_timer_timeout() {
+    let clock = ManualClock::new();
+    let mut timer = Timer::new(Machine::new(Idle).as_enum(), &clock);
+
+    timer.dispatch(Hit).unwrap();
+    assert_eq!(timer.remaining(), Some(Duration::from_secs(3)));
+
+    clock.advance(Duration::from_millis(2_999));
+    assert!(!timer.update());
+    assert_eq!(timer.remaining(), Some(Duration::from_millis(1)));
+
+    clock.advance(Duration::from_millis(1));
+    assert!(timer.update());
+    match timer.machine() {
+        Variant::DazedByTimeout(_) => {}
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_timer_restarts_on_transition() {
+    let clock = ManualClock::new();
+    let mut timer = Timer::new(Machine::new(Idle).as_enum(), &clock);
+
+    timer.dispatch(Hit).unwrap();
+    clock.advance(Duration::from_secs(2));
+
+    // Re-entering the state restarts its timeout.
+    timer.dispatch(Hit).unwrap();
+    clock.advance(Duration::from_secs(2));
+    assert!(!timer.update());
+
+    timer.dispatch(Heal).unwrap();
+    clock.advance(Duration::from_secs(2));
+    assert!(!timer.update());
+    match timer.machine() {
+        Variant::IdleByHeal(_) => {}
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_timer_chained_timeouts_do_not_drift() {
+    let clock = ManualClock::new();
+    let mut timer = Timer::new(Machine::new(Idle).as_enum(), &clock);
+
+    timer.dispatch(Hit).unwrap();
+    clock.advance(Duration::from_millis(3_400));
+
+    assert!(timer.update());
+    assert_eq!(timer.remaining(), Some(Duration::from_millis(100)));
+
+    clock.advance(Duration::from_millis(100));
+    assert!(timer.update());
+    assert!(!timer.update());
+    match timer.machine() {
+        Variant::IdleByTimeout(_) => {}
+        _ => unreachable!(),
+    }
+
+    assert_eq!(timer.clock().now(), Duration::from_millis(3_500));
+}
+
+#[test]
+fn test_timer_unhandled_event() {
+    let clock = ManualClock::new();
+    let mut timer = Timer::new(Machine::new(Idle).as_enum(), &clock);
+
+    assert_eq!(timer.dispatch(Heal), Err(Events::Heal(Heal)));
+}
@@ -0,0 +1,72 @@
+extern crate sm;
+
+mod engine {
+    use sm::sm;
+
+    sm! {
+        pub Lock {
+            InitialStates { Locked }
+
+            TurnKey { Locked => Unlocked  Unlocked => Locked }
+            pub(crate) Reset { Locked, Unlocked => Locked }
+        }
+
+        pub(crate) Door {
+            InitialStates { Closed }
+            FinalStates { Opened }
+
+            pub(super) Open { Closed => Opened }
+        }
+    }
+
+    /// A gate, declared as a public module.
+    #[sm::machine]
+    pub mod Gate {
+        enum States {
+            #[initial]
+            Shut,
+            #[final_state]
+            Raised,
+        }
+
+        enum Events {
+            Raise,
+        }
+
+        transitions! {
+            Raise {
+                Shut => Raised
+            }
+        }
+    }
+}
+
+use sm::Dispatch;
+
+#[test]
+fn test_visibility_event() {
+    use crate::engine::Lock::*;
+
+    let sm = Machine::new(Locked).turn_key().reset();
+    assert_eq!(sm.state(), Locked);
+    assert_eq!(sm.trigger().unwrap(), Reset);
+
+    let sm = sm.as_enum().dispatch(Events::Reset(Reset)).unwrap();
+    assert!(sm.is(&Locked));
+}
+
+#[test]
+fn test_visibility_machine() {
+    use crate::engine::Door::*;
+
+    let sm = Machine::new(Closed).open();
+    assert_eq!(sm.state(), Opened);
+}
+
+#[test]
+fn test_visibility_attribute() {
+    use crate::engine::Gate::*;
+
+    let sm = Machine::new(Shut).transition(Raise);
+    assert_eq!(sm.state(), Raised);
+}